@@ -0,0 +1,8 @@
+-- Full-text search index over link title and description
+-- Version: 20250726000000
+
+CREATE INDEX IF NOT EXISTS idx_links_search ON links USING gin (
+    to_tsvector('english', COALESCE(title, '') || ' ' || COALESCE(description, ''))
+);
+
+COMMENT ON INDEX idx_links_search IS 'Backs full-text search over link title and description';
@@ -0,0 +1,8 @@
+-- Soft-delete support for links
+-- Version: 20250726000001
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_links_deleted_at ON links(deleted_at) WHERE deleted_at IS NOT NULL;
+
+COMMENT ON COLUMN links.deleted_at IS 'Set when a link is soft-deleted; NULL for live links';
@@ -0,0 +1,10 @@
+-- Keep the URL exactly as the user submitted it, alongside the normalized one
+-- Version: 20250726000002
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS original_url TEXT;
+
+UPDATE links SET original_url = url WHERE original_url IS NULL;
+
+ALTER TABLE links ALTER COLUMN original_url SET NOT NULL;
+
+COMMENT ON COLUMN links.original_url IS 'URL as submitted by the user; links.url holds the normalized form';
@@ -0,0 +1,15 @@
+-- Individual click events for link analytics
+-- Version: 20250726000003
+
+CREATE TABLE IF NOT EXISTS link_clicks (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    link_id UUID NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    clicked_at TIMESTAMPTZ NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    referrer TEXT,
+    user_agent TEXT,
+    ip_hash VARCHAR(64)
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_clicks_link_id_clicked_at ON link_clicks(link_id, clicked_at DESC);
+
+COMMENT ON TABLE link_clicks IS 'One row per tracked click; ip_hash is a salted SHA-256 of the client IP';
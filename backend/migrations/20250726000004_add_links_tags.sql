@@ -0,0 +1,8 @@
+-- Free-form tags for organizing links
+-- Version: 20250726000004
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_links_tags ON links USING gin (tags);
+
+COMMENT ON COLUMN links.tags IS 'Lowercased, deduplicated tags (max 10, 30 chars each)';
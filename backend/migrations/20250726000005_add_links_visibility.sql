@@ -0,0 +1,12 @@
+-- Public/private visibility for links
+-- Version: 20250726000005
+
+CREATE TYPE link_visibility AS ENUM ('public', 'private');
+
+-- Existing links were world-readable, so backfill them as public
+ALTER TABLE links ADD COLUMN IF NOT EXISTS visibility link_visibility NOT NULL DEFAULT 'public';
+ALTER TABLE links ALTER COLUMN visibility SET DEFAULT 'private';
+
+CREATE INDEX IF NOT EXISTS idx_links_visibility ON links(visibility);
+
+COMMENT ON COLUMN links.visibility IS 'Public links are listed for everyone; private links only for their owner';
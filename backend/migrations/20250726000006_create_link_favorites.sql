@@ -0,0 +1,11 @@
+-- Links starred by users, regardless of who owns them
+-- Version: 20250726000006
+
+CREATE TABLE IF NOT EXISTS link_favorites (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    link_id UUID NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    PRIMARY KEY (user_id, link_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_favorites_link_id ON link_favorites(link_id);
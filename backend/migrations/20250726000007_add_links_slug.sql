@@ -0,0 +1,29 @@
+-- Short, shareable codes used by the /s/{slug} redirect
+-- Version: 20250726000007
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS slug VARCHAR(32);
+
+-- Give existing links a random 7-character base62 slug
+DO $$
+DECLARE
+    link RECORD;
+BEGIN
+    FOR link IN SELECT id FROM links WHERE slug IS NULL LOOP
+        UPDATE links
+        SET slug = (
+            SELECT string_agg(
+                substr(
+                    '0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz',
+                    floor(random() * 62)::int + 1,
+                    1
+                ),
+                ''
+            )
+            FROM generate_series(1, 7)
+        )
+        WHERE id = link.id;
+    END LOOP;
+END $$;
+
+ALTER TABLE links ALTER COLUMN slug SET NOT NULL;
+ALTER TABLE links ADD CONSTRAINT links_slug_key UNIQUE (slug);
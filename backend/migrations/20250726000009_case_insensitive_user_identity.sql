@@ -0,0 +1,76 @@
+-- Treat emails and usernames as case-insensitive when checking for duplicates
+-- Version: 20250726000009
+
+-- The old case-sensitive unique indexes would reject lowercasing an email that collides
+-- with another account's, so they go before any data changes
+DROP INDEX IF EXISTS idx_users_username;
+DROP INDEX IF EXISTS idx_users_verified_email;
+DROP INDEX IF EXISTS idx_users_email;
+
+-- Usernames that differ only in case can't all stay. The oldest account keeps its name;
+-- the others get the start of their id appended (e.g. alice_1a2b3c4d), which still
+-- matches chk_username_format and fits in 50 characters.
+DO $$
+DECLARE
+    renamed RECORD;
+BEGIN
+    FOR renamed IN
+        WITH ranked AS (
+            SELECT
+                id,
+                username,
+                ROW_NUMBER() OVER (PARTITION BY LOWER(username) ORDER BY created_at, id) AS position
+            FROM users
+        )
+        UPDATE users u
+        SET username = LEFT(r.username, 41) || '_' || LEFT(REPLACE(u.id::text, '-', ''), 8)
+        FROM ranked r
+        WHERE u.id = r.id AND r.position > 1
+        RETURNING u.id, r.username AS old_username, u.username AS new_username
+    LOOP
+        RAISE WARNING 'Renamed user % from % to % because another account has the same username in a different case',
+            renamed.id, renamed.old_username, renamed.new_username;
+    END LOOP;
+END $$;
+
+-- Only one verified account may own an email. The first to verify keeps it; the others
+-- go back to pending verification and are reported so they can be merged by hand.
+DO $$
+DECLARE
+    demoted RECORD;
+BEGIN
+    FOR demoted IN
+        WITH ranked AS (
+            SELECT
+                id,
+                ROW_NUMBER() OVER (
+                    PARTITION BY LOWER(email)
+                    ORDER BY verified_at NULLS LAST, created_at, id
+                ) AS position
+            FROM users
+            WHERE is_verified = true
+        )
+        UPDATE users u
+        SET is_verified = false,
+            verified_at = NULL,
+            status = 'pending_verification'
+        FROM ranked r
+        WHERE u.id = r.id AND r.position > 1
+        RETURNING u.id, u.email
+    LOOP
+        RAISE WARNING 'Unverified user % because another verified account uses % in a different case',
+            demoted.id, demoted.email;
+    END LOOP;
+END $$;
+
+-- Store emails in their canonical lowercase form
+UPDATE users
+SET email = LOWER(email)
+WHERE email <> LOWER(email);
+
+CREATE UNIQUE INDEX idx_users_username_lower ON users(LOWER(username));
+CREATE UNIQUE INDEX idx_users_verified_email_lower ON users(LOWER(email)) WHERE is_verified = true;
+CREATE INDEX idx_users_email_lower ON users(LOWER(email));
+
+COMMENT ON INDEX idx_users_username_lower IS 'Usernames keep their display case but are unique regardless of case';
+COMMENT ON INDEX idx_users_verified_email_lower IS 'Ensures case-insensitive email uniqueness only for verified users';
@@ -0,0 +1,16 @@
+-- Track when each link was last clicked, separately from its click count
+-- Version: 20250726000011
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS last_clicked_at TIMESTAMPTZ;
+
+-- Backfill from recorded click events
+UPDATE links l
+SET last_clicked_at = c.last_clicked_at
+FROM (
+    SELECT link_id, MAX(clicked_at) AS last_clicked_at
+    FROM link_clicks
+    GROUP BY link_id
+) c
+WHERE c.link_id = l.id;
+
+CREATE INDEX IF NOT EXISTS idx_links_last_clicked_at ON links(last_clicked_at DESC NULLS LAST);
@@ -0,0 +1,8 @@
+-- Add a role to users so operators can be granted admin access
+-- Version: 20250726000012
+
+CREATE TYPE user_role AS ENUM ('user', 'admin');
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role user_role NOT NULL DEFAULT 'user';
+
+CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at);
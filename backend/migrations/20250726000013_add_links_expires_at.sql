@@ -0,0 +1,9 @@
+-- Optional expiry for temporary links; expired links are soft-deleted by a background task
+-- Version: 20250726000013
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_links_expires_at ON links(expires_at)
+    WHERE expires_at IS NOT NULL AND deleted_at IS NULL;
+
+COMMENT ON COLUMN links.expires_at IS 'When set, the link is hidden after this time and soft-deleted shortly after';
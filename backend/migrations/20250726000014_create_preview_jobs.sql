@@ -0,0 +1,17 @@
+-- Durable queue of link preview fetches, processed by a background worker
+-- Version: 20250726000014
+
+CREATE TABLE IF NOT EXISTS preview_jobs (
+    link_id UUID PRIMARY KEY REFERENCES links(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'running', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    run_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_preview_jobs_pending ON preview_jobs(run_after)
+    WHERE status = 'pending';
+
+COMMENT ON TABLE preview_jobs IS 'One job per link whose preview still has to be fetched; finished jobs are deleted';
@@ -0,0 +1,17 @@
+-- Reachability of each link's target, maintained by a background checker
+-- Version: 20250726000015
+
+CREATE TYPE link_health AS ENUM ('alive', 'dead', 'unknown');
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS health link_health NOT NULL DEFAULT 'unknown';
+ALTER TABLE links ADD COLUMN IF NOT EXISTS last_checked_at TIMESTAMPTZ;
+ALTER TABLE links ADD COLUMN IF NOT EXISTS health_failures INTEGER NOT NULL DEFAULT 0;
+
+-- The checker picks never-checked links first, then the least recently checked
+CREATE INDEX IF NOT EXISTS idx_links_last_checked_at ON links(last_checked_at NULLS FIRST)
+    WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_links_user_id_health ON links(user_id, health)
+    WHERE deleted_at IS NULL;
+
+COMMENT ON COLUMN links.health IS 'alive after a successful check, dead after several consecutive failed checks';
+COMMENT ON COLUMN links.health_failures IS 'Consecutive failed checks since the target last responded';
@@ -0,0 +1,15 @@
+-- Anonymous links: created without an owner and claimed later with a one-time token
+-- Version: 20250726000016
+
+ALTER TABLE links ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS claim_token_hash TEXT;
+
+-- Every link has either an owner or a way for someone to become its owner
+ALTER TABLE links ADD CONSTRAINT links_owner_or_claim_token
+    CHECK (user_id IS NOT NULL OR claim_token_hash IS NOT NULL);
+
+CREATE INDEX IF NOT EXISTS idx_links_unclaimed ON links(expires_at)
+    WHERE user_id IS NULL;
+
+COMMENT ON COLUMN links.claim_token_hash IS 'SHA-256 of the one-time token that assigns an anonymous link to the user presenting it';
@@ -0,0 +1,7 @@
+-- Cap on how many active links each user can have, raised per user by administrators
+-- Version: 20250726000017
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS link_quota INTEGER NOT NULL DEFAULT 1000
+    CHECK (link_quota >= 0);
+
+COMMENT ON COLUMN users.link_quota IS 'Maximum number of non-deleted, unexpired links the user can own';
@@ -0,0 +1,26 @@
+-- Track preview generation so clients know when to stop polling for a preview
+-- Version: 20250726000018
+
+CREATE TYPE preview_status AS ENUM ('pending', 'ready', 'failed', 'skipped');
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS preview_status preview_status NOT NULL DEFAULT 'pending';
+
+-- Links with a preview are done, queued or abandoned fetches keep their state, and
+-- anything else predates the preview queue and will never get a preview on its own.
+-- The backfill isn't an edit, so it must not bump updated_at.
+ALTER TABLE links DISABLE TRIGGER update_links_updated_at;
+
+UPDATE links l
+SET preview_status = CASE
+    WHEN l.preview <> 'null'::jsonb THEN 'ready'::preview_status
+    WHEN j.status = 'failed' THEN 'failed'::preview_status
+    WHEN j.status IS NOT NULL THEN 'pending'::preview_status
+    ELSE 'skipped'::preview_status
+END
+FROM links target
+LEFT JOIN preview_jobs j ON j.link_id = target.id
+WHERE target.id = l.id;
+
+ALTER TABLE links ENABLE TRIGGER update_links_updated_at;
+
+COMMENT ON COLUMN links.preview_status IS 'Whether the preview is still being fetched, was stored, failed for good, or was never requested';
@@ -0,0 +1,18 @@
+-- Collections (folders) that group a user's links
+-- Version: 20250726000019
+
+CREATE TABLE IF NOT EXISTS collections (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Names are unique per user, ignoring case
+CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_user_id_name ON collections(user_id, lower(name));
+
+-- Deleting a collection keeps its links, they just leave the collection
+ALTER TABLE links ADD COLUMN IF NOT EXISTS collection_id UUID REFERENCES collections(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_links_collection_id ON links(collection_id)
+    WHERE collection_id IS NOT NULL;
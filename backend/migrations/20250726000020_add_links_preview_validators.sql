@@ -0,0 +1,6 @@
+-- Keep the ETag and Last-Modified a page was served with so preview refreshes can be conditional
+-- Version: 20250726000020
+
+ALTER TABLE links
+    ADD COLUMN IF NOT EXISTS preview_etag TEXT,
+    ADD COLUMN IF NOT EXISTS preview_last_modified TEXT;
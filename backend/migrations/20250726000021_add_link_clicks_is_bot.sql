@@ -0,0 +1,5 @@
+-- Flag clicks from crawlers and link-preview bots so they can be left out of click counts
+-- Version: 20250726000021
+
+ALTER TABLE link_clicks
+    ADD COLUMN IF NOT EXISTS is_bot BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,4 @@
+-- Let sync clients page through a user's links in the order they changed
+-- Version: 20250726000022
+
+CREATE INDEX IF NOT EXISTS idx_links_user_id_updated_at ON links (user_id, updated_at, id);
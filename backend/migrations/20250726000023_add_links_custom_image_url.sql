@@ -0,0 +1,4 @@
+-- Add an owner-chosen image that is shown instead of the preview's image
+-- Version: 20250726000023
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS custom_image_url TEXT;
@@ -0,0 +1,22 @@
+-- Trail of who changed or removed what, written in the same transaction as the change
+-- Version: 20250726000025
+
+CREATE TYPE audit_action AS ENUM ('link_updated', 'link_deleted', 'link_transferred');
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    -- No foreign key, so entries outlive the accounts that made them
+    actor_id UUID NOT NULL,
+    action audit_action NOT NULL,
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id UUID NOT NULL,
+    metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor_id ON audit_log(actor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log(action, created_at DESC);
+
+COMMENT ON COLUMN audit_log.entity_type IS 'Kind of record entity_id points to, such as link';
+COMMENT ON COLUMN audit_log.metadata IS 'Action-specific details, such as the fields an update changed';
@@ -0,0 +1,15 @@
+-- Weighted full-text search: title matches rank above description matches
+-- Version: 20250726000026
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', COALESCE(title, '')), 'A')
+        || setweight(to_tsvector('english', COALESCE(description, '')), 'B')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_links_search_vector ON links USING gin (search_vector);
+
+-- Replaced by the index on search_vector
+DROP INDEX IF EXISTS idx_links_search;
+
+COMMENT ON COLUMN links.search_vector IS 'Title lexemes weighted A, description lexemes weighted B';
@@ -0,0 +1,15 @@
+-- Downscaled copies of each link's preview image, served by the preview image proxy
+-- Version: 20250726000027
+
+CREATE TYPE thumbnail_variant AS ENUM ('small', 'large');
+
+CREATE TABLE IF NOT EXISTS link_thumbnails (
+    link_id UUID NOT NULL REFERENCES links(id) ON DELETE CASCADE,
+    variant thumbnail_variant NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    data BYTEA NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    PRIMARY KEY (link_id, variant)
+);
+
+COMMENT ON TABLE link_thumbnails IS 'Only variants narrower than the source image are stored; the proxy serves the original otherwise';
@@ -0,0 +1,4 @@
+-- Let owners turn off click tracking for individual links
+-- Version: 20250726000028
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS track_clicks BOOLEAN NOT NULL DEFAULT TRUE;
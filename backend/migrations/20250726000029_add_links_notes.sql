@@ -0,0 +1,4 @@
+-- Add private notes that only the link's owner can see
+-- Version: 20250726000029
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS notes TEXT;
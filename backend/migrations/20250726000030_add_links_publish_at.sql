@@ -0,0 +1,4 @@
+-- Add scheduled publishing; links stay hidden from everyone but their owner until publish_at
+-- Version: 20250726000030
+
+ALTER TABLE links ADD COLUMN IF NOT EXISTS publish_at TIMESTAMPTZ;
@@ -0,0 +1,8 @@
+-- Record when a preview job was claimed so only abandoned jobs are put back in the queue
+-- Version: 20250726000031
+
+ALTER TABLE preview_jobs ADD COLUMN IF NOT EXISTS started_at TIMESTAMPTZ;
+
+UPDATE preview_jobs SET started_at = updated_at WHERE status = 'running';
+
+COMMENT ON COLUMN preview_jobs.started_at IS 'When the running attempt was claimed; a job running longer than the lease is considered abandoned';
@@ -0,0 +1,107 @@
+use crate::api::models::UpdateLinkQuotaRequest;
+use crate::api::{ApiResponse, ErrorResponse};
+use crate::database::models::{AuditAction, AuditLogEntry, LinkQuota};
+use crate::models::auth::UserSummary;
+use crate::routes::admin::{DeletedUser, PoolStats};
+
+/// Admin Endpoints
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    params(
+        ("page" = Option<u32>, Query, description = "1-based page number, defaults to 1"),
+        ("page_size" = Option<u32>, Query, description = "Users per page, defaults to 50 and is capped at 200")
+    ),
+    responses(
+        (status = 200, description = "One page of users with pagination details", body = ApiResponse<Vec<UserSummary>>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Caller is not an administrator", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+pub fn list_users_docs() {}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}",
+    params(
+        ("id" = Uuid, Path, description = "ID of the user to delete")
+    ),
+    responses(
+        (status = 200, description = "User deleted along with their links", body = ApiResponse<DeletedUser>),
+        (status = 400, description = "Administrators can't delete themselves", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Caller is not an administrator", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+pub fn delete_user_docs() {}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{id}/quota",
+    params(
+        ("id" = Uuid, Path, description = "ID of the user whose quota changes")
+    ),
+    request_body = UpdateLinkQuotaRequest,
+    responses(
+        (status = 200, description = "Quota updated, with the user's current number of active links", body = ApiResponse<LinkQuota>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Caller is not an administrator", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 422, description = "Negative quota", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+pub fn update_link_quota_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/pool-stats",
+    responses(
+        (status = 200, description = "Open, idle and checked-out database connections", body = ApiResponse<PoolStats>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Caller is not an administrator", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+pub fn pool_stats_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/audit",
+    params(
+        ("actor_id" = Option<Uuid>, Query, description = "Only include changes made by this user"),
+        ("action" = Option<AuditAction>, Query, description = "Only include changes of this kind"),
+        ("page" = Option<u32>, Query, description = "1-based page number, defaults to 1"),
+        ("page_size" = Option<u32>, Query, description = "Entries per page, defaults to 50 and is capped at 200")
+    ),
+    responses(
+        (status = 200, description = "One page of audit log entries, newest first, with pagination details", body = ApiResponse<Vec<AuditLogEntry>>),
+        (status = 400, description = "Unknown action or malformed actor ID", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Caller is not an administrator", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "admin"
+)]
+pub fn list_audit_log_docs() {}
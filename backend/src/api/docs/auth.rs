@@ -1,8 +1,8 @@
 use crate::api::models::{ResendOtpRequest, VerifyEmailRequest};
 use crate::api::{ApiResponse, ErrorResponse};
-use crate::models::auth::{AuthResponse, LoginRequest, RegisterRequest};
+use crate::models::auth::{AuthResponse, LoginRequest, RefreshRequest, RegisterRequest};
+
 type EmptyResponse = ApiResponse<()>;
-type AuthResponseWrapper = ApiResponse<AuthResponse>;
 
 #[utoipa::path(
     post,
@@ -51,16 +51,27 @@ pub fn resend_otp_docs() {}
     path = "/api/auth/login",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "Login successful", body = AuthResponseWrapper),
-        (status = 400, description = "Invalid credentials", body = ErrorResponse),
-        (status = 401, description = "Email not verified", body = ErrorResponse),
-        (status = 403, description = "Account not active", body = ErrorResponse),
+        (status = 200, description = "Login successful", body = ApiResponse<AuthResponse>),
+        (status = 401, description = "Invalid email or password, email not verified, or account not active (LOGIN_ERROR)", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     tag = "auth"
 )]
 pub fn login_docs() {}
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New access token and a replacement refresh token; the presented refresh token no longer works", body = ApiResponse<AuthResponse>),
+        (status = 401, description = "Refresh token invalid or revoked (INVALID_REFRESH_TOKEN), expired (REFRESH_TOKEN_EXPIRED), already used, which revokes every token from the same login (REFRESH_TOKEN_REUSED), or account not active (ACCOUNT_INACTIVE)", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    ),
+    tag = "auth"
+)]
+pub fn refresh_docs() {}
+
 #[utoipa::path(
     post,
     path = "/api/auth/admin/reset-otp-attempts",
@@ -0,0 +1,77 @@
+use crate::api::models::{CreateCollectionRequest, ValidationErrorResponse};
+use crate::api::{ApiResponse, ErrorResponse};
+use crate::database::models::{Collection, Link};
+
+type EmptyResponse = ApiResponse<()>;
+
+/// Collection Endpoints
+#[utoipa::path(
+    post,
+    path = "/api/collections",
+    request_body = CreateCollectionRequest,
+    responses(
+        (status = 201, description = "Collection created", body = ApiResponse<Collection>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 409, description = "A collection with this name already exists (COLLECTION_EXISTS)", body = ErrorResponse),
+        (status = 422, description = "Invalid name", body = ValidationErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "collections"
+)]
+pub fn create_collection_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/collections",
+    responses(
+        (status = 200, description = "The user's collections with their link counts", body = ApiResponse<Vec<Collection>>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "collections"
+)]
+pub fn list_collections_docs() {}
+
+#[utoipa::path(
+    delete,
+    path = "/api/collections/{id}",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Collection ID")
+    ),
+    responses(
+        (status = 200, description = "Collection deleted; its links are kept", body = EmptyResponse),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "collections"
+)]
+pub fn delete_collection_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/collections/{id}/links",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Collection ID")
+    ),
+    responses(
+        (status = 200, description = "Active links in the collection, newest first", body = ApiResponse<Vec<Link>>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "collections"
+)]
+pub fn get_collection_links_docs() {}
@@ -0,0 +1,18 @@
+use crate::api::{ApiResponse, ErrorResponse};
+use crate::routes::dashboard::Dashboard;
+
+/// Dashboard Endpoints
+#[utoipa::path(
+    get,
+    path = "/api/dashboard",
+    responses(
+        (status = 200, description = "Link and click totals, the 5 most clicked and 5 newest links, and clicks in the last 7 days", body = ApiResponse<Dashboard>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "dashboard"
+)]
+pub fn get_dashboard_docs() {}
@@ -12,6 +12,18 @@ use serde_json::Value;
 )]
 pub fn root_docs() {}
 
+/// Readiness probe endpoint
+#[utoipa::path(
+    get,
+    path = "/ready",
+    responses(
+        (status = 200, description = "Service is ready to accept traffic", body = ApiResponse<Value>),
+        (status = 503, description = "Database is unavailable", body = ErrorResponse)
+    ),
+    tag = "health"
+)]
+pub fn ready_docs() {}
+
 /// Admin database health check endpoint
 #[utoipa::path(
     get,
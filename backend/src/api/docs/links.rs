@@ -1,18 +1,30 @@
-use crate::api::models::CreateLinkRequest;
+use crate::api::models::{
+    ClaimLinkRequest, CreateLinkRequest, PaginatedResponse, TransferLinkRequest, UndoDeleteRequest,
+    UpdateLinkRequest,
+};
 use crate::api::{ApiResponse, ErrorResponse};
-use crate::database::models::Link;
-
-type EmptyResponse = ApiResponse<()>;
-type LinkResponse = ApiResponse<Link>;
-type LinksResponse = ApiResponse<Vec<Link>>;
+use crate::database::models::{LeaderboardEntry, Link, LinkStats, SyncedLink, TagCount};
+use crate::routes::links::{
+    AnonymousLink, ClickEventsPage, DeletedLink, LinkStatus, RenderedLink, TrackedClick,
+};
 
 /// Link Management Endpoints
 #[utoipa::path(
     get,
     path = "/api/links",
+    params(
+        ("tag" = Option<Vec<String>>, Query, description = "Only return links carrying this tag, case-insensitive; repeat to require several tags"),
+        ("created_after" = Option<String>, Query, description = "Only return links created at or after this RFC3339 timestamp"),
+        ("created_before" = Option<String>, Query, description = "Only return links created at or before this RFC3339 timestamp"),
+        ("sort" = Option<String>, Query, description = "Sort order: created_asc, created_desc (default), clicks_desc, title_asc or recently_clicked"),
+        ("health" = Option<String>, Query, description = "Only return the caller's own links in this health state: alive, dead or unknown"),
+        ("limit" = Option<i64>, Query, description = "Page size, 1 to 200 (default 50); without it or cursor every matching link is returned"),
+        ("cursor" = Option<String>, Query, description = "next_cursor from the previous page, or a target from the Link header")
+    ),
     responses(
-        (status = 200, description = "Links retrieved successfully", body = LinksResponse),
-        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 200, description = "Links retrieved successfully; when paging, a `Link` header carries rel=\"next\" and rel=\"prev\" URLs", body = PaginatedResponse<Link>),
+        (status = 401, description = "Invalid JWT token, or `health` used without one", body = ErrorResponse),
+        (status = 422, description = "Invalid timestamp filter, sort order, health or cursor", body = ErrorResponse),
         (status = 500, description = "Server error", body = ErrorResponse)
     ),
     security(
@@ -26,9 +38,19 @@ pub fn get_links_docs() {}
     post,
     path = "/api/links",
     request_body = CreateLinkRequest,
+    params(
+        ("allow_duplicate" = Option<bool>, Query, description = "Save the link even if the URL was already saved"),
+        ("verify" = Option<bool>, Query, description = "Send a quick HEAD request first and refuse URLs that clearly don't exist (URL_UNREACHABLE); off by default"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-generated key; retries with the same key return the original link for 24 hours")
+    ),
     responses(
-        (status = 201, description = "Link created successfully", body = LinkResponse),
-        (status = 422, description = "Invalid request data (URL format, title/description length)", body = ErrorResponse),
+        (status = 200, description = "Link already created with this idempotency key", body = ApiResponse<Link>),
+        (status = 201, description = "Link created successfully", body = ApiResponse<Link>),
+        (status = 400, description = "Malformed JSON body or Idempotency-Key header", body = ErrorResponse),
+        (status = 403, description = "Active link quota reached; details carry current and limit", body = ErrorResponse),
+        (status = 409, description = "URL already saved by this user, slug already in use, or idempotency key reused with a different request", body = ErrorResponse),
+        (status = 429, description = "Link creation rate limit exceeded", body = ErrorResponse),
+        (status = 422, description = "Invalid request data (URL format, title/description length), a collection that isn't yours (INVALID_COLLECTION), or with verify=true a URL that answered 404/410 or whose host doesn't resolve (URL_UNREACHABLE)", body = ErrorResponse),
         (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
         (status = 500, description = "Server error", body = ErrorResponse)
     ),
@@ -39,6 +61,115 @@ pub fn get_links_docs() {}
 )]
 pub fn create_link_docs() {}
 
+#[utoipa::path(
+    post,
+    path = "/api/links/anonymous",
+    request_body = CreateLinkRequest,
+    responses(
+        (status = 201, description = "Public link created without an owner, with a one-time claim token; it is deleted after 24 hours unless claimed", body = ApiResponse<AnonymousLink>),
+        (status = 400, description = "Malformed JSON body", body = ErrorResponse),
+        (status = 409, description = "Slug already in use", body = ErrorResponse),
+        (status = 422, description = "Invalid request data, or expires_at or publish_at given", body = ErrorResponse),
+        (status = 429, description = "Too many links created from this IP", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(()),
+    tag = "links"
+)]
+pub fn create_anonymous_link_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/search",
+    params(
+        ("q" = String, Query, description = "Words to search for in link titles and descriptions; each matches as a prefix"),
+        ("fields" = Option<String>, Query, description = "Comma-separated fields to search in: title, description or both (default)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of results, 1 to 100 (default 20)")
+    ),
+    responses(
+        (status = 200, description = "Matching links, most relevant first; title matches rank above description matches", body = ApiResponse<Vec<Link>>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 422, description = "Empty search query or unknown search field", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn search_links_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{id}",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to fetch"),
+        ("render" = Option<String>, Query, description = "`html` to add `description_html`, the markdown description rendered to sanitized HTML"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag from a previous response; returns 304 if the link is unchanged")
+    ),
+    responses(
+        (status = 200, description = "Link retrieved successfully; the ETag header identifies this version", body = ApiResponse<Link>),
+        (status = 200, description = "With `render=html`, the link plus its rendered description", body = ApiResponse<RenderedLink>),
+        (status = 304, description = "Link unchanged since the ETag in If-None-Match"),
+        (status = 401, description = "Invalid JWT token", body = ErrorResponse),
+        (status = 422, description = "Unsupported render mode", body = ErrorResponse),
+        (status = 404, description = "Link not found or not visible to the caller", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        (),
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_link_docs() {}
+
+#[utoipa::path(
+    put,
+    path = "/api/links/{id}",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to update")
+    ),
+    request_body = CreateLinkRequest,
+    responses(
+        (status = 200, description = "Link updated successfully", body = ApiResponse<Link>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Not authorized to update this link", body = ErrorResponse),
+        (status = 404, description = "Link not found", body = ErrorResponse),
+        (status = 409, description = "Slug already in use", body = ErrorResponse),
+        (status = 422, description = "Invalid request data (URL format, title/description length, slug)", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn update_link_docs() {}
+
+#[utoipa::path(
+    patch,
+    path = "/api/links/{id}",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to update")
+    ),
+    request_body = UpdateLinkRequest,
+    responses(
+        (status = 200, description = "Link updated successfully; fields missing from the body, such as url and title when only visibility is sent, are unchanged", body = ApiResponse<Link>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Not authorized to update this link", body = ErrorResponse),
+        (status = 404, description = "Link not found", body = ErrorResponse),
+        (status = 409, description = "Slug already in use", body = ErrorResponse),
+        (status = 422, description = "Empty update (EMPTY_UPDATE), invalid field values including a non-http(s) custom_image_url, or a collection that isn't yours (INVALID_COLLECTION)", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn patch_link_docs() {}
+
 #[utoipa::path(
     delete,
     path = "/api/links/{id}",
@@ -46,7 +177,7 @@ pub fn create_link_docs() {}
         ("id" = Uuid, Path, description = "ID of the link to delete")
     ),
     responses(
-        (status = 200, description = "Link deleted successfully", body = EmptyResponse),
+        (status = 200, description = "Link deleted; data holds a token that undoes it for 30 seconds", body = ApiResponse<DeletedLink>),
         (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
         (status = 403, description = "Not authorized to delete this link", body = ErrorResponse),
         (status = 404, description = "Link not found", body = ErrorResponse),
@@ -59,6 +190,22 @@ pub fn create_link_docs() {}
 )]
 pub fn delete_link_docs() {}
 
+#[utoipa::path(
+    post,
+    path = "/api/links/restore",
+    request_body = UndoDeleteRequest,
+    responses(
+        (status = 200, description = "Link restored successfully", body = ApiResponse<Link>),
+        (status = 400, description = "Malformed or tampered undo token", body = ErrorResponse),
+        (status = 404, description = "Link not found or can no longer be restored", body = ErrorResponse),
+        (status = 410, description = "Undo token expired", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(()),
+    tag = "links"
+)]
+pub fn undo_delete_link_docs() {}
+
 #[utoipa::path(
     post,
     path = "/api/links/{id}/click",
@@ -66,10 +213,252 @@ pub fn delete_link_docs() {}
         ("id" = Uuid, Path, description = "ID of the link to track click for")
     ),
     responses(
-        (status = 200, description = "Click tracked successfully", body = EmptyResponse),
+        (status = 200, description = "Click tracked; bot clicks are recorded but not counted, and links with track_clicks off are neither", body = ApiResponse<TrackedClick>),
         (status = 404, description = "Link not found", body = ErrorResponse),
         (status = 500, description = "Server error", body = ErrorResponse)
     ),
     tag = "links"
 )]
 pub fn track_click_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{id}/pixel.gif",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to track click for")
+    ),
+    responses(
+        (status = 200, description = "Click tracked; the body is an uncacheable transparent 1x1 GIF", content_type = "image/gif"),
+        (status = 404, description = "Link not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(()),
+    tag = "links"
+)]
+pub fn track_click_pixel_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{id}/stats",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to get statistics for"),
+        ("bucket" = Option<String>, Query, description = "Grouping of the clicks: hour, day (default), week or month"),
+        ("from" = Option<String>, Query, description = "Only count clicks at or after this RFC3339 timestamp"),
+        ("to" = Option<String>, Query, description = "Only count clicks at or before this RFC3339 timestamp")
+    ),
+    responses(
+        (status = 200, description = "Total clicks and unique visitors, overall and per time bucket", body = ApiResponse<LinkStats>),
+        (status = 400, description = "Unknown bucket"),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Not authorized to view this link's statistics", body = ErrorResponse),
+        (status = 404, description = "Link not found", body = ErrorResponse),
+        (status = 422, description = "Invalid timestamp filter", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_link_stats_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{id}/clicks",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to get clicks for"),
+        ("from" = Option<String>, Query, description = "Only return clicks at or after this RFC3339 timestamp"),
+        ("to" = Option<String>, Query, description = "Only return clicks at or before this RFC3339 timestamp"),
+        ("limit" = Option<i64>, Query, description = "Page size, 1 to 500 (default 50)"),
+        ("cursor" = Option<Uuid>, Query, description = "next_cursor from the previous page")
+    ),
+    responses(
+        (status = 200, description = "A page of click events, newest first", body = ApiResponse<ClickEventsPage>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Not authorized to view this link's clicks", body = ErrorResponse),
+        (status = 404, description = "Link not found", body = ErrorResponse),
+        (status = 422, description = "Invalid timestamp filter", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_link_clicks_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{id}/related",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to find related links for")
+    ),
+    responses(
+        (status = 200, description = "Up to 5 public links sharing the most tags (or title words) with the link", body = ApiResponse<Vec<Link>>),
+        (status = 401, description = "Invalid JWT token", body = ErrorResponse),
+        (status = 404, description = "Link not found or not visible to the caller", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        (),
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_related_links_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/sync",
+    params(
+        ("since" = Option<String>, Query, description = "RFC3339 timestamp; only links changed after it are returned, every link when absent"),
+        ("cursor" = Option<String>, Query, description = "meta.next_cursor from the previous page; takes the place of since"),
+        ("limit" = Option<i64>, Query, description = "Page size, 1 to 500 (default 100)")
+    ),
+    responses(
+        (status = 200, description = "The caller's changed links, oldest change first, with deleted ones as tombstones", body = ApiResponse<Vec<SyncedLink>>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 422, description = "Invalid since timestamp or cursor", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn sync_links_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/trending",
+    params(
+        ("limit" = Option<i64>, Query, description = "Number of links to return, 1 to 50 (default 10)")
+    ),
+    responses(
+        (status = 200, description = "Public links ranked by click_count / (hours_since_created + 2) ^ 1.5, highest first; links without clicks are left out", body = ApiResponse<Vec<Link>>),
+        (status = 401, description = "Invalid JWT token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        (),
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_trending_links_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/leaderboard",
+    params(
+        ("period" = Option<String>, Query, description = "Window to count clicks over: week (last 7 days, default) or month (last 30 days)"),
+        ("limit" = Option<i64>, Query, description = "Number of links to return, 1 to 50 (default 10)")
+    ),
+    responses(
+        (status = 200, description = "The caller's links ranked by non-bot clicks within the period, most clicked first; links without clicks in it are left out", body = ApiResponse<Vec<LeaderboardEntry>>),
+        (status = 400, description = "Invalid period", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    tag = "links"
+)]
+pub fn get_link_leaderboard_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    responses(
+        (status = 200, description = "Tags on the links visible to the caller with their usage counts, most used first", body = ApiResponse<Vec<TagCount>>),
+        (status = 401, description = "Invalid JWT token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        (),
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_tags_docs() {}
+
+#[utoipa::path(
+    get,
+    path = "/api/links/{id}/status",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to check")
+    ),
+    responses(
+        (status = 200, description = "Result of a live HEAD request to the link's URL, along with the link's updated health", body = ApiResponse<LinkStatus>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Not authorized to check this link", body = ErrorResponse),
+        (status = 404, description = "Link not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn get_link_status_docs() {}
+
+#[utoipa::path(
+    post,
+    path = "/api/links/{id}/transfer",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to transfer")
+    ),
+    request_body = TransferLinkRequest,
+    responses(
+        (status = 200, description = "Link transferred successfully", body = ApiResponse<Link>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Not authorized to transfer this link, or the new owner's active link quota is reached (QUOTA_EXCEEDED)", body = ErrorResponse),
+        (status = 404, description = "Link or target user not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn transfer_link_docs() {}
+
+#[utoipa::path(
+    post,
+    path = "/api/links/{id}/duplicate",
+    params(
+        ("id" = Uuid, Path, description = "ID of the link to duplicate; your own or a public one")
+    ),
+    responses(
+        (status = 201, description = "Copy created and owned by the caller, titled \"<title> (copy)\"; a copy of someone else's link is private", body = ApiResponse<Link>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Active link quota reached (QUOTA_EXCEEDED)", body = ErrorResponse),
+        (status = 404, description = "Link not found, or private and not yours", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn duplicate_link_docs() {}
+
+#[utoipa::path(
+    post,
+    path = "/api/links/{id}/claim",
+    params(
+        ("id" = Uuid, Path, description = "ID of the anonymous link to claim")
+    ),
+    request_body = ClaimLinkRequest,
+    responses(
+        (status = 200, description = "Link claimed; the caller now owns it and it no longer expires", body = ApiResponse<Link>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 403, description = "Invalid claim token, or the caller's active link quota is reached", body = ErrorResponse),
+        (status = 404, description = "Link not found or expired before it was claimed", body = ErrorResponse),
+        (status = 409, description = "Link already has an owner", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub fn claim_link_docs() {}
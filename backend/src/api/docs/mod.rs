@@ -1,18 +1,33 @@
+mod admin;
 mod auth;
+mod collections;
+mod dashboard;
 mod health;
 mod links;
+mod users;
+mod webhooks;
 
-use crate::api::models::VerifyEmailRequest;
-use crate::api::{ApiResponse, ErrorResponse};
-use crate::database::models::Link;
-use crate::models::auth::{AuthResponse, LoginRequest, RegisterRequest, User, UserStatus};
+use crate::api::models::{
+    ClaimLinkRequest, CreateCollectionRequest, CreateWebhookRequest, PaginatedResponse,
+    TransferLinkRequest, UndoDeleteRequest, UpdateLinkRequest, VerifyEmailRequest,
+};
+use crate::api::{ApiResponse, ErrorCode, ErrorResponse};
+use crate::database::models::{
+    ClickEvent, ClickStat, Collection, LeaderboardEntry, Link, LinkStats, SyncedLink, Webhook,
+};
+use crate::models::auth::{
+    AuthResponse, LoginRequest, RefreshRequest, RegisterRequest, User, UserProfile, UserRole,
+    UserStatus, UserSummary,
+};
 use crate::models::user::Gender;
+use crate::routes::dashboard::Dashboard;
+use crate::routes::links::{
+    AnonymousLink, ClickEventsPage, DeletedLink, LinkStatus, RenderedLink, TrackedClick,
+};
 use utoipa::OpenApi;
 
+/// Response without data; generic instances with `()` cannot be named by utoipa
 type EmptyResponse = ApiResponse<()>;
-type AuthResponseWrapper = ApiResponse<AuthResponse>;
-type LinkResponse = ApiResponse<Link>;
-type LinksResponse = ApiResponse<Vec<Link>>;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -20,28 +35,100 @@ type LinksResponse = ApiResponse<Vec<Link>>;
         crate::api::docs::auth::register_docs,
         crate::api::docs::auth::verify_email_docs,
         crate::api::docs::auth::login_docs,
+        crate::api::docs::auth::refresh_docs,
         crate::api::docs::links::get_links_docs,
         crate::api::docs::links::create_link_docs,
+        crate::api::docs::links::create_anonymous_link_docs,
+        crate::api::docs::links::search_links_docs,
+        crate::api::docs::links::get_link_docs,
+        crate::api::docs::links::update_link_docs,
+        crate::api::docs::links::patch_link_docs,
         crate::api::docs::links::delete_link_docs,
+        crate::api::docs::links::undo_delete_link_docs,
         crate::api::docs::links::track_click_docs,
+        crate::api::docs::links::track_click_pixel_docs,
+        crate::api::docs::links::get_link_stats_docs,
+        crate::api::docs::links::get_link_clicks_docs,
+        crate::api::docs::links::get_related_links_docs,
+        crate::api::docs::links::sync_links_docs,
+        crate::api::docs::links::get_trending_links_docs,
+        crate::api::docs::links::get_link_leaderboard_docs,
+        crate::api::docs::links::get_tags_docs,
+        crate::api::docs::links::get_link_status_docs,
+        crate::api::docs::links::transfer_link_docs,
+        crate::api::docs::links::duplicate_link_docs,
+        crate::api::docs::links::claim_link_docs,
+        crate::api::docs::collections::create_collection_docs,
+        crate::api::docs::collections::list_collections_docs,
+        crate::api::docs::collections::delete_collection_docs,
+        crate::api::docs::collections::get_collection_links_docs,
+        crate::api::docs::dashboard::get_dashboard_docs,
+        crate::api::docs::users::get_user_profile_docs,
+        crate::api::docs::webhooks::create_webhook_docs,
+        crate::api::docs::admin::list_users_docs,
+        crate::api::docs::admin::delete_user_docs,
+        crate::api::docs::admin::update_link_quota_docs,
+        crate::api::docs::admin::pool_stats_docs,
+        crate::api::docs::admin::list_audit_log_docs,
         crate::api::docs::health::root_docs,
+        crate::api::docs::health::ready_docs,
         crate::api::docs::health::admin_db_health_docs
     ),
     components(schemas(
         RegisterRequest,
         LoginRequest,
+        RefreshRequest,
         AuthResponse,
         User,
         Gender,
         UserStatus,
+        UserRole,
+        ApiResponse<Vec<UserSummary>>,
+        UserProfile,
+        ApiResponse<UserProfile>,
         VerifyEmailRequest,
         EmptyResponse,
-        AuthResponseWrapper,
-        LinkResponse,
-        LinksResponse,
+        ApiResponse<AuthResponse>,
+        ApiResponse<Link>,
+        PaginatedResponse<Link>,
+        ApiResponse<Vec<Link>>,
+        SyncedLink,
+        ApiResponse<Vec<SyncedLink>>,
+        LeaderboardEntry,
+        ApiResponse<Vec<LeaderboardEntry>>,
+        ClickStat,
+        ClickEvent,
+        ClickEventsPage,
+        TrackedClick,
+        RenderedLink,
+        DeletedLink,
+        LinkStats,
+        ApiResponse<LinkStats>,
+        ApiResponse<ClickEventsPage>,
+        ApiResponse<TrackedClick>,
+        ApiResponse<RenderedLink>,
+        ApiResponse<DeletedLink>,
+        LinkStatus,
+        ApiResponse<LinkStatus>,
+        AnonymousLink,
+        ApiResponse<AnonymousLink>,
+        TransferLinkRequest,
+        UndoDeleteRequest,
+        ClaimLinkRequest,
+        UpdateLinkRequest,
+        Collection,
+        CreateCollectionRequest,
+        ApiResponse<Collection>,
+        ApiResponse<Vec<Collection>>,
+        Dashboard,
+        ApiResponse<Dashboard>,
+        CreateWebhookRequest,
+        ApiResponse<Webhook>,
         ErrorResponse,
+        ErrorCode,
         Link
-    ))
+    )),
+    modifiers(&SecurityAddon)
 )]
 pub struct ApiDoc;
 
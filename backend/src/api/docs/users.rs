@@ -0,0 +1,19 @@
+use crate::api::{ApiResponse, ErrorResponse};
+use crate::models::auth::UserProfile;
+
+/// User Endpoints
+#[utoipa::path(
+    get,
+    path = "/api/users/{username}",
+    params(
+        ("username" = String, Path, description = "Username, matched ignoring case")
+    ),
+    responses(
+        (status = 200, description = "The user's public profile and link stats", body = ApiResponse<UserProfile>),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(()),
+    tag = "users"
+)]
+pub fn get_user_profile_docs() {}
@@ -0,0 +1,21 @@
+use crate::api::models::{CreateWebhookRequest, ValidationErrorResponse};
+use crate::api::{ApiResponse, ErrorResponse};
+use crate::database::models::Webhook;
+
+/// Webhook Endpoints
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered; the response includes its signing secret", body = ApiResponse<Webhook>),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 422, description = "Invalid URL or unknown event", body = ValidationErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "webhooks"
+)]
+pub fn create_webhook_docs() {}
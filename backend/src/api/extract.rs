@@ -0,0 +1,46 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use super::ErrorResponse;
+
+/// JSON body extractor that reports malformed bodies with the usual [`ErrorResponse`]
+/// envelope instead of axum's plain-text rejection
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(rejection_response(rejection)),
+        }
+    }
+}
+
+fn rejection_response(rejection: JsonRejection) -> Response {
+    let (status, code) = match &rejection {
+        JsonRejection::JsonSyntaxError(_) | JsonRejection::JsonDataError(_) => {
+            (StatusCode::BAD_REQUEST, "INVALID_JSON")
+        }
+        JsonRejection::MissingJsonContentType(_) => {
+            (StatusCode::UNSUPPORTED_MEDIA_TYPE, "UNSUPPORTED_MEDIA_TYPE")
+        }
+        _ if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+            (StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE")
+        }
+        _ => (rejection.status(), "INVALID_REQUEST_BODY"),
+    };
+
+    let error = ErrorResponse::new(rejection.body_text()).with_code(code);
+    (status, Json(error)).into_response()
+}
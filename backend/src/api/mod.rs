@@ -1,11 +1,14 @@
 #![allow(dead_code)]
 pub mod docs;
+pub mod extract;
 pub mod models;
 pub mod utils;
 
+use crate::logging::current_request_id;
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::{env, fmt, sync::OnceLock};
 use utoipa::ToSchema;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -16,9 +19,27 @@ pub struct ApiResponse<T: Serialize + ToSchema> {
     pub data: T,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pagination: Option<PaginationMeta>,
+    /// Cursors, totals and warnings; absent when a response has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ResponseMeta>,
     pub timestamp: DateTime<Utc>,
 }
 
+/// Extra details about a successful response, each field present only when set
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ResponseMeta {
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Total number of items matching the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Non-blocking notices about a request that succeeded, such as `duplicate_title`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(example = json!(["duplicate_title"]))]
+    pub warnings: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PaginationMeta {
     pub current_page: u32,
@@ -27,12 +48,189 @@ pub struct PaginationMeta {
     pub total_pages: u32,
 }
 
+/// Machine-readable codes sent in [`ErrorResponse::code`]
+///
+/// The links endpoints take their codes from here, so a code can't be misspelled in one
+/// handler and the OpenAPI schema lists every code they can return. Other endpoints
+/// still pass string literals to [`ErrorResponse::with_code`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    AlreadyClaimed,
+    BlockedHost,
+    ClicksFetchError,
+    ClickStatsError,
+    ClickTrackError,
+    CollectionFetchError,
+    ConfirmationRequired,
+    DuplicateLink,
+    EmptySearchQuery,
+    EmptyUpdate,
+    FavoritesFetchError,
+    FavoriteError,
+    Forbidden,
+    IdempotencyKeyConflict,
+    IdempotencyKeyError,
+    IdempotencyKeyInProgress,
+    IdempotencyKeyReused,
+    ImageTooLarge,
+    InvalidClaimToken,
+    InvalidCollection,
+    InvalidCursor,
+    InvalidExpiry,
+    InvalidHealth,
+    InvalidIdempotencyKey,
+    InvalidPublishAt,
+    InvalidRender,
+    InvalidScheme,
+    InvalidSearchFields,
+    InvalidSort,
+    InvalidTimestamp,
+    InvalidUndoToken,
+    InvalidUpload,
+    InvalidUrl,
+    LinksFetchError,
+    LinksSearchError,
+    LinkClaimError,
+    LinkCreateError,
+    LinkDeleteError,
+    LinkFetchError,
+    LinkNotFound,
+    LinkRestoreError,
+    LinkTransferError,
+    LinkUpdateError,
+    NotAcceptable,
+    NotAnImage,
+    NotFound,
+    NoPreviewImage,
+    PayloadTooLarge,
+    PreviewDisallowed,
+    PreviewFetchFailed,
+    PreviewImageFetchFailed,
+    QrRenderError,
+    QuotaExceeded,
+    QuotaFetchError,
+    SlugTaken,
+    TagsFetchError,
+    TargetUserNotFound,
+    TooManyRedirects,
+    Unauthorized,
+    UndoTokenExpired,
+    UrlHasCredentials,
+    UrlTooLong,
+    UrlUnreachable,
+    UserFetchError,
+    UserNotFound,
+}
+
+impl ErrorCode {
+    /// The code as sent to clients, such as `LINK_FETCH_ERROR`
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::AlreadyClaimed => "ALREADY_CLAIMED",
+            ErrorCode::BlockedHost => "BLOCKED_HOST",
+            ErrorCode::ClicksFetchError => "CLICKS_FETCH_ERROR",
+            ErrorCode::ClickStatsError => "CLICK_STATS_ERROR",
+            ErrorCode::ClickTrackError => "CLICK_TRACK_ERROR",
+            ErrorCode::CollectionFetchError => "COLLECTION_FETCH_ERROR",
+            ErrorCode::ConfirmationRequired => "CONFIRMATION_REQUIRED",
+            ErrorCode::DuplicateLink => "DUPLICATE_LINK",
+            ErrorCode::EmptySearchQuery => "EMPTY_SEARCH_QUERY",
+            ErrorCode::EmptyUpdate => "EMPTY_UPDATE",
+            ErrorCode::FavoritesFetchError => "FAVORITES_FETCH_ERROR",
+            ErrorCode::FavoriteError => "FAVORITE_ERROR",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::IdempotencyKeyConflict => "IDEMPOTENCY_KEY_CONFLICT",
+            ErrorCode::IdempotencyKeyError => "IDEMPOTENCY_KEY_ERROR",
+            ErrorCode::IdempotencyKeyInProgress => "IDEMPOTENCY_KEY_IN_PROGRESS",
+            ErrorCode::IdempotencyKeyReused => "IDEMPOTENCY_KEY_REUSED",
+            ErrorCode::ImageTooLarge => "IMAGE_TOO_LARGE",
+            ErrorCode::InvalidClaimToken => "INVALID_CLAIM_TOKEN",
+            ErrorCode::InvalidCollection => "INVALID_COLLECTION",
+            ErrorCode::InvalidCursor => "INVALID_CURSOR",
+            ErrorCode::InvalidExpiry => "INVALID_EXPIRY",
+            ErrorCode::InvalidHealth => "INVALID_HEALTH",
+            ErrorCode::InvalidIdempotencyKey => "INVALID_IDEMPOTENCY_KEY",
+            ErrorCode::InvalidPublishAt => "INVALID_PUBLISH_AT",
+            ErrorCode::InvalidRender => "INVALID_RENDER",
+            ErrorCode::InvalidScheme => "INVALID_SCHEME",
+            ErrorCode::InvalidSearchFields => "INVALID_SEARCH_FIELDS",
+            ErrorCode::InvalidSort => "INVALID_SORT",
+            ErrorCode::InvalidTimestamp => "INVALID_TIMESTAMP",
+            ErrorCode::InvalidUndoToken => "INVALID_UNDO_TOKEN",
+            ErrorCode::InvalidUpload => "INVALID_UPLOAD",
+            ErrorCode::InvalidUrl => "INVALID_URL",
+            ErrorCode::LinksFetchError => "LINKS_FETCH_ERROR",
+            ErrorCode::LinksSearchError => "LINKS_SEARCH_ERROR",
+            ErrorCode::LinkClaimError => "LINK_CLAIM_ERROR",
+            ErrorCode::LinkCreateError => "LINK_CREATE_ERROR",
+            ErrorCode::LinkDeleteError => "LINK_DELETE_ERROR",
+            ErrorCode::LinkFetchError => "LINK_FETCH_ERROR",
+            ErrorCode::LinkNotFound => "LINK_NOT_FOUND",
+            ErrorCode::LinkRestoreError => "LINK_RESTORE_ERROR",
+            ErrorCode::LinkTransferError => "LINK_TRANSFER_ERROR",
+            ErrorCode::LinkUpdateError => "LINK_UPDATE_ERROR",
+            ErrorCode::NotAcceptable => "NOT_ACCEPTABLE",
+            ErrorCode::NotAnImage => "NOT_AN_IMAGE",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::NoPreviewImage => "NO_PREVIEW_IMAGE",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::PreviewDisallowed => "PREVIEW_DISALLOWED",
+            ErrorCode::PreviewFetchFailed => "PREVIEW_FETCH_FAILED",
+            ErrorCode::PreviewImageFetchFailed => "PREVIEW_IMAGE_FETCH_FAILED",
+            ErrorCode::QrRenderError => "QR_RENDER_ERROR",
+            ErrorCode::QuotaExceeded => "QUOTA_EXCEEDED",
+            ErrorCode::QuotaFetchError => "QUOTA_FETCH_ERROR",
+            ErrorCode::SlugTaken => "SLUG_TAKEN",
+            ErrorCode::TagsFetchError => "TAGS_FETCH_ERROR",
+            ErrorCode::TargetUserNotFound => "TARGET_USER_NOT_FOUND",
+            ErrorCode::TooManyRedirects => "TOO_MANY_REDIRECTS",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::UndoTokenExpired => "UNDO_TOKEN_EXPIRED",
+            ErrorCode::UrlHasCredentials => "URL_HAS_CREDENTIALS",
+            ErrorCode::UrlTooLong => "URL_TOO_LONG",
+            ErrorCode::UrlUnreachable => "URL_UNREACHABLE",
+            ErrorCode::UserFetchError => "USER_FETCH_ERROR",
+            ErrorCode::UserNotFound => "USER_NOT_FOUND",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<ErrorCode> for String {
+    fn from(code: ErrorCode) -> Self {
+        code.as_str().to_string()
+    }
+}
+
+static DEBUG_ERRORS: OnceLock<bool> = OnceLock::new();
+
+/// Whether internal error details are sent to clients, configurable via `DEBUG_ERRORS`;
+/// off by default and meant for local development only
+fn debug_errors() -> bool {
+    *DEBUG_ERRORS.get_or_init(|| {
+        env::var("DEBUG_ERRORS").is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"))
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ErrorResponse {
     pub success: bool,
     pub message: String,
+    /// Machine-readable error code; the links endpoints send one of [`ErrorCode`]
     pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    /// ID of the request, as in the `X-Request-ID` header, sent with internal errors so
+    /// they can be found in the server logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -43,6 +241,7 @@ impl<T: Serialize + ToSchema> ApiResponse<T> {
             message: String::new(),
             data,
             pagination: None,
+            meta: None,
             timestamp: Utc::now(),
         }
     }
@@ -53,6 +252,7 @@ impl<T: Serialize + ToSchema> ApiResponse<T> {
             message: message.into(),
             data,
             pagination: None,
+            meta: None,
             timestamp: Utc::now(),
         }
     }
@@ -61,6 +261,31 @@ impl<T: Serialize + ToSchema> ApiResponse<T> {
         self.pagination = Some(pagination);
         self
     }
+
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>) -> Self {
+        if next_cursor.is_some() {
+            self.meta
+                .get_or_insert_with(ResponseMeta::default)
+                .next_cursor = next_cursor;
+        }
+        self
+    }
+
+    pub fn with_total(mut self, total: i64) -> Self {
+        self.meta.get_or_insert_with(ResponseMeta::default).total = Some(total);
+        self
+    }
+
+    /// Adds warnings to the response; an empty list leaves `meta` untouched
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        if !warnings.is_empty() {
+            self.meta
+                .get_or_insert_with(ResponseMeta::default)
+                .warnings
+                .extend(warnings);
+        }
+        self
+    }
 }
 
 impl ErrorResponse {
@@ -69,14 +294,42 @@ impl ErrorResponse {
             success: false,
             message: message.into(),
             code: String::new(),
+            details: None,
+            correlation_id: None,
             timestamp: Utc::now(),
         }
     }
 
+    /// An error response for a failure on our side, such as a database error
+    ///
+    /// Clients get `message` and a correlation ID but not the error itself, which could
+    /// leak query or connection details; callers log the error. With `DEBUG_ERRORS=true`
+    /// the error is appended to the message, as in `Failed to fetch links: <error>`.
+    pub fn internal(message: &str, error: impl fmt::Display) -> Self {
+        Self::internal_with(message, error, debug_errors())
+    }
+
+    fn internal_with(message: &str, error: impl fmt::Display, show_error: bool) -> Self {
+        let message = if show_error {
+            format!("{message}: {error}")
+        } else {
+            message.to_string()
+        };
+        let mut response = Self::new(message);
+        response.correlation_id = current_request_id();
+        response
+    }
+
+    /// Sets the error code, either an [`ErrorCode`] or, where none fits yet, a string
     pub fn with_code(mut self, code: impl Into<String>) -> Self {
         self.code = code.into();
         self
     }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
 }
 
 impl IntoResponse for ErrorResponse {
@@ -84,3 +337,37 @@ impl IntoResponse for ErrorResponse {
         (StatusCode::INTERNAL_SERVER_ERROR, Json(self)).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database_error() -> sqlx::Error {
+        sqlx::Error::Protocol("relation \"links\" does not exist".into())
+    }
+
+    #[test]
+    fn internal_errors_leave_out_the_database_error() {
+        let response = ErrorResponse::internal_with("Database error", database_error(), false)
+            .with_code(ErrorCode::ClicksFetchError);
+        let body = serde_json::to_string(&response).unwrap();
+
+        assert_eq!(response.message, "Database error");
+        assert!(!body.contains("relation"), "{body}");
+        assert!(!body.contains(&database_error().to_string()), "{body}");
+    }
+
+    #[test]
+    fn internal_errors_show_the_database_error_when_debugging() {
+        let response = ErrorResponse::internal_with("Database error", database_error(), true);
+
+        assert!(
+            response.message.starts_with("Database error: "),
+            "{}",
+            response.message
+        );
+        assert!(response
+            .message
+            .contains("relation \"links\" does not exist"));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::models::auth::Claims;
+use crate::{
+    models::auth::Claims,
+    services::auth::{access_token_validation, jwt_issuer},
+};
 use axum::{
     body::Body,
     extract::State,
@@ -6,16 +9,20 @@ use axum::{
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use jsonwebtoken::{decode, DecodingKey};
 
 #[derive(Clone)]
 pub struct AuthMiddlewareState {
     jwt_secret: String,
+    jwt_issuer: String,
 }
 
 impl AuthMiddlewareState {
     pub fn new(jwt_secret: String) -> Self {
-        Self { jwt_secret }
+        Self {
+            jwt_secret,
+            jwt_issuer: jwt_issuer(),
+        }
     }
 }
 
@@ -39,7 +46,7 @@ pub async fn auth_middleware(
     let token_data = decode::<Claims>(
         token,
         &DecodingKey::from_secret(state.jwt_secret.as_bytes()),
-        &Validation::default(),
+        &access_token_validation(&state.jwt_issuer),
     )
     .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
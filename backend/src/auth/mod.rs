@@ -1,9 +1,12 @@
 pub mod middleware;
 pub mod routes;
 
+use crate::middleware::body_limit::{body_limit, DEFAULT_BODY_LIMIT};
 use axum::Router;
 use sqlx::PgPool;
 
 pub fn create_router(pool: PgPool) -> Router {
-    Router::new().merge(routes::create_router(pool))
+    Router::new()
+        .merge(routes::create_router(pool))
+        .layer(body_limit(DEFAULT_BODY_LIMIT))
 }
@@ -1,4 +1,6 @@
-use crate::handlers::auth::{admin_reset_otp_attempts, login, register, resend_otp, verify_email};
+use crate::handlers::auth::{
+    admin_reset_otp_attempts, login, refresh, register, resend_otp, verify_email,
+};
 use crate::services::{auth::AuthService, email::EmailService};
 use axum::{routing::post, Router};
 use sqlx::PgPool;
@@ -22,6 +24,7 @@ pub fn create_router(pool: PgPool) -> Router {
     Router::new()
         .route("/api/auth/register", post(register))
         .route("/api/auth/login", post(login))
+        .route("/api/auth/refresh", post(refresh))
         .route("/api/auth/verify", post(verify_email))
         .route("/api/auth/resend-otp", post(resend_otp))
         .route(
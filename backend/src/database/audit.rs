@@ -0,0 +1,112 @@
+use super::models::{AuditAction, AuditLogEntry};
+use serde_json::Value as JsonValue;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Filters applied when listing the audit log
+#[derive(Debug, Default, Clone)]
+pub struct AuditFilters {
+    /// Only include changes made by this user
+    pub actor_id: Option<Uuid>,
+    /// Only include changes of this kind
+    pub action: Option<AuditAction>,
+}
+
+/// Records a change in the audit log
+///
+/// Takes the connection of the transaction making the change, so the entry is committed
+/// or rolled back together with it; an error here must abort the change.
+///
+/// # Arguments
+/// * `conn` - Connection of the transaction making the change
+/// * `actor_id` - The user making the change
+/// * `action` - What kind of change it is
+/// * `entity_id` - The ID of the changed record
+/// * `metadata` - Action-specific details of the change
+///
+/// # Returns
+/// * `Result<(), sqlx::Error>` - Success or an error
+pub async fn record_audit_event(
+    conn: &mut PgConnection,
+    actor_id: Uuid,
+    action: AuditAction,
+    entity_id: Uuid,
+    metadata: JsonValue,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_id, action, entity_type, entity_id, metadata)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        actor_id,
+        action as _,
+        action.entity_type(),
+        entity_id,
+        metadata
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Lists audit log entries, newest first
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `filters` - Optional actor and action to narrow the entries down to
+/// * `limit` - Maximum number of entries to return
+/// * `offset` - Number of entries to skip
+///
+/// # Returns
+/// * `Result<Vec<AuditLogEntry>, sqlx::Error>` - One page of entries or an error
+pub async fn get_audit_log(
+    pool: &PgPool,
+    filters: &AuditFilters,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<AuditLogEntry>, sqlx::Error> {
+    sqlx::query_as!(
+        AuditLogEntry,
+        r#"
+        SELECT
+            id,
+            actor_id,
+            action as "action: AuditAction",
+            entity_type,
+            entity_id,
+            metadata,
+            created_at
+        FROM audit_log
+        WHERE ($1::uuid IS NULL OR actor_id = $1)
+            AND ($2::audit_action IS NULL OR action = $2)
+        ORDER BY created_at DESC, id
+        LIMIT $3 OFFSET $4
+        "#,
+        filters.actor_id,
+        filters.action as _,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Counts the audit log entries matching `filters`
+pub async fn get_audit_log_count(
+    pool: &PgPool,
+    filters: &AuditFilters,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM audit_log
+        WHERE ($1::uuid IS NULL OR actor_id = $1)
+            AND ($2::audit_action IS NULL OR action = $2)
+        "#,
+        filters.actor_id,
+        filters.action as _
+    )
+    .fetch_one(pool)
+    .await
+}
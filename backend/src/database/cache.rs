@@ -0,0 +1,72 @@
+use super::{models::Link, queries, PgPool};
+use chrono::Utc;
+use moka::future::Cache;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long a link stays cached before it is read from the database again
+const LINK_CACHE_TTL: Duration = Duration::from_secs(30);
+/// Upper bound on the number of cached links; least recently used entries are evicted first
+const LINK_CACHE_CAPACITY: u64 = 10_000;
+
+/// Read-through cache in front of [`queries::get_link_by_id`]
+///
+/// Handlers that change a link must call [`LinkCache::invalidate`] afterwards so readers
+/// don't see stale data for the rest of the TTL.
+#[derive(Clone)]
+pub struct LinkCache {
+    links: Cache<Uuid, Link>,
+}
+
+impl LinkCache {
+    pub fn new() -> Self {
+        Self {
+            links: Cache::builder()
+                .max_capacity(LINK_CACHE_CAPACITY)
+                .time_to_live(LINK_CACHE_TTL)
+                .build(),
+        }
+    }
+
+    /// Returns a link by its ID, only hitting the database on a cache miss
+    ///
+    /// Missing and deleted links are not cached, and a cached link is dropped once it expires.
+    pub async fn get_link_by_id(
+        &self,
+        pool: &PgPool,
+        link_id: Uuid,
+    ) -> Result<Option<Link>, sqlx::Error> {
+        if let Some(link) = self.links.get(&link_id).await {
+            if link
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= Utc::now())
+            {
+                self.links.invalidate(&link_id).await;
+                return Ok(None);
+            }
+            return Ok(Some(link));
+        }
+
+        let link = queries::get_link_by_id(pool, link_id).await?;
+        if let Some(link) = &link {
+            self.links.insert(link_id, link.clone()).await;
+        }
+        Ok(link)
+    }
+
+    /// Drops a link from the cache after it was changed
+    pub async fn invalidate(&self, link_id: Uuid) {
+        self.links.invalidate(&link_id).await;
+    }
+
+    /// Drops every cached link, for changes touching many links at once
+    pub fn invalidate_all(&self) {
+        self.links.invalidate_all();
+    }
+}
+
+impl Default for LinkCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
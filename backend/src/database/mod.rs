@@ -1,14 +1,63 @@
+pub mod audit;
+pub mod cache;
 pub mod models;
+pub mod pagination;
 pub mod queries;
+pub mod refresh_tokens;
+pub use cache::LinkCache;
 pub use queries::get_all_links;
 pub use sqlx::PgPool;
 
 use sqlx::migrate::MigrateError;
 use sqlx::postgres::PgPoolOptions;
+use std::{env, str::FromStr, time::Duration};
 
+/// How often expired idempotency keys are purged
+const IDEMPOTENCY_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How often refresh tokens past their expiry are purged
+const REFRESH_TOKEN_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// How often links past their expiry are soft-deleted
+const EXPIRED_LINK_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+const DEFAULT_MIN_CONNECTIONS: u32 = 0;
+const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 10 * 60;
+
+/// Reads a numeric setting from the environment, using `default` when unset or invalid
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Connects the pool, sized and timed by `DATABASE_MAX_CONNECTIONS` (default 5),
+/// `DATABASE_MIN_CONNECTIONS` (default 0), `DATABASE_ACQUIRE_TIMEOUT_SECS` (default 30)
+/// and `DATABASE_IDLE_TIMEOUT_SECS` (default 600)
 pub async fn create_pool(database_url: &str) -> PgPool {
+    let max_connections = env_or("DATABASE_MAX_CONNECTIONS", DEFAULT_MAX_CONNECTIONS).max(1);
+    let min_connections =
+        env_or("DATABASE_MIN_CONNECTIONS", DEFAULT_MIN_CONNECTIONS).min(max_connections);
+    let acquire_timeout = env_or(
+        "DATABASE_ACQUIRE_TIMEOUT_SECS",
+        DEFAULT_ACQUIRE_TIMEOUT_SECS,
+    );
+    let idle_timeout = env_or("DATABASE_IDLE_TIMEOUT_SECS", DEFAULT_IDLE_TIMEOUT_SECS);
+
+    tracing::info!(
+        max_connections,
+        min_connections,
+        acquire_timeout_secs = acquire_timeout,
+        idle_timeout_secs = idle_timeout,
+        "Connecting to the database"
+    );
+
     PgPoolOptions::new()
-        .max_connections(5)
+        .max_connections(max_connections)
+        .min_connections(min_connections)
+        .acquire_timeout(Duration::from_secs(acquire_timeout))
+        .idle_timeout(Duration::from_secs(idle_timeout))
         .connect(database_url)
         .await
         .expect("Failed to create database pool")
@@ -17,3 +66,57 @@ pub async fn create_pool(database_url: &str) -> PgPool {
 pub async fn run_migrations(pool: &PgPool) -> Result<(), MigrateError> {
     sqlx::migrate!("./migrations").run(pool).await
 }
+
+/// Spawns a background task that periodically deletes expired idempotency keys
+pub fn spawn_idempotency_key_cleanup(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(IDEMPOTENCY_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match queries::delete_expired_idempotency_keys(&pool).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Deleted {deleted} expired idempotency keys"),
+                Err(e) => tracing::warn!("Failed to delete expired idempotency keys: {e}"),
+            }
+        }
+    });
+}
+
+/// Spawns a background task that periodically deletes expired refresh tokens
+pub fn spawn_refresh_token_cleanup(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_TOKEN_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match refresh_tokens::delete_expired_refresh_tokens(&pool).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Deleted {deleted} expired refresh tokens"),
+                Err(e) => tracing::warn!("Failed to delete expired refresh tokens: {e}"),
+            }
+        }
+    });
+}
+
+/// Spawns a background task that periodically soft-deletes expired links
+///
+/// Reads already hide expired links, so this only has to keep the table tidy and
+/// make expired links show up among the owner's restorable deletions. Anonymous links
+/// nobody claimed within a day have no owner to restore them and are removed for good.
+pub fn spawn_expired_link_cleanup(pool: PgPool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRED_LINK_CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match queries::delete_unclaimed_links(&pool).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Deleted {deleted} unclaimed anonymous links"),
+                Err(e) => tracing::warn!("Failed to delete unclaimed links: {e}"),
+            }
+            match queries::delete_expired_links(&pool).await {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Deleted {deleted} expired links"),
+                Err(e) => tracing::warn!("Failed to delete expired links: {e}"),
+            }
+        }
+    });
+}
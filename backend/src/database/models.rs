@@ -5,6 +5,105 @@ use sqlx::types::Json;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use super::pagination::Keyed;
+
+/// Who can see a link
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "link_visibility", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LinkVisibility {
+    /// Listed for everyone, including unauthenticated visitors
+    Public,
+    /// Only visible to the link's owner
+    #[default]
+    Private,
+}
+
+/// Whether a link's target was still reachable when last checked
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "link_health", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LinkHealth {
+    /// The target answered the last check with a success or redirect
+    Alive,
+    /// The target failed several checks in a row with an error status or DNS failure
+    Dead,
+    /// Not checked yet, or recent checks were inconclusive
+    #[default]
+    Unknown,
+}
+
+impl std::str::FromStr for LinkHealth {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "alive" => Ok(LinkHealth::Alive),
+            "dead" => Ok(LinkHealth::Dead),
+            "unknown" => Ok(LinkHealth::Unknown),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Progress of fetching a link's preview in the background
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "preview_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewStatus {
+    /// The preview is queued or being fetched
+    #[default]
+    Pending,
+    /// The preview was fetched and stored
+    Ready,
+    /// Fetching the preview failed and won't be retried
+    Failed,
+    /// No preview was ever requested for the link
+    Skipped,
+}
+
+/// What kind of resource a preview describes, so clients can render it appropriately
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkPreviewKind {
+    /// A web page whose metadata was parsed from its HTML
+    #[default]
+    Html,
+    /// An image; `image` points at the link itself
+    Image,
+    /// A PDF document; only the title is filled in
+    Pdf,
+    /// A video, either a direct file or a YouTube page
+    Video,
+    /// Any other content type; only the title is filled in
+    Other,
+}
+
+impl LinkPreviewKind {
+    /// Classifies a `Content-Type` header value, ignoring parameters such as charset
+    pub fn from_content_type(content_type: &str) -> Self {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match mime.as_str() {
+            "text/html" | "application/xhtml+xml" => LinkPreviewKind::Html,
+            "application/pdf" => LinkPreviewKind::Pdf,
+            _ if mime.starts_with("image/") => LinkPreviewKind::Image,
+            _ if mime.starts_with("video/") => LinkPreviewKind::Video,
+            _ => LinkPreviewKind::Other,
+        }
+    }
+}
+
 /// Represents a link preview metadata
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct LinkPreview {
@@ -20,6 +119,82 @@ pub struct LinkPreview {
     /// URL of the page's main image
     #[schema(example = "https://www.rust-lang.org/static/images/rust-social.jpg")]
     pub image: Option<String>,
+    /// Name of the site the page belongs to, from `og:site_name`
+    #[schema(example = "Rust Programming Language")]
+    pub site_name: Option<String>,
+    /// Language of the page, from `og:locale` or the `<html lang>` attribute
+    #[schema(example = "en-US")]
+    pub language: Option<String>,
+    /// Kind of resource the link points to; previews stored before this existed are HTML
+    #[serde(default)]
+    pub kind: LinkPreviewKind,
+    /// True when the page had no image and `image` is the deployment's default image
+    #[serde(default)]
+    #[schema(example = false)]
+    pub image_is_fallback: bool,
+    /// The image at most 320px wide, for lists; set whenever `image` is
+    #[serde(default)]
+    #[schema(
+        example = "/api/links/3fa85f64-5717-4562-b3fc-2c963f66afa6/preview-image?variant=small"
+    )]
+    pub thumbnail_small: Option<String>,
+    /// The image at most 1280px wide, for detail views; set whenever `image` is
+    #[serde(default)]
+    #[schema(
+        example = "/api/links/3fa85f64-5717-4562-b3fc-2c963f66afa6/preview-image?variant=large"
+    )]
+    pub thumbnail_large: Option<String>,
+}
+
+/// Downscaled size of a preview image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "thumbnail_variant", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailVariant {
+    /// 320px wide, for lists
+    Small,
+    /// 1280px wide, for detail views
+    Large,
+}
+
+impl ThumbnailVariant {
+    pub const ALL: [ThumbnailVariant; 2] = [ThumbnailVariant::Small, ThumbnailVariant::Large];
+
+    /// Width the image is scaled down to, keeping its aspect ratio
+    pub fn width(self) -> u32 {
+        match self {
+            ThumbnailVariant::Small => 320,
+            ThumbnailVariant::Large => 1280,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThumbnailVariant::Small => "small",
+            ThumbnailVariant::Large => "large",
+        }
+    }
+}
+
+/// A stored, downscaled copy of a link's preview image
+#[derive(Debug, Clone)]
+pub struct LinkThumbnail {
+    pub variant: ThumbnailVariant,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// `ETag` and `Last-Modified` a page was served with, sent back to ask whether it changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreviewValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl PreviewValidators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
 }
 
 #[derive(Debug, sqlx::Type)]
@@ -44,16 +219,194 @@ impl From<Option<&LinkPreview>> for JsonLinkPreview {
     }
 }
 
-/// Simple user representation for link associations
+/// Number of clicks a link received within a time bucket
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClickStat {
+    /// Start of the time bucket
+    #[schema(example = "2024-03-10T00:00:00Z")]
+    pub bucket_start: DateTime<Utc>,
+    /// Number of clicks within the bucket
+    #[schema(example = 42)]
+    pub clicks: i64,
+    /// Number of distinct visitors within the bucket
+    #[schema(example = 17)]
+    pub unique_visitors: i64,
+}
+
+/// Click totals for a link along with their breakdown over time
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LinkStats {
+    /// Number of clicks in the requested window
+    #[schema(example = 120)]
+    pub total_clicks: i64,
+    /// Number of distinct visitors in the requested window; a visitor returning on another
+    /// day counts again while client IPs are hashed with a daily salt
+    #[schema(example = 45)]
+    pub unique_visitors: i64,
+    /// Click counts per time bucket, oldest first
+    pub buckets: Vec<ClickStat>,
+}
+
+/// A tag along with the number of links carrying it
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagCount {
+    #[schema(example = "rust")]
+    pub tag: String,
+    /// Number of visible links carrying the tag
+    #[schema(example = 12)]
+    pub count: i64,
+}
+
+/// Totals over a user's active links
+#[derive(Debug)]
+pub struct LinkTotals {
+    /// Links that are neither deleted nor expired
+    pub links: i64,
+    /// Sum of the click counts of those links
+    pub clicks: i64,
+}
+
+/// How many active links a user has against how many they may have
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkQuota {
+    /// Links that are neither deleted nor expired
+    #[schema(example = 42)]
+    pub current: i64,
+    /// Maximum number of active links
+    #[schema(example = 1000)]
+    pub limit: i32,
+}
+
+/// A single recorded click on a link
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClickEvent {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub id: Uuid,
+    /// When the click happened
+    #[schema(example = "2024-03-10T15:00:00Z")]
+    pub clicked_at: DateTime<Utc>,
+    /// The Referer header sent with the click, if any
+    #[schema(example = "https://news.ycombinator.com/")]
+    pub referrer: Option<String>,
+    /// Salted SHA-256 of the client IP
+    pub ip_hash: Option<String>,
+    /// Whether the click came from a crawler; bot clicks aren't counted
+    pub is_bot: bool,
+}
+
+/// A stored `Idempotency-Key` for link creation
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    /// SHA-256 of the request the key was first used with
+    pub request_hash: String,
+    /// The link created by that request; None while it is still being processed
+    pub link_id: Option<Uuid>,
+}
+
+/// A change recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "audit_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A link's fields were edited
+    LinkUpdated,
+    /// A link was soft-deleted
+    LinkDeleted,
+    /// A link was handed to another user
+    LinkTransferred,
+}
+
+impl AuditAction {
+    /// The kind of record the action applies to, stored as the entry's `entity_type`
+    pub fn entity_type(self) -> &'static str {
+        match self {
+            AuditAction::LinkUpdated | AuditAction::LinkDeleted | AuditAction::LinkTransferred => {
+                "link"
+            }
+        }
+    }
+}
+
+/// One entry of the audit log
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditLogEntry {
+    #[schema(example = "3fa85f64-5717-4562-b3fc-2c963f66afa6")]
+    pub id: Uuid,
+    /// The user who made the change
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub actor_id: Uuid,
+    pub action: AuditAction,
+    #[schema(example = "link")]
+    pub entity_type: String,
+    #[schema(example = "3fa85f64-5717-4562-b3fc-2c963f66afa6")]
+    pub entity_id: Uuid,
+    /// Details of the change, which depend on the action
+    #[schema(value_type = Object, example = json!({"fields": ["title", "tags"]}))]
+    pub metadata: JsonValue,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A user's subscription to link events
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct Webhook {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub id: Uuid,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub user_id: Uuid,
+    /// Endpoint that receives the event payloads
+    #[schema(example = "https://example.com/hooks/linksphere")]
+    pub url: String,
+    /// Key used to sign each delivery's `X-Signature` header
+    pub secret: String,
+    /// Event names the subscription receives
+    #[schema(example = json!(["link.created", "link.deleted"]))]
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A named folder grouping some of a user's links
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+pub struct Collection {
+    #[schema(example = "3fa85f64-5717-4562-b3fc-2c963f66afa6")]
+    pub id: Uuid,
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub user_id: Uuid,
+    #[schema(example = "Reading list")]
+    pub name: String,
+    /// Number of live links in the collection
+    #[schema(example = 12)]
+    pub link_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Simple user representation for link associations
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct SimpleUser {
     pub username: String,
 }
 
-#[derive(Debug, sqlx::Type)]
-#[sqlx(transparent)]
+/// Link owner as selected by queries, `None` when the link has no owner
+#[derive(Debug)]
 pub struct OptionalJsonUser(pub Json<Option<SimpleUser>>);
 
+impl sqlx::Type<sqlx::Postgres> for OptionalJsonUser {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        <Json<JsonValue> as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+        <Json<JsonValue> as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    }
+}
+
+// Unclaimed links have no owner, so the joined username comes back as null
+impl<'r> sqlx::Decode<'r, sqlx::Postgres> for OptionalJsonUser {
+    fn decode(value: sqlx::postgres::PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let Json(value) = <Json<JsonValue> as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        Ok(OptionalJsonUser::from(value))
+    }
+}
+
 impl From<JsonValue> for OptionalJsonUser {
     fn from(value: JsonValue) -> Self {
         OptionalJsonUser(Json(serde_json::from_value(value).ok()))
@@ -73,7 +426,7 @@ impl From<Option<SimpleUser>> for OptionalJsonUser {
 }
 
 /// Represents a link in the system
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, sqlx::FromRow)]
 pub struct Link {
     /// Unique identifier for the link
     #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
@@ -81,18 +434,61 @@ pub struct Link {
     /// The URL of the link
     #[schema(example = "https://www.rust-lang.org")]
     pub url: String,
+    /// The URL exactly as submitted by the user, before normalization
+    #[schema(example = "https://WWW.Rust-Lang.org/?utm_source=newsletter")]
+    pub original_url: String,
     /// Title of the link
     #[schema(example = "Official Rust Website")]
     pub title: String,
     /// Description of the link
     #[schema(example = "The home page of the Rust programming language")]
     pub description: String,
-    /// ID of the user who created the link
+    /// ID of the user who owns the link; `null` while an anonymously created link is unclaimed
     #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
-    pub user_id: Uuid,
+    pub user_id: Option<Uuid>,
     /// Number of times the link has been clicked
     #[schema(example = 0)]
     pub click_count: i32,
+    /// Lowercased tags used to organize the link
+    #[schema(example = json!(["rust", "programming"]))]
+    pub tags: Vec<String>,
+    /// Who can see the link
+    pub visibility: LinkVisibility,
+    /// Short code resolving to the link through `/s/{slug}`
+    #[schema(example = "aZ3k9Qx")]
+    pub slug: String,
+    /// When the link was last clicked, if ever
+    #[schema(example = "2024-03-12T09:30:00Z")]
+    pub last_clicked_at: Option<DateTime<Utc>>,
+    /// When the link expires and stops resolving, if it is temporary
+    #[schema(example = "2024-04-10T15:00:00Z")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether the link's target was reachable when last checked
+    pub health: LinkHealth,
+    /// When the link's target was last checked
+    #[schema(example = "2024-03-12T04:00:00Z")]
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Whether the preview is still being fetched; clients can stop polling once it is
+    /// `ready` or `failed`
+    pub preview_status: PreviewStatus,
+    /// Collection the link is filed under, if any
+    #[schema(example = "3fa85f64-5717-4562-b3fc-2c963f66afa6")]
+    pub collection_id: Option<Uuid>,
+    /// Image chosen by the owner, shown instead of the preview's image
+    #[schema(example = "https://example.com/cover.png")]
+    pub custom_image_url: Option<String>,
+    /// Whether clicks on the link are counted and recorded; when off, the link still resolves
+    /// but leaves no analytics behind
+    pub track_clicks: bool,
+    /// Private notes of the owner; left out of responses to anyone else
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "Check the 2024 edition guide before sharing")]
+    pub notes: Option<String>,
+    /// When the link becomes visible to others; until then only its owner can see it
+    #[schema(example = "2030-01-01T09:00:00Z")]
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Whether the link is waiting for `publish_at`, so only its owner can see it
+    pub scheduled: bool,
     /// When the link was created
     #[schema(example = "2024-03-10T15:00:00Z")]
     pub created_at: DateTime<Utc>,
@@ -101,12 +497,74 @@ pub struct Link {
     pub updated_at: DateTime<Utc>,
     /// Preview metadata from the link
     #[serde(with = "preview_serde")]
+    #[sqlx(try_from = "JsonLinkPreview")]
     pub preview: Option<LinkPreview>,
     /// User who created the link
     #[serde(with = "user_serde")]
+    #[sqlx(try_from = "OptionalJsonUser")]
     pub user: Option<SimpleUser>,
 }
 
+impl Link {
+    /// The image to show for the link: the owner's custom image, else the preview's
+    pub fn image_url(&self) -> Option<&str> {
+        self.custom_image_url.as_deref().or_else(|| {
+            self.preview
+                .as_ref()
+                .and_then(|preview| preview.image.as_deref())
+        })
+    }
+
+    /// Whether `viewer_id` may see the link: its owner always can, anyone else only once
+    /// it is public and published
+    pub fn is_visible_to(&self, viewer_id: Option<Uuid>) -> bool {
+        let published = self
+            .publish_at
+            .is_none_or(|publish_at| publish_at <= Utc::now());
+        (self.visibility == LinkVisibility::Public && published)
+            || viewer_id.is_some_and(|id| self.user_id == Some(id))
+    }
+
+    /// The link as `viewer_id` may see it, with owner-only fields stripped unless they own it
+    ///
+    /// Every response that can show a link to someone other than its owner must pass it
+    /// through here.
+    pub fn redacted_for(mut self, viewer_id: Option<Uuid>) -> Self {
+        if viewer_id.is_none() || self.user_id != viewer_id {
+            self.notes = None;
+        }
+        self
+    }
+}
+
+impl Keyed for Link {
+    fn key(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// A link as reported to sync clients, deleted ones included
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct SyncedLink {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub link: Link,
+    /// True for a tombstone: the link was deleted and clients should drop their copy
+    #[schema(example = false)]
+    pub deleted: bool,
+}
+
+/// A link with the clicks it received within a leaderboard's period
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct LeaderboardEntry {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub link: Link,
+    /// Clicks within the period, leaving out bots
+    #[schema(example = 128)]
+    pub period_clicks: i64,
+}
+
 // Custom serialization for preview field to handle JSON conversion
 mod preview_serde {
     use super::*;
@@ -0,0 +1,197 @@
+use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder};
+use std::{fmt, str::FromStr};
+use uuid::Uuid;
+
+/// Columns a listing is ordered by, all in the same direction
+///
+/// The columns are compared as one row value to find where a page starts, which is why
+/// they can't mix directions. The last column must be unique, normally the ID, so every
+/// row has exactly one position.
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey {
+    columns: &'static [&'static str],
+    descending: bool,
+}
+
+impl SortKey {
+    pub const fn ascending(columns: &'static [&'static str]) -> Self {
+        Self {
+            columns,
+            descending: false,
+        }
+    }
+
+    pub const fn descending(columns: &'static [&'static str]) -> Self {
+        Self {
+            columns,
+            descending: true,
+        }
+    }
+}
+
+/// Where a page starts, relative to a row of the previous or next page
+///
+/// Written as `after:<id>` or `before:<id>`. Clients are told to treat it as opaque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    /// The page holds the rows following this one
+    After(Uuid),
+    /// The page holds the rows leading up to this one
+    Before(Uuid),
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cursor::After(id) => write!(f, "after:{id}"),
+            Cursor::Before(id) => write!(f, "before:{id}"),
+        }
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (direction, id) = value.split_once(':').ok_or(())?;
+        let id = id.parse::<Uuid>().map_err(|_| ())?;
+        match direction {
+            "after" => Ok(Cursor::After(id)),
+            "before" => Ok(Cursor::Before(id)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Rows that can be paged through, identified by the key column of their query
+pub trait Keyed {
+    fn key(&self) -> Uuid;
+}
+
+/// One page of rows plus the cursors of the pages around it
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of the following page; None on the last page or when not paging
+    pub next_cursor: Option<Cursor>,
+    /// Cursor of the preceding page; None on the first page or when not paging
+    pub prev_cursor: Option<Cursor>,
+}
+
+/// Builds a filtered, sorted listing and fetches it a page at a time
+///
+/// Filters are written by the query function using it, so only conditions the code
+/// knows about reach the SQL, and sorts are picked from a fixed [`SortKey`] per option.
+/// Pages are keyed on the sort columns of the cursor's row rather than an offset, so rows
+/// added or removed while paging don't shift later pages.
+pub struct PaginatedQuery<'args> {
+    query: QueryBuilder<'args, Postgres>,
+    has_filter: bool,
+    source: &'static str,
+    key_column: &'static str,
+    sort: SortKey,
+}
+
+impl<'args> PaginatedQuery<'args> {
+    /// Starts a listing from its `SELECT ... FROM ...` part, without a `WHERE` clause
+    ///
+    /// A cursor's position is read with `SELECT <sort columns> FROM {source} WHERE
+    /// {key_column} = <id>`, so `source` must give the table the alias the sort columns use.
+    pub fn new(
+        select: &str,
+        source: &'static str,
+        key_column: &'static str,
+        sort: SortKey,
+    ) -> Self {
+        Self {
+            query: QueryBuilder::new(select),
+            has_filter: false,
+            source,
+            key_column,
+            sort,
+        }
+    }
+
+    /// Starts another `WHERE` condition, joined to the previous ones with `AND`
+    pub fn filter(&mut self) -> &mut QueryBuilder<'args, Postgres> {
+        self.query
+            .push(if self.has_filter { " AND " } else { " WHERE " });
+        self.has_filter = true;
+        &mut self.query
+    }
+
+    /// Fetches the page at `cursor`, or the first page without one
+    ///
+    /// Without a `limit` every matching row is returned and no cursors are set.
+    pub async fn fetch_page<T>(
+        mut self,
+        pool: &PgPool,
+        cursor: Option<Cursor>,
+        limit: Option<i64>,
+    ) -> Result<Page<T>, sqlx::Error>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Keyed + Send + Unpin,
+    {
+        let backwards = matches!(cursor, Some(Cursor::Before(_)));
+        // Reading backwards flips the order; the rows are put back in order below
+        let descending = self.sort.descending != backwards;
+        let columns = self.sort.columns.join(", ");
+
+        if let Some(Cursor::After(id) | Cursor::Before(id)) = cursor {
+            let (source, key_column) = (self.source, self.key_column);
+            self.filter()
+                .push(format_args!(
+                    "({columns}) {} (SELECT {columns} FROM {source} WHERE {key_column} = ",
+                    if descending { "<" } else { ">" }
+                ))
+                .push_bind(id)
+                .push(")");
+        }
+
+        let direction = if descending { " DESC" } else { " ASC" };
+        self.query.push(" ORDER BY ");
+        let mut order = self.query.separated(", ");
+        for column in self.sort.columns {
+            order.push(format_args!("{column}{direction}"));
+        }
+        if let Some(limit) = limit {
+            // One extra row tells whether there is another page
+            self.query.push(" LIMIT ").push_bind(limit + 1);
+        }
+
+        let mut items: Vec<T> = self.query.build_query_as().fetch_all(pool).await?;
+        let Some(limit) = limit else {
+            return Ok(Page {
+                items,
+                next_cursor: None,
+                prev_cursor: None,
+            });
+        };
+
+        let has_more = items.len() as i64 > limit;
+        items.truncate(usize::try_from(limit).unwrap_or_default());
+        if backwards {
+            items.reverse();
+        }
+
+        let first = items.first().map(|item| item.key());
+        let last = items.last().map(|item| item.key());
+        let (next_cursor, prev_cursor) = if backwards {
+            (
+                last.map(Cursor::After),
+                first.filter(|_| has_more).map(Cursor::Before),
+            )
+        } else {
+            (
+                last.filter(|_| has_more).map(Cursor::After),
+                first.filter(|_| cursor.is_some()).map(Cursor::Before),
+            )
+        };
+
+        Ok(Page {
+            items,
+            next_cursor,
+            prev_cursor,
+        })
+    }
+}
@@ -1,227 +1,887 @@
-use super::models::{JsonLinkPreview, Link, LinkPreview, OptionalJsonUser};
-use chrono::Utc;
-use sqlx::PgPool;
+use super::audit::record_audit_event;
+use super::models::{
+    AuditAction, ClickEvent, ClickStat, Collection, IdempotencyRecord, JsonLinkPreview,
+    LeaderboardEntry, Link, LinkHealth, LinkPreview, LinkQuota, LinkThumbnail, LinkTotals,
+    LinkVisibility, OptionalJsonUser, PreviewStatus, PreviewValidators, SyncedLink, TagCount,
+    ThumbnailVariant, Webhook,
+};
+use super::pagination::{Cursor, Page, PaginatedQuery, SortKey};
+use crate::models::auth::{UserProfile, UserRole, UserStatus, UserSummary};
+use crate::services::url::{dedupe_key, generate_slug};
+use chrono::{DateTime, Utc};
+use futures_util::stream::BoxStream;
+use serde_json::json;
+use sqlx::{PgConnection, PgExecutor, PgPool, Postgres, QueryBuilder};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
-/// Retrieves all links from the database
+/// Runs `f` in a transaction, committing if it succeeds and rolling back if it fails
+///
+/// Queries that take several statements use this so their result is one consistent
+/// snapshot. `f` gets the transaction's connection; pass it as `&mut *conn`.
+pub async fn with_transaction<T, F>(pool: &PgPool, f: F) -> Result<T, sqlx::Error>
+where
+    F: AsyncFnOnce(&mut PgConnection) -> Result<T, sqlx::Error>,
+{
+    let mut tx = pool.begin().await?;
+    let value = f(&mut tx).await?;
+    tx.commit().await?;
+    Ok(value)
+}
+
+/// Filters applied when listing links
+#[derive(Debug, Default, Clone)]
+pub struct LinkFilters {
+    /// Only include links carrying all of these lowercased tags
+    pub tags: Vec<String>,
+    /// The user viewing the list; their private links are included alongside public ones
+    pub viewer_id: Option<Uuid>,
+    /// Only include links created at or after this instant
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only include links created at or before this instant
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only include the viewer's own links in this health state
+    pub health: Option<LinkHealth>,
+    /// Order in which links are returned
+    pub sort: LinkSort,
+    /// Maximum number of links to return; all of them when None
+    pub limit: Option<i64>,
+    /// Position of the page to return, from a previous page
+    pub cursor: Option<Cursor>,
+}
+
+/// Supported orderings for link listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkSort {
+    CreatedAsc,
+    #[default]
+    CreatedDesc,
+    ClicksDesc,
+    TitleAsc,
+    RecentlyClicked,
+}
+
+impl LinkSort {
+    /// The query parameter value for this ordering
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkSort::CreatedAsc => "created_asc",
+            LinkSort::CreatedDesc => "created_desc",
+            LinkSort::ClicksDesc => "clicks_desc",
+            LinkSort::TitleAsc => "title_asc",
+            LinkSort::RecentlyClicked => "recently_clicked",
+        }
+    }
+
+    /// Columns links are ordered by, ending in the ID so pages have a stable order
+    fn key(self) -> SortKey {
+        match self {
+            LinkSort::CreatedAsc => SortKey::ascending(&["l.created_at", "l.id"]),
+            LinkSort::CreatedDesc => SortKey::descending(&["l.created_at", "l.id"]),
+            LinkSort::ClicksDesc => SortKey::descending(&["l.click_count", "l.created_at", "l.id"]),
+            LinkSort::TitleAsc => SortKey::ascending(&["lower(l.title)", "l.id"]),
+            // Links that were never clicked sort last
+            LinkSort::RecentlyClicked => SortKey::descending(&[
+                "COALESCE(l.last_clicked_at, '-infinity'::timestamptz)",
+                "l.created_at",
+                "l.id",
+            ]),
+        }
+    }
+}
+
+impl std::str::FromStr for LinkSort {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "created_asc" => Ok(LinkSort::CreatedAsc),
+            "created_desc" => Ok(LinkSort::CreatedDesc),
+            "clicks_desc" => Ok(LinkSort::ClicksDesc),
+            "title_asc" => Ok(LinkSort::TitleAsc),
+            "recently_clicked" => Ok(LinkSort::RecentlyClicked),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Retrieves a page of links from the database
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `filters` - Optional filters narrowing down the result, and the page to return
 ///
 /// # Returns
-/// * `Result<Vec<Link>, sqlx::Error>` - A list of all links or an error
-pub async fn get_all_links(pool: &PgPool) -> Result<Vec<Link>, sqlx::Error> {
-    sqlx::query_as!(
-        Link,
+/// * `Result<Page<Link>, sqlx::Error>` - The matching links with the cursors around them,
+///   or an error
+pub async fn get_all_links(
+    pool: &PgPool,
+    filters: &LinkFilters,
+) -> Result<Page<Link>, sqlx::Error> {
+    let mut query = PaginatedQuery::new(
         r#"
-        SELECT 
+        SELECT
             l.id,
-            l.url as "url!",
-            l.title as "title!",
-            l.description as "description!",
-            l.user_id as "user_id!",
-            l.click_count as "click_count!",
-            l.created_at as "created_at!",
-            l.updated_at as "updated_at!",
-            l.preview as "preview: JsonLinkPreview",
+            l.url,
+            l.original_url,
+            l.title,
+            l.description,
+            l.user_id,
+            l.click_count,
+            l.created_at,
+            l.updated_at,
+            l.preview,
+            l.tags,
+            l.visibility,
+            l.slug,
+            l.last_clicked_at,
+            l.expires_at,
+            l.health,
+            l.last_checked_at,
+            l.preview_status,
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks,
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as scheduled,
             COALESCE(
                 jsonb_build_object('username', u.username)::jsonb,
                 'null'::jsonb
-            ) as "user!: OptionalJsonUser"
+            ) as user
         FROM links l
         LEFT JOIN users u ON l.user_id = u.id
-        ORDER BY l.created_at DESC
-        "#
-    )
-    .fetch_all(pool)
-    .await
+        "#,
+        "links l",
+        "l.id",
+        filters.sort.key(),
+    );
+
+    query
+        .filter()
+        .push("l.deleted_at IS NULL AND (l.expires_at IS NULL OR l.expires_at > NOW())");
+    if !filters.tags.is_empty() {
+        query.filter().push("l.tags @> ").push_bind(&filters.tags);
+    }
+    query
+        .filter()
+        .push("(l.visibility = 'public' OR l.user_id = ")
+        .push_bind(filters.viewer_id)
+        .push(")");
+    query
+        .filter()
+        .push("(l.publish_at IS NULL OR l.publish_at <= NOW() OR l.user_id = ")
+        .push_bind(filters.viewer_id)
+        .push(")");
+    if let Some(created_after) = filters.created_after {
+        query
+            .filter()
+            .push("l.created_at >= ")
+            .push_bind(created_after);
+    }
+    if let Some(created_before) = filters.created_before {
+        query
+            .filter()
+            .push("l.created_at <= ")
+            .push_bind(created_before);
+    }
+    if let Some(health) = filters.health {
+        query
+            .filter()
+            .push("l.health = ")
+            .push_bind(health)
+            .push(" AND l.user_id = ")
+            .push_bind(filters.viewer_id);
+    }
+
+    query.fetch_page(pool, filters.cursor, filters.limit).await
 }
 
-/// Creates a new link in the database
+/// Retrieves a user's links that changed after a point in time, oldest change first
+///
+/// Soft-deleted links are included as tombstones. Every write to a link, deleting it
+/// included, bumps `updated_at`, so it is the only column compared. Pages are keyed on
+/// `(updated_at, id)` values rather than on a cursor row, because a row's position moves
+/// whenever it changes again.
 ///
 /// # Arguments
 /// * `pool` - Database connection pool
-/// * `url` - The URL to be added
-/// * `title` - The title of the link
-/// * `description` - A description of the link
-/// * `user_id` - The ID of the user creating the link
-/// * `preview` - The preview of the link
+/// * `user_id` - The ID of the link owner
+/// * `since` - Only include links changed after this instant
+/// * `after_id` - Among links changed exactly at `since`, only include those with a greater ID
+/// * `limit` - Maximum number of links to return
 ///
 /// # Returns
-/// * `Result<Link, sqlx::Error>` - The created link or an error
-pub async fn create_link(
+/// * `Result<Vec<SyncedLink>, sqlx::Error>` - The changed links or an error
+pub async fn get_links_changed_since(
     pool: &PgPool,
-    url: String,
-    title: String,
-    description: String,
     user_id: Uuid,
-    preview: Option<&LinkPreview>,
-) -> Result<Link, sqlx::Error> {
-    let now = Utc::now();
-    let preview_json = JsonLinkPreview::from(preview);
-
-    sqlx::query_as!(
-        Link,
+    since: DateTime<Utc>,
+    after_id: Uuid,
+    limit: i64,
+) -> Result<Vec<SyncedLink>, sqlx::Error> {
+    sqlx::query_as::<_, SyncedLink>(
         r#"
-        WITH inserted_link AS (
-            INSERT INTO links (url, title, description, user_id, created_at, updated_at, preview)
-            VALUES ($1, $2, $3, $4, $5, $5, $6)
-            RETURNING *
-        )
-        SELECT 
+        SELECT
             l.id,
-            l.url as "url!",
-            l.title as "title!",
-            l.description as "description!",
-            l.user_id as "user_id!",
-            l.click_count as "click_count!",
-            l.created_at as "created_at!",
-            l.updated_at as "updated_at!",
-            l.preview as "preview: JsonLinkPreview",
+            l.url,
+            l.original_url,
+            l.title,
+            l.description,
+            l.user_id,
+            l.click_count,
+            l.created_at,
+            l.updated_at,
+            l.preview,
+            l.tags,
+            l.visibility,
+            l.slug,
+            l.last_clicked_at,
+            l.expires_at,
+            l.health,
+            l.last_checked_at,
+            l.preview_status,
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks,
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as scheduled,
             COALESCE(
                 jsonb_build_object('username', u.username)::jsonb,
                 'null'::jsonb
-            ) as "user!: OptionalJsonUser"
-        FROM inserted_link l
+            ) as user,
+            l.deleted_at IS NOT NULL as deleted
+        FROM links l
         LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.user_id = $1
+            AND (l.updated_at, l.id) > ($2, $3)
+        ORDER BY l.updated_at ASC, l.id ASC
+        LIMIT $4
         "#,
-        url,
-        title,
-        description,
-        user_id,
-        now,
-        preview_json as _
     )
-    .fetch_one(pool)
+    .bind(user_id)
+    .bind(since)
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(pool)
     .await
 }
 
-/// Increment the click count for a link
+/// Counts the links matching the given filters
+///
+/// Uses the same conditions as [`get_all_links`] so the total matches what a listing returns;
+/// `limit` and `cursor` are ignored.
 ///
 /// # Arguments
 /// * `pool` - Database connection pool
-/// * `link_id` - The ID of the link
+/// * `filters` - Filters narrowing down the count
 ///
 /// # Returns
-/// * `Result<(), sqlx::Error>` - Success or error
-pub async fn increment_click_count(pool: &PgPool, link_id: Uuid) -> Result<(), sqlx::Error> {
-    sqlx::query!(
+/// * `Result<i64, sqlx::Error>` - The number of matching links or an error
+pub async fn get_links_count(pool: &PgPool, filters: &LinkFilters) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
         r#"
-        UPDATE links 
-        SET click_count = click_count + 1 
-        WHERE id = $1
+        SELECT COUNT(*) as "count!"
+        FROM links l
+        WHERE l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND l.tags @> $1::text[]
+            AND (l.visibility = 'public' OR l.user_id = $2)
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW() OR l.user_id = $2)
+            AND l.created_at BETWEEN COALESCE($3, '-infinity'::timestamptz)
+                AND COALESCE($4, 'infinity'::timestamptz)
+            AND ($5::link_health IS NULL OR (l.health = $5 AND l.user_id = $2))
         "#,
-        link_id
+        &filters.tags,
+        filters.viewer_id,
+        filters.created_after,
+        filters.created_before,
+        filters.health as _
     )
-    .execute(pool)
-    .await?;
-
-    Ok(())
+    .fetch_one(pool)
+    .await
 }
 
-/// Deletes a link from the database
+/// Lists every tag on the links a viewer can see, most used first
 ///
 /// # Arguments
 /// * `pool` - Database connection pool
-/// * `link_id` - The ID of the link to delete
+/// * `viewer_id` - The user viewing the list; tags on their private links are included
 ///
 /// # Returns
-/// * `Result<(), sqlx::Error>` - Success or error
-pub async fn delete_link(pool: &PgPool, link_id: Uuid) -> Result<(), sqlx::Error> {
-    sqlx::query!("DELETE FROM links WHERE id = $1", link_id)
-        .execute(pool)
-        .await?;
+/// * `Result<Vec<TagCount>, sqlx::Error>` - Tags with their usage counts or an error
+pub async fn get_tag_counts(
+    pool: &PgPool,
+    viewer_id: Option<Uuid>,
+) -> Result<Vec<TagCount>, sqlx::Error> {
+    sqlx::query_as!(
+        TagCount,
+        r#"
+        SELECT tag as "tag!", COUNT(*) as "count!"
+        FROM links l, unnest(l.tags) AS tag
+        WHERE l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND (l.visibility = 'public' OR l.user_id = $1)
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW() OR l.user_id = $1)
+        GROUP BY tag
+        ORDER BY 2 DESC, tag ASC
+        "#,
+        viewer_id
+    )
+    .fetch_all(pool)
+    .await
+}
 
-    Ok(())
+/// Fields required to insert a new link
+#[derive(Debug, Clone)]
+pub struct NewLink {
+    /// The normalized URL to be added
+    pub url: String,
+    /// The URL as submitted by the user
+    pub original_url: String,
+    pub title: String,
+    pub description: String,
+    /// The ID of the user creating the link; `None` for anonymous links
+    pub user_id: Option<Uuid>,
+    /// SHA-256 of the token that lets someone claim an anonymous link
+    pub claim_token_hash: Option<String>,
+    /// Collection the link is filed under; must belong to the same user
+    pub collection_id: Option<Uuid>,
+    /// Normalized tags for the link
+    pub tags: Vec<String>,
+    pub visibility: LinkVisibility,
+    /// Custom short code; a random one is generated when absent
+    pub slug: Option<String>,
+    /// When the link stops resolving; `None` keeps it forever
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether clicks on the link are counted
+    pub track_clicks: bool,
+    /// Private notes of the owner
+    pub notes: Option<String>,
+    /// When the link becomes visible to others; `None` publishes it right away
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
-/// Retrieves a single link by its ID
+/// How many random slugs to try before giving up on a link insert
+const MAX_SLUG_ATTEMPTS: u32 = 5;
+
+/// Whether an error was caused by a slug already taken by another link
+pub fn is_slug_conflict(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|e| e.is_unique_violation() && e.constraint() == Some("links_slug_key"))
+}
+
+/// Creates a new link in the database
 ///
 /// # Arguments
 /// * `pool` - Database connection pool
-/// * `link_id` - The ID of the link to fetch
+/// * `new_link` - The fields of the link to create
+/// * `preview` - The preview of the link, if it is already known
 ///
 /// # Returns
-/// * `Result<Option<Link>, sqlx::Error>` - The link if found, None if not found, or an error
-pub async fn get_link_by_id(pool: &PgPool, link_id: Uuid) -> Result<Option<Link>, sqlx::Error> {
+/// * `Result<Link, sqlx::Error>` - The created link or an error
+pub async fn create_link(
+    pool: &PgPool,
+    new_link: NewLink,
+    preview: Option<&LinkPreview>,
+) -> Result<Link, sqlx::Error> {
+    // A custom slug is tried once; generated ones are retried on collision
+    let mut attempt = 1;
+    loop {
+        let slug = new_link.slug.clone().unwrap_or_else(generate_slug);
+        match insert_link(pool, &new_link, &slug, preview).await {
+            Err(e)
+                if new_link.slug.is_none()
+                    && attempt < MAX_SLUG_ATTEMPTS
+                    && is_slug_conflict(&e) =>
+            {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn insert_link(
+    pool: &PgPool,
+    new_link: &NewLink,
+    slug: &str,
+    preview: Option<&LinkPreview>,
+) -> Result<Link, sqlx::Error> {
+    let now = Utc::now();
+    let preview_json = JsonLinkPreview::from(preview);
+    // A link created with its preview has nothing left to fetch
+    let preview_status = if preview.is_some() {
+        PreviewStatus::Ready
+    } else {
+        PreviewStatus::Pending
+    };
+
     sqlx::query_as!(
         Link,
         r#"
+        WITH inserted_link AS (
+            INSERT INTO links (url, original_url, title, description, user_id, created_at, updated_at, preview, tags, visibility, slug, expires_at, claim_token_hash, collection_id, preview_status, track_clicks, notes, publish_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+            RETURNING *
+        )
         SELECT 
             l.id,
             l.url as "url!",
+            l.original_url as "original_url!",
             l.title as "title!",
             l.description as "description!",
-            l.user_id as "user_id!",
+            l.user_id as "user_id?",
             l.click_count as "click_count!",
             l.created_at as "created_at!",
             l.updated_at as "updated_at!",
             l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
             COALESCE(
                 jsonb_build_object('username', u.username)::jsonb,
                 'null'::jsonb
             ) as "user!: OptionalJsonUser"
-        FROM links l
+        FROM inserted_link l
         LEFT JOIN users u ON l.user_id = u.id
-        WHERE l.id = $1
         "#,
-        link_id
+        new_link.url,
+        new_link.original_url,
+        new_link.title,
+        new_link.description,
+        new_link.user_id,
+        now,
+        preview_json as _,
+        &new_link.tags,
+        new_link.visibility as _,
+        slug,
+        new_link.expires_at,
+        new_link.claim_token_hash,
+        new_link.collection_id,
+        preview_status as _,
+        new_link.track_clicks,
+        new_link.notes,
+        new_link.publish_at
     )
-    .fetch_optional(pool)
+    .fetch_one(pool)
     .await
 }
 
-pub async fn check_user_exists(
+/// Editable fields of an existing link
+#[derive(Debug, Clone)]
+pub struct LinkUpdate {
+    /// The normalized URL
+    pub url: String,
+    /// The URL as submitted by the user
+    pub original_url: String,
+    pub title: String,
+    pub description: String,
+    /// Normalized tags for the link
+    pub tags: Vec<String>,
+    pub visibility: LinkVisibility,
+    /// New custom short code; the current slug is kept when absent
+    pub slug: Option<String>,
+    pub track_clicks: bool,
+    pub notes: Option<String>,
+    /// `None` makes the link permanent
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl LinkUpdate {
+    /// Names of the fields the update writes, for the audit log
+    fn fields(&self) -> Vec<&'static str> {
+        let mut fields = vec![
+            "url",
+            "title",
+            "description",
+            "tags",
+            "visibility",
+            "track_clicks",
+            "notes",
+            "expires_at",
+        ];
+        if self.slug.is_some() {
+            fields.push("slug");
+        }
+        fields
+    }
+}
+
+/// Updates the editable fields of a link
+///
+/// The update and the owner lookup are a single statement, so the returned link is one
+/// consistent snapshot. The change is recorded in the audit log in the same transaction.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to update
+/// * `update` - The new values of the editable fields
+/// * `actor_id` - The user making the change
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The updated link, None if not found, or an error
+pub async fn update_link(
     pool: &PgPool,
-    email: &str,
-    username: &str,
-) -> Result<bool, sqlx::Error> {
-    let count = if username.is_empty() {
-        // Only check email
-        sqlx::query!(
+    link_id: Uuid,
+    update: LinkUpdate,
+    actor_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    let fields = update.fields();
+    with_transaction(pool, async |conn| {
+        let link = sqlx::query_as!(
+            Link,
             r#"
-            SELECT COUNT(*) as count
-            FROM users
-            WHERE email = $1
+            WITH updated_link AS (
+                UPDATE links
+                SET url = $2, original_url = $3, title = $4, description = $5, tags = $6, visibility = $7,
+                    slug = COALESCE($8, slug), track_clicks = $9,
+                    notes = $10, expires_at = $11
+                WHERE id = $1 AND deleted_at IS NULL
+                RETURNING *
+            )
+            SELECT
+                l.id,
+                l.url as "url!",
+                l.original_url as "original_url!",
+                l.title as "title!",
+                l.description as "description!",
+                l.user_id as "user_id?",
+                l.click_count as "click_count!",
+                l.created_at as "created_at!",
+                l.updated_at as "updated_at!",
+                l.preview as "preview: JsonLinkPreview",
+                l.tags as "tags!",
+                l.visibility as "visibility!: LinkVisibility",
+                l.slug as "slug!",
+                l.last_clicked_at,
+                l.expires_at,
+                l.health as "health!: LinkHealth",
+                l.last_checked_at,
+                l.preview_status as "preview_status!: PreviewStatus",
+                l.collection_id,
+                l.custom_image_url,
+                l.track_clicks as "track_clicks!",
+                l.notes,
+                l.publish_at,
+                (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+                COALESCE(
+                    jsonb_build_object('username', u.username)::jsonb,
+                    'null'::jsonb
+                ) as "user!: OptionalJsonUser"
+            FROM updated_link l
+            LEFT JOIN users u ON l.user_id = u.id
             "#,
-            email
+            link_id,
+            update.url,
+            update.original_url,
+            update.title,
+            update.description,
+            &update.tags,
+            update.visibility as _,
+            update.slug,
+            update.track_clicks,
+            update.notes,
+            update.expires_at
         )
-        .fetch_one(pool)
-        .await?
-        .count
-        .unwrap_or(0)
-    } else {
-        // Check both email and username
-        sqlx::query!(
-            r#"
-            SELECT COUNT(*) as count
-            FROM users
-            WHERE email = $1 OR username = $2
-            "#,
-            email,
-            username
+        .fetch_optional(&mut *conn)
+        .await?;
+        if link.is_some() {
+            record_audit_event(
+                conn,
+                actor_id,
+                AuditAction::LinkUpdated,
+                link_id,
+                json!({ "fields": fields }),
+            )
+            .await?;
+        }
+        Ok(link)
+    })
+    .await
+}
+
+/// Fields to change in a partial link update; `None` leaves the column untouched
+#[derive(Debug, Default)]
+pub struct LinkPatch {
+    /// The normalized URL, set together with `original_url`
+    pub url: Option<String>,
+    /// The URL as submitted by the user
+    pub original_url: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Normalized tags for the link
+    pub tags: Option<Vec<String>>,
+    pub visibility: Option<LinkVisibility>,
+    pub slug: Option<String>,
+    /// `Some(None)` takes the link out of its collection
+    pub collection_id: Option<Option<Uuid>>,
+    /// `Some(None)` goes back to the preview's image
+    pub custom_image_url: Option<Option<String>>,
+    pub track_clicks: Option<bool>,
+    /// `Some(None)` clears the notes
+    pub notes: Option<Option<String>>,
+}
+
+impl LinkPatch {
+    fn is_empty(&self) -> bool {
+        self.url.is_none()
+            && self.original_url.is_none()
+            && self.title.is_none()
+            && self.description.is_none()
+            && self.tags.is_none()
+            && self.visibility.is_none()
+            && self.slug.is_none()
+            && self.collection_id.is_none()
+            && self.custom_image_url.is_none()
+            && self.track_clicks.is_none()
+            && self.notes.is_none()
+    }
+
+    /// Names of the fields the patch writes, for the audit log
+    fn fields(&self) -> Vec<&'static str> {
+        [
+            ("url", self.url.is_some()),
+            ("title", self.title.is_some()),
+            ("description", self.description.is_some()),
+            ("tags", self.tags.is_some()),
+            ("visibility", self.visibility.is_some()),
+            ("slug", self.slug.is_some()),
+            ("collection_id", self.collection_id.is_some()),
+            ("custom_image_url", self.custom_image_url.is_some()),
+            ("track_clicks", self.track_clicks.is_some()),
+            ("notes", self.notes.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+}
+
+/// Updates only the provided fields of a link
+///
+/// The update, its audit log entry and reading back the updated link run in one
+/// transaction, so the result is exactly what was written even if the link changes right after.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to update
+/// * `patch` - The fields to change
+/// * `actor_id` - The user making the change
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The updated link, None if it doesn't exist, or an error
+pub async fn patch_link(
+    pool: &PgPool,
+    link_id: Uuid,
+    patch: LinkPatch,
+    actor_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    if patch.is_empty() {
+        return get_link_by_id(pool, link_id).await;
+    }
+
+    let fields = patch.fields();
+
+    let mut builder = QueryBuilder::<Postgres>::new("UPDATE links SET ");
+    let mut set = builder.separated(", ");
+    if let Some(url) = patch.url {
+        set.push("url = ").push_bind_unseparated(url);
+    }
+    if let Some(original_url) = patch.original_url {
+        set.push("original_url = ")
+            .push_bind_unseparated(original_url);
+    }
+    if let Some(title) = patch.title {
+        set.push("title = ").push_bind_unseparated(title);
+    }
+    if let Some(description) = patch.description {
+        set.push("description = ")
+            .push_bind_unseparated(description);
+    }
+    if let Some(tags) = patch.tags {
+        set.push("tags = ").push_bind_unseparated(tags);
+    }
+    if let Some(visibility) = patch.visibility {
+        set.push("visibility = ").push_bind_unseparated(visibility);
+    }
+    if let Some(slug) = patch.slug {
+        set.push("slug = ").push_bind_unseparated(slug);
+    }
+    if let Some(collection_id) = patch.collection_id {
+        set.push("collection_id = ")
+            .push_bind_unseparated(collection_id);
+    }
+    if let Some(custom_image_url) = patch.custom_image_url {
+        set.push("custom_image_url = ")
+            .push_bind_unseparated(custom_image_url);
+    }
+    if let Some(track_clicks) = patch.track_clicks {
+        set.push("track_clicks = ")
+            .push_bind_unseparated(track_clicks);
+    }
+    if let Some(notes) = patch.notes {
+        set.push("notes = ").push_bind_unseparated(notes);
+    }
+    builder
+        .push(" WHERE id = ")
+        .push_bind(link_id)
+        .push(" AND deleted_at IS NULL RETURNING id");
+
+    with_transaction(pool, async |conn| {
+        let updated: Option<Uuid> = builder
+            .build_query_scalar()
+            .fetch_optional(&mut *conn)
+            .await?;
+        let Some(link_id) = updated else {
+            return Ok(None);
+        };
+        record_audit_event(
+            &mut *conn,
+            actor_id,
+            AuditAction::LinkUpdated,
+            link_id,
+            json!({ "fields": fields }),
         )
-        .fetch_one(pool)
-        .await?
-        .count
-        .unwrap_or(0)
-    };
+        .await?;
+        get_link_by_id(&mut *conn, link_id).await
+    })
+    .await
+}
 
-    Ok(count > 0)
+/// A counted click waiting in the click buffer to be written
+#[derive(Debug, Clone)]
+pub struct BufferedClick {
+    pub link_id: Uuid,
+    /// The `Referer` header sent with the click, if any
+    pub referrer: Option<String>,
+    /// The `User-Agent` header sent with the click, if any
+    pub user_agent: Option<String>,
+    /// Salted hash of the client IP
+    pub ip_hash: String,
+    pub clicked_at: DateTime<Utc>,
 }
 
-#[allow(dead_code)]
-pub async fn create_unverified_user(
+/// Click events inserted per statement, keeping the bind parameters well under Postgres' limit
+const CLICK_INSERT_CHUNK: usize = 1000;
+
+/// Counts a batch of buffered clicks and records their click events, in one transaction
+///
+/// Each link's count is raised by all of its clicks in a single `UPDATE ... FROM (VALUES ...)`,
+/// so a popular link costs one row update per flush instead of one per click. The counter
+/// updates and the event inserts commit or fail together, so click counts always equal the
+/// number of counted events. Clicks on links that no longer exist or were deleted are dropped.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `clicks` - The clicks to write
+///
+/// # Returns
+/// * `Result<Vec<Uuid>, sqlx::Error>` - The IDs of the links whose counts changed, or an error
+pub async fn flush_clicks(
     pool: &PgPool,
-    email: &str,
-    username: &str,
-    password_hash: &str,
+    clicks: &[BufferedClick],
+) -> Result<Vec<Uuid>, sqlx::Error> {
+    let mut per_link: HashMap<Uuid, (i32, DateTime<Utc>)> = HashMap::new();
+    for click in clicks {
+        let (count, last_clicked_at) = per_link
+            .entry(click.link_id)
+            .or_insert((0, click.clicked_at));
+        *count += 1;
+        *last_clicked_at = (*last_clicked_at).max(click.clicked_at);
+    }
+    if per_link.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    with_transaction(pool, async |conn| {
+        let mut update = QueryBuilder::<Postgres>::new(
+            "UPDATE links AS l SET click_count = l.click_count + v.clicks, \
+             last_clicked_at = GREATEST(l.last_clicked_at, v.last_clicked_at) FROM (",
+        );
+        update.push_values(&per_link, |mut row, (link_id, (count, last_clicked_at))| {
+            row.push_bind(*link_id)
+                .push_bind(*count)
+                .push_bind(*last_clicked_at);
+        });
+        update.push(
+            ") AS v(link_id, clicks, last_clicked_at) \
+             WHERE l.id = v.link_id AND l.deleted_at IS NULL RETURNING l.id",
+        );
+        let counted: HashSet<Uuid> = update
+            .build_query_scalar()
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .collect();
+
+        let counted_clicks: Vec<&BufferedClick> = clicks
+            .iter()
+            .filter(|click| counted.contains(&click.link_id))
+            .collect();
+        for chunk in counted_clicks.chunks(CLICK_INSERT_CHUNK) {
+            let mut insert = QueryBuilder::<Postgres>::new(
+                "INSERT INTO link_clicks (link_id, referrer, user_agent, ip_hash, is_bot, clicked_at) ",
+            );
+            insert.push_values(chunk, |mut row, click| {
+                row.push_bind(click.link_id)
+                    .push_bind(click.referrer.as_deref())
+                    .push_bind(click.user_agent.as_deref())
+                    .push_bind(click.ip_hash.as_str())
+                    .push_bind(false)
+                    .push_bind(click.clicked_at);
+            });
+            insert.build().execute(&mut *conn).await?;
+        }
+
+        Ok(counted.into_iter().collect())
+    })
+    .await
+}
+
+/// Records a click event for analytics without counting it, as for bot clicks
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the clicked link
+/// * `referrer` - The `Referer` header sent with the click, if any
+/// * `user_agent` - The `User-Agent` header sent with the click, if any
+/// * `ip_hash` - Salted hash of the client IP
+/// * `is_bot` - Whether the user agent belongs to a crawler, which isn't counted as a click
+///
+/// # Returns
+/// * `Result<(), sqlx::Error>` - Success or error
+pub async fn record_click(
+    pool: &PgPool,
+    link_id: Uuid,
+    referrer: Option<&str>,
+    user_agent: Option<&str>,
+    ip_hash: &str,
+    is_bot: bool,
 ) -> Result<(), sqlx::Error> {
     sqlx::query!(
         r#"
-        INSERT INTO users (email, username, password_hash, is_verified)
-        VALUES ($1, $2, $3, false)
+        INSERT INTO link_clicks (link_id, referrer, user_agent, ip_hash, is_bot)
+        VALUES ($1, $2, $3, $4, $5)
         "#,
-        email,
-        username,
-        password_hash
+        link_id,
+        referrer,
+        user_agent,
+        ip_hash,
+        is_bot
     )
     .execute(pool)
     .await?;
@@ -229,26 +889,2336 @@ pub async fn create_unverified_user(
     Ok(())
 }
 
-pub async fn complete_registration(pool: &PgPool, email: &str) -> Result<(), sqlx::Error> {
-    let result = sqlx::query!(
-        r#"
-        UPDATE users
-        SET 
-            is_verified = true,
-            status = 'active',
-            verified_at = NOW()
-        WHERE email = $1 AND is_verified = false
-        "#,
-        email
-    )
-    .execute(pool)
-    .await?;
+/// Time bucket used to group click statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickBucket {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
 
-    if result.rows_affected() == 0 {
-        return Err(sqlx::Error::RowNotFound);
+impl ClickBucket {
+    /// The `date_trunc` field name for this bucket
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClickBucket::Hour => "hour",
+            ClickBucket::Day => "day",
+            ClickBucket::Week => "week",
+            ClickBucket::Month => "month",
+        }
     }
-
-    Ok(())
+}
+
+/// Counts clicks on a link grouped into time buckets
+///
+/// Bot clicks are left out, like they are from the link's click count.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+/// * `bucket` - The size of each time bucket
+/// * `from` - Only count clicks at or after this instant
+/// * `to` - Only count clicks at or before this instant
+///
+/// # Returns
+/// * `Result<Vec<ClickStat>, sqlx::Error>` - Click counts per bucket, oldest first, or an error
+pub async fn get_click_stats(
+    pool: &PgPool,
+    link_id: Uuid,
+    bucket: ClickBucket,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<ClickStat>, sqlx::Error> {
+    sqlx::query_as!(
+        ClickStat,
+        r#"
+        SELECT
+            date_trunc($2, clicked_at) as "bucket_start!",
+            COUNT(*) as "clicks!",
+            COUNT(DISTINCT ip_hash) as "unique_visitors!"
+        FROM link_clicks
+        WHERE link_id = $1
+            AND NOT is_bot
+            AND clicked_at BETWEEN COALESCE($3, '-infinity'::timestamptz)
+                AND COALESCE($4, 'infinity'::timestamptz)
+        GROUP BY 1
+        ORDER BY 1 ASC
+        "#,
+        link_id,
+        bucket.as_str(),
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Counts the recorded clicks on a link within a time window, leaving out bots
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+/// * `from` - Only count clicks at or after this instant
+/// * `to` - Only count clicks at or before this instant
+///
+/// # Returns
+/// * `Result<i64, sqlx::Error>` - The number of clicks or an error
+pub async fn get_click_count(
+    pool: &PgPool,
+    link_id: Uuid,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM link_clicks
+        WHERE link_id = $1
+            AND NOT is_bot
+            AND clicked_at BETWEEN COALESCE($2, '-infinity'::timestamptz)
+                AND COALESCE($3, 'infinity'::timestamptz)
+        "#,
+        link_id,
+        from,
+        to
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Counts the distinct visitors that clicked a link within a time window, leaving out bots
+///
+/// Visitors are told apart by their hashed IP. While the hash salt rotates daily, the same
+/// visitor clicking on two different days counts twice.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+/// * `from` - Only count clicks at or after this instant
+/// * `to` - Only count clicks at or before this instant
+///
+/// # Returns
+/// * `Result<i64, sqlx::Error>` - The number of unique visitors or an error
+pub async fn get_unique_click_count(
+    pool: &PgPool,
+    link_id: Uuid,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(DISTINCT ip_hash) as "count!"
+        FROM link_clicks
+        WHERE link_id = $1
+            AND NOT is_bot
+            AND clicked_at BETWEEN COALESCE($2, '-infinity'::timestamptz)
+                AND COALESCE($3, 'infinity'::timestamptz)
+        "#,
+        link_id,
+        from,
+        to
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Filters and page position for listing raw click events
+#[derive(Debug, Default, Clone)]
+pub struct ClickFilters {
+    /// Only include clicks at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Only include clicks at or before this instant
+    pub to: Option<DateTime<Utc>>,
+    /// ID of the last click of the previous page
+    pub cursor: Option<Uuid>,
+    /// Maximum number of clicks to return
+    pub limit: i64,
+}
+
+/// Retrieves a link's click events, newest first
+///
+/// Pages are keyed on `(clicked_at, id)`, so clicks recorded while paging
+/// don't shift later pages.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+/// * `filters` - Time window and page position
+///
+/// # Returns
+/// * `Result<Vec<ClickEvent>, sqlx::Error>` - Up to `filters.limit` clicks or an error
+pub async fn get_clicks_for_link(
+    pool: &PgPool,
+    link_id: Uuid,
+    filters: &ClickFilters,
+) -> Result<Vec<ClickEvent>, sqlx::Error> {
+    sqlx::query_as!(
+        ClickEvent,
+        r#"
+        SELECT c.id, c.clicked_at, c.referrer, c.ip_hash, c.is_bot
+        FROM link_clicks c
+        WHERE c.link_id = $1
+            AND c.clicked_at BETWEEN COALESCE($2, '-infinity'::timestamptz)
+                AND COALESCE($3, 'infinity'::timestamptz)
+            AND (
+                $4::uuid IS NULL
+                OR (c.clicked_at, c.id) < (
+                    SELECT clicked_at, id FROM link_clicks WHERE id = $4 AND link_id = $1
+                )
+            )
+        ORDER BY c.clicked_at DESC, c.id DESC
+        LIMIT $5
+        "#,
+        link_id,
+        filters.from,
+        filters.to,
+        filters.cursor,
+        filters.limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// How long a soft-deleted link can still be restored by its owner
+pub const RESTORE_GRACE_PERIOD_DAYS: i32 = 30;
+
+/// Soft-deletes a link by stamping its `deleted_at` column
+///
+/// The deletion is recorded in the audit log in the same transaction, along with the
+/// link's URL and title.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to delete
+/// * `actor_id` - The user deleting the link
+///
+/// # Returns
+/// * `Result<(), sqlx::Error>` - Success or error
+pub async fn delete_link(pool: &PgPool, link_id: Uuid, actor_id: Uuid) -> Result<(), sqlx::Error> {
+    with_transaction(pool, async |conn| {
+        let deleted = sqlx::query!(
+            r#"
+            UPDATE links SET deleted_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING url, title
+            "#,
+            link_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some(link) = deleted {
+            record_audit_event(
+                conn,
+                actor_id,
+                AuditAction::LinkDeleted,
+                link_id,
+                json!({ "url": link.url, "title": link.title }),
+            )
+            .await?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Soft-deletes every link whose expiry has passed
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+///
+/// # Returns
+/// * `Result<u64, sqlx::Error>` - The number of links deleted, or an error
+pub async fn delete_expired_links(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE links SET deleted_at = NOW() WHERE expires_at <= NOW() AND deleted_at IS NULL"
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Permanently deletes anonymous links that expired before anyone claimed them
+///
+/// Nobody could restore them, so unlike owned links they are not soft-deleted.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+///
+/// # Returns
+/// * `Result<u64, sqlx::Error>` - The number of links deleted, or an error
+pub async fn delete_unclaimed_links(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM links WHERE user_id IS NULL AND (expires_at <= NOW() OR deleted_at IS NOT NULL)"
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Soft-deletes every link owned by a user
+///
+/// Runs as a single statement, so either all of the user's links are deleted or none are.
+/// The links stay restorable for the usual grace period.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user whose links are deleted
+///
+/// # Returns
+/// * `Result<u64, sqlx::Error>` - The number of links deleted, or an error
+pub async fn delete_all_links_for_user(pool: &PgPool, user_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE links SET deleted_at = NOW() WHERE user_id = $1 AND deleted_at IS NULL",
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Restores a soft-deleted link that is still within the grace period
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to restore
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The restored link, None if not restorable, or an error
+pub async fn restore_link(pool: &PgPool, link_id: Uuid) -> Result<Option<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        WITH restored_link AS (
+            UPDATE links
+            SET deleted_at = NULL
+            WHERE id = $1
+                AND deleted_at IS NOT NULL
+                AND deleted_at > NOW() - make_interval(days => $2)
+            RETURNING *
+        )
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM restored_link l
+        LEFT JOIN users u ON l.user_id = u.id
+        "#,
+        link_id,
+        RESTORE_GRACE_PERIOD_DAYS
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Marks a link as a favorite of a user
+///
+/// Favoriting an already favorited link is a no-op.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user
+/// * `link_id` - The ID of the link to favorite
+///
+/// # Returns
+/// * `Result<(), sqlx::Error>` - Success or an error
+pub async fn add_favorite(pool: &PgPool, user_id: Uuid, link_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO link_favorites (user_id, link_id)
+        VALUES ($1, $2)
+        ON CONFLICT DO NOTHING
+        "#,
+        user_id,
+        link_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Removes a link from a user's favorites
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user
+/// * `link_id` - The ID of the link to unfavorite
+///
+/// # Returns
+/// * `Result<(), sqlx::Error>` - Success or an error
+pub async fn remove_favorite(
+    pool: &PgPool,
+    user_id: Uuid,
+    link_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM link_favorites
+        WHERE user_id = $1 AND link_id = $2
+        "#,
+        user_id,
+        link_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Retrieves the links a user has favorited, most recently favorited first
+///
+/// Links that were deleted or made private by their owner are left out.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - The favorited links or an error
+pub async fn get_favorited_links(pool: &PgPool, user_id: Uuid) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM link_favorites f
+        JOIN links l ON f.link_id = l.id
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE f.user_id = $1
+            AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND (l.visibility = 'public' OR l.user_id = $1)
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW() OR l.user_id = $1)
+        ORDER BY f.created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// How long an idempotency key is remembered
+pub const IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+
+/// Reserves an idempotency key for a request that is about to create a link
+///
+/// Succeeds when the key is new for the user or its previous use has expired.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user sending the request
+/// * `key` - The client-supplied idempotency key
+/// * `request_hash` - Fingerprint of the request body
+///
+/// # Returns
+/// * `Result<bool, sqlx::Error>` - Whether the key was claimed, or an error
+pub async fn claim_idempotency_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    request_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let claimed = sqlx::query!(
+        r#"
+        INSERT INTO idempotency_keys (user_id, key, request_hash)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, key) DO UPDATE
+        SET request_hash = EXCLUDED.request_hash, link_id = NULL, created_at = NOW()
+        WHERE idempotency_keys.created_at <= NOW() - make_interval(hours => $4)
+        "#,
+        user_id,
+        key,
+        request_hash,
+        IDEMPOTENCY_KEY_TTL_HOURS
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(claimed > 0)
+}
+
+/// Looks up an unexpired idempotency key
+pub async fn get_idempotency_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+) -> Result<Option<IdempotencyRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        IdempotencyRecord,
+        r#"
+        SELECT request_hash, link_id
+        FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2
+            AND created_at > NOW() - make_interval(hours => $3)
+        "#,
+        user_id,
+        key,
+        IDEMPOTENCY_KEY_TTL_HOURS
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Records the link created under a claimed idempotency key
+pub async fn complete_idempotency_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    link_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE idempotency_keys
+        SET link_id = $3
+        WHERE user_id = $1 AND key = $2
+        "#,
+        user_id,
+        key,
+        link_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Frees a claimed idempotency key after the request failed, so the client can retry
+pub async fn release_idempotency_key(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE user_id = $1 AND key = $2 AND link_id IS NULL
+        "#,
+        user_id,
+        key
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes idempotency keys older than the retention window
+///
+/// # Returns
+/// * `Result<u64, sqlx::Error>` - The number of keys removed, or an error
+pub async fn delete_expired_idempotency_keys(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE created_at <= NOW() - make_interval(hours => $1)
+        "#,
+        IDEMPOTENCY_KEY_TTL_HOURS
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Registers a webhook subscription for a user
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user who owns the subscription
+/// * `url` - The endpoint deliveries are posted to
+/// * `secret` - The key used to sign deliveries
+/// * `events` - The event names to deliver
+pub async fn create_webhook(
+    pool: &PgPool,
+    user_id: Uuid,
+    url: &str,
+    secret: &str,
+    events: &[String],
+) -> Result<Webhook, sqlx::Error> {
+    sqlx::query_as!(
+        Webhook,
+        r#"
+        INSERT INTO webhooks (user_id, url, secret, events)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, url, secret, events, created_at
+        "#,
+        user_id,
+        url,
+        secret,
+        events
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Gets a user's webhooks subscribed to the given event
+pub async fn get_webhooks_for_event(
+    pool: &PgPool,
+    user_id: Uuid,
+    event: &str,
+) -> Result<Vec<Webhook>, sqlx::Error> {
+    sqlx::query_as!(
+        Webhook,
+        r#"
+        SELECT id, user_id, url, secret, events, created_at
+        FROM webhooks
+        WHERE user_id = $1 AND $2 = ANY(events)
+        ORDER BY created_at
+        "#,
+        user_id,
+        event
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Moves a link from its current owner to a different user
+///
+/// Runs in a transaction that locks the link and the new owner's account, so the link
+/// can't change hands and the new owner can't be deleted while the transfer is written.
+/// The transfer is recorded in the audit log as made by `owner_id` in the same transaction.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to transfer
+/// * `owner_id` - The ID of the user who must currently own the link
+/// * `new_owner_id` - The ID of the user who will own the link
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The link with its new owner, None if the link is
+///   gone, isn't owned by `owner_id` or the new owner doesn't exist, or an error
+pub async fn transfer_link(
+    pool: &PgPool,
+    link_id: Uuid,
+    owner_id: Uuid,
+    new_owner_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    with_transaction(pool, async |conn| {
+        let owned = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM links
+                WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+                FOR UPDATE
+            ) as "owned!"
+            "#,
+            link_id,
+            owner_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        let new_owner_exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 FOR SHARE) as "exists!""#,
+            new_owner_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if !owned || !new_owner_exists {
+            return Ok(None);
+        }
+
+        let link = sqlx::query_as!(
+            Link,
+            r#"
+            WITH transferred_link AS (
+                UPDATE links
+                SET user_id = $2, collection_id = NULL, notes = NULL, updated_at = NOW()
+                WHERE id = $1 AND deleted_at IS NULL
+                RETURNING *
+            )
+            SELECT
+                l.id,
+                l.url as "url!",
+                l.original_url as "original_url!",
+                l.title as "title!",
+                l.description as "description!",
+                l.user_id as "user_id?",
+                l.click_count as "click_count!",
+                l.created_at as "created_at!",
+                l.updated_at as "updated_at!",
+                l.preview as "preview: JsonLinkPreview",
+                l.tags as "tags!",
+                l.visibility as "visibility!: LinkVisibility",
+                l.slug as "slug!",
+                l.last_clicked_at,
+                l.expires_at,
+                l.health as "health!: LinkHealth",
+                l.last_checked_at,
+                l.preview_status as "preview_status!: PreviewStatus",
+                l.collection_id,
+                l.custom_image_url,
+                l.track_clicks as "track_clicks!",
+                l.notes,
+                l.publish_at,
+                (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+                COALESCE(
+                    jsonb_build_object('username', u.username)::jsonb,
+                    'null'::jsonb
+                ) as "user!: OptionalJsonUser"
+            FROM transferred_link l
+            LEFT JOIN users u ON l.user_id = u.id
+            "#,
+            link_id,
+            new_owner_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if link.is_some() {
+            record_audit_event(
+                conn,
+                owner_id,
+                AuditAction::LinkTransferred,
+                link_id,
+                json!({ "from": owner_id, "to": new_owner_id }),
+            )
+            .await?;
+        }
+        Ok(link)
+    })
+    .await
+}
+
+/// Assigns an unclaimed anonymous link to the user presenting its claim token
+///
+/// Claiming consumes the token and lifts the expiry given to unclaimed links.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to claim
+/// * `claim_token_hash` - SHA-256 of the presented claim token
+/// * `user_id` - The ID of the user who becomes the owner
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The claimed link, None if the link is gone, already
+///   claimed or the token doesn't match, or an error
+pub async fn claim_link(
+    pool: &PgPool,
+    link_id: Uuid,
+    claim_token_hash: &str,
+    user_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        WITH claimed_link AS (
+            UPDATE links
+            SET user_id = $3, claim_token_hash = NULL, expires_at = NULL, updated_at = NOW()
+            WHERE id = $1
+                AND user_id IS NULL
+                AND claim_token_hash = $2
+                AND deleted_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+            RETURNING *
+        )
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM claimed_link l
+        LEFT JOIN users u ON l.user_id = u.id
+        "#,
+        link_id,
+        claim_token_hash,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Retrieves a soft-deleted link that is still within the restore grace period
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the deleted link
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The deleted link if restorable, None otherwise, or an error
+pub async fn get_deleted_link_by_id(
+    pool: &PgPool,
+    link_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.id = $1
+            AND l.deleted_at IS NOT NULL
+            AND l.deleted_at > NOW() - make_interval(days => $2)
+        "#,
+        link_id,
+        RESTORE_GRACE_PERIOD_DAYS
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Retrieves a single link by its ID
+///
+/// # Arguments
+/// * `executor` - Database connection pool, or a connection inside a transaction
+/// * `link_id` - The ID of the link to fetch
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The link if found, None if not found, or an error
+pub async fn get_link_by_id(
+    executor: impl PgExecutor<'_>,
+    link_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.id = $1 AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        "#,
+        link_id
+    )
+    .fetch_optional(executor)
+    .await
+}
+
+/// Finds public links similar to the given one
+///
+/// Similarity is the number of shared tags, or of shared title words (3+ letters) when the
+/// source link has no tags. Links with nothing in common are left out.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to find related links for
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - Related links, most similar first, or an error
+pub async fn get_related_links(
+    pool: &PgPool,
+    link_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        WITH source AS (
+            SELECT
+                id,
+                tags,
+                ARRAY(
+                    SELECT DISTINCT word
+                    FROM unnest(regexp_split_to_array(lower(title), '[^a-z0-9]+')) AS word
+                    WHERE length(word) >= 3
+                ) AS keywords
+            FROM links
+            WHERE id = $1
+        ),
+        candidates AS (
+            SELECT
+                l.id,
+                CASE WHEN cardinality(s.tags) > 0
+                    THEN cardinality(ARRAY(
+                        SELECT unnest(l.tags) INTERSECT SELECT unnest(s.tags)
+                    ))
+                    ELSE cardinality(ARRAY(
+                        SELECT unnest(regexp_split_to_array(lower(l.title), '[^a-z0-9]+'))
+                        INTERSECT SELECT unnest(s.keywords)
+                    ))
+                END AS overlap
+            FROM links l
+            CROSS JOIN source s
+            WHERE l.id <> s.id
+                AND l.deleted_at IS NULL
+                AND (l.expires_at IS NULL OR l.expires_at > NOW())
+                AND l.visibility = 'public'
+                AND (l.publish_at IS NULL OR l.publish_at <= NOW())
+                AND (cardinality(s.tags) = 0 OR l.tags && s.tags)
+        )
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM candidates c
+        JOIN links l ON l.id = c.id
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE c.overlap > 0
+        ORDER BY c.overlap DESC, l.click_count DESC, l.created_at DESC
+        LIMIT $2
+        "#,
+        link_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the public links gaining clicks fastest right now
+///
+/// Links are ranked by `click_count / (hours_since_created + 2) ^ 1.5`, so a link's score
+/// decays as it ages and a recent link with a moderate number of clicks outranks an old one
+/// that collected many clicks over the years. The `+ 2` keeps brand-new links from dividing
+/// by almost nothing. Links without clicks are left out.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - Trending links, highest score first, or an error
+pub async fn get_trending_links(pool: &PgPool, limit: i64) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND l.visibility = 'public'
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW())
+            AND l.click_count > 0
+        ORDER BY
+            l.click_count / power(
+                GREATEST(EXTRACT(EPOCH FROM NOW() - l.created_at), 0) / 3600 + 2,
+                1.5
+            ) DESC,
+            l.created_at DESC
+        LIMIT $1
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Retrieves the non-deleted links with the given IDs, in no particular order
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_ids` - The IDs of the links to fetch
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - The links that exist, or an error
+pub async fn get_links_by_ids(pool: &PgPool, link_ids: &[Uuid]) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.id = ANY($1) AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        "#,
+        link_ids
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Retrieves a user's most recent public links that are already published
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - The public links, newest first, or an error
+pub async fn get_public_links_by_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.user_id = $1 AND l.deleted_at IS NULL AND l.visibility = 'public'
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW())
+        ORDER BY l.created_at DESC
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Counts a user's active links and the clicks they received
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+///
+/// # Returns
+/// * `Result<LinkTotals, sqlx::Error>` - The totals or an error
+pub async fn get_link_totals(pool: &PgPool, user_id: Uuid) -> Result<LinkTotals, sqlx::Error> {
+    sqlx::query_as!(
+        LinkTotals,
+        r#"
+        SELECT
+            COUNT(*) as "links!",
+            COALESCE(SUM(l.click_count), 0)::bigint as "clicks!"
+        FROM links l
+        WHERE l.user_id = $1
+            AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Counts the clicks on all of a user's links since an instant, leaving out bots
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+/// * `since` - Only count clicks at or after this instant
+///
+/// # Returns
+/// * `Result<i64, sqlx::Error>` - The number of clicks or an error
+pub async fn get_user_click_count_since(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM link_clicks c
+        JOIN links l ON l.id = c.link_id
+        WHERE l.user_id = $1
+            AND l.deleted_at IS NULL
+            AND NOT c.is_bot
+            AND c.clicked_at >= $2
+        "#,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Retrieves a user's most clicked active links
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - The links, most clicked first, or an error
+pub async fn get_most_clicked_links_by_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.user_id = $1
+            AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        ORDER BY l.click_count DESC, l.id
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Ranks a user's links by the clicks they received since an instant, leaving out bots
+///
+/// Unlike [`get_most_clicked_links_by_user`], which ranks by all-time click count, only
+/// click events within the window count. Links without clicks in it are left out.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+/// * `since` - Only count clicks at or after this instant
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<LeaderboardEntry>, sqlx::Error>` - The links with their clicks in the
+///   window, most clicked first, or an error
+pub async fn get_user_leaderboard(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+    sqlx::query_as::<_, LeaderboardEntry>(
+        r#"
+        WITH windowed AS (
+            SELECT c.link_id, COUNT(*) AS period_clicks
+            FROM link_clicks c
+            JOIN links l ON l.id = c.link_id
+            WHERE l.user_id = $1
+                AND l.deleted_at IS NULL
+                AND NOT c.is_bot
+                AND c.clicked_at >= $2
+            GROUP BY c.link_id
+        )
+        SELECT
+            l.id,
+            l.url,
+            l.original_url,
+            l.title,
+            l.description,
+            l.user_id,
+            l.click_count,
+            l.created_at,
+            l.updated_at,
+            l.preview,
+            l.tags,
+            l.visibility,
+            l.slug,
+            l.last_clicked_at,
+            l.expires_at,
+            l.health,
+            l.last_checked_at,
+            l.preview_status,
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks,
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as scheduled,
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as user,
+            w.period_clicks
+        FROM windowed w
+        JOIN links l ON l.id = w.link_id
+        LEFT JOIN users u ON l.user_id = u.id
+        ORDER BY w.period_clicks DESC, l.click_count DESC, l.id
+        LIMIT $3
+        "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Retrieves a user's most recently created active links, private ones included
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - The links, newest first, or an error
+pub async fn get_recent_links_by_user(
+    pool: &PgPool,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.user_id = $1
+            AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        ORDER BY l.created_at DESC, l.id DESC
+        LIMIT $2
+        "#,
+        user_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Streams every non-deleted link owned by a user, newest first
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+///
+/// # Returns
+/// * `BoxStream<Result<Link, sqlx::Error>>` - The user's links, yielded as they are read
+pub fn get_links_by_user(pool: &PgPool, user_id: Uuid) -> BoxStream<'_, Result<Link, sqlx::Error>> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.user_id = $1 AND l.deleted_at IS NULL
+        ORDER BY l.created_at DESC
+        "#,
+        user_id
+    )
+    .fetch(pool)
+}
+
+/// Retrieves a single link by its short-link slug
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `slug` - The slug of the link to fetch
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The link if found, None if not found, or an error
+pub async fn get_link_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT 
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.slug = $1 AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        "#,
+        slug
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Counts a user's non-deleted links whose title matches `title`
+///
+/// Titles are compared case-insensitively with surrounding whitespace trimmed and inner
+/// runs of whitespace collapsed, so "Rust  Book" and "rust book" count as the same title.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the link owner
+/// * `title` - The title to look for
+///
+/// # Returns
+/// * `Result<i64, sqlx::Error>` - The number of links with that title or an error
+pub async fn count_links_with_title(
+    pool: &PgPool,
+    user_id: Uuid,
+    title: &str,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM links
+        WHERE user_id = $1
+            AND deleted_at IS NULL
+            AND lower(regexp_replace(btrim(title), '\s+', ' ', 'g'))
+                = lower(regexp_replace(btrim($2), '\s+', ' ', 'g'))
+        "#,
+        user_id,
+        title
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Finds a live link owned by a user that points at the same URL
+///
+/// URLs are compared by their normalized form (see [`dedupe_key`]), so
+/// differences in host casing, default ports or trailing slashes are ignored.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the link owner
+/// * `url` - The URL to look for
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The existing link if found, None otherwise, or an error
+pub async fn find_link_by_url(
+    pool: &PgPool,
+    user_id: Uuid,
+    url: &str,
+) -> Result<Option<Link>, sqlx::Error> {
+    let Some(key) = dedupe_key(url) else {
+        return Ok(None);
+    };
+    let host = url::Url::parse(&key)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_owned))
+        .unwrap_or_default();
+
+    // Narrow the candidates down by host in SQL, then compare normalized forms
+    let candidates = sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.user_id = $1
+            AND l.deleted_at IS NULL
+            AND strpos(lower(l.url), $2) > 0
+        ORDER BY l.created_at ASC
+        "#,
+        user_id,
+        host
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(candidates
+        .into_iter()
+        .find(|link| dedupe_key(&link.url).as_deref() == Some(key.as_str())))
+}
+
+/// Replaces the preview metadata of a link along with its thumbnails
+///
+/// The link's previous thumbnails are dropped in the same transaction, so they never
+/// outlive the preview image they were made from.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link to update
+/// * `preview` - The new preview of the link
+/// * `validators` - Cache validators the page was served with, for the next refresh
+/// * `thumbnails` - Downscaled copies of the preview's image
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The updated link, None if not found, or an error
+pub async fn update_link_preview(
+    pool: &PgPool,
+    link_id: Uuid,
+    preview: Option<&LinkPreview>,
+    validators: &PreviewValidators,
+    thumbnails: &[LinkThumbnail],
+) -> Result<Option<Link>, sqlx::Error> {
+    let preview_json = JsonLinkPreview::from(preview);
+
+    with_transaction(pool, async |conn| {
+        let link = sqlx::query_as!(
+            Link,
+            r#"
+            WITH updated_link AS (
+                UPDATE links
+                SET preview = $2,
+                    preview_status = 'ready',
+                    preview_etag = $3,
+                    preview_last_modified = $4
+                WHERE id = $1
+                RETURNING *
+            )
+            SELECT
+                l.id,
+                l.url as "url!",
+                l.original_url as "original_url!",
+                l.title as "title!",
+                l.description as "description!",
+                l.user_id as "user_id?",
+                l.click_count as "click_count!",
+                l.created_at as "created_at!",
+                l.updated_at as "updated_at!",
+                l.preview as "preview: JsonLinkPreview",
+                l.tags as "tags!",
+                l.visibility as "visibility!: LinkVisibility",
+                l.slug as "slug!",
+                l.last_clicked_at,
+                l.expires_at,
+                l.health as "health!: LinkHealth",
+                l.last_checked_at,
+                l.preview_status as "preview_status!: PreviewStatus",
+                l.collection_id,
+                l.custom_image_url,
+                l.track_clicks as "track_clicks!",
+                l.notes,
+                l.publish_at,
+                (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+                COALESCE(
+                    jsonb_build_object('username', u.username)::jsonb,
+                    'null'::jsonb
+                ) as "user!: OptionalJsonUser"
+            FROM updated_link l
+            LEFT JOIN users u ON l.user_id = u.id
+            "#,
+            link_id,
+            preview_json as _,
+            validators.etag,
+            validators.last_modified
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if link.is_none() {
+            return Ok(None);
+        }
+
+        sqlx::query!("DELETE FROM link_thumbnails WHERE link_id = $1", link_id)
+            .execute(&mut *conn)
+            .await?;
+        for thumbnail in thumbnails {
+            sqlx::query!(
+                r#"
+                INSERT INTO link_thumbnails (link_id, variant, content_type, data)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                link_id,
+                thumbnail.variant as _,
+                thumbnail.content_type,
+                thumbnail.data
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        Ok(link)
+    })
+    .await
+}
+
+/// Fetches one stored thumbnail of a link's preview image
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+/// * `variant` - The size of thumbnail to fetch
+///
+/// # Returns
+/// * `Result<Option<LinkThumbnail>, sqlx::Error>` - The thumbnail, None if none was stored
+///   because the image is already small enough or couldn't be scaled, or an error
+pub async fn get_link_thumbnail(
+    pool: &PgPool,
+    link_id: Uuid,
+    variant: ThumbnailVariant,
+) -> Result<Option<LinkThumbnail>, sqlx::Error> {
+    sqlx::query_as!(
+        LinkThumbnail,
+        r#"
+        SELECT variant as "variant: ThumbnailVariant", content_type, data
+        FROM link_thumbnails
+        WHERE link_id = $1 AND variant = $2
+        "#,
+        link_id,
+        variant as _
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Reads the cache validators stored with a link's preview
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+///
+/// # Returns
+/// * `Result<PreviewValidators, sqlx::Error>` - The validators, empty if there are none, or an error
+pub async fn get_preview_validators(
+    pool: &PgPool,
+    link_id: Uuid,
+) -> Result<PreviewValidators, sqlx::Error> {
+    let validators = sqlx::query_as!(
+        PreviewValidators,
+        r#"
+        SELECT preview_etag as etag, preview_last_modified as last_modified
+        FROM links
+        WHERE id = $1
+        "#,
+        link_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(validators.unwrap_or_default())
+}
+
+/// Records that a link's page hasn't changed since its preview was fetched
+///
+/// The preview is kept as is; only the time the link was last checked moves forward.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `link_id` - The ID of the link
+///
+/// # Returns
+/// * `Result<Option<Link>, sqlx::Error>` - The link, None if not found, or an error
+pub async fn mark_preview_unchanged(
+    pool: &PgPool,
+    link_id: Uuid,
+) -> Result<Option<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        WITH updated_link AS (
+            UPDATE links
+            SET last_checked_at = NOW()
+            WHERE id = $1
+            RETURNING *
+        )
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM updated_link l
+        LEFT JOIN users u ON l.user_id = u.id
+        "#,
+        link_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Link fields a search looks in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchFields {
+    pub title: bool,
+    pub description: bool,
+}
+
+impl Default for SearchFields {
+    fn default() -> Self {
+        Self {
+            title: true,
+            description: true,
+        }
+    }
+}
+
+impl SearchFields {
+    /// The `search_vector` weights of these fields, as tsquery labels; empty matches any
+    fn weights(self) -> &'static str {
+        match (self.title, self.description) {
+            (true, false) => "A",
+            (false, true) => "B",
+            _ => "",
+        }
+    }
+}
+
+impl std::str::FromStr for SearchFields {
+    type Err = ();
+
+    /// Parses a comma-separated list of `title` and `description`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut fields = Self {
+            title: false,
+            description: false,
+        };
+        for field in value.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            match field {
+                "title" => fields.title = true,
+                "description" => fields.description = true,
+                _ => return Err(()),
+            }
+        }
+        if !fields.title && !fields.description {
+            return Err(());
+        }
+        Ok(fields)
+    }
+}
+
+/// Builds a tsquery matching every word of `query` as a prefix, only within `fields`
+///
+/// Anything but letters and digits separates words, so user input can't inject tsquery
+/// operators. None when the query has no words at all.
+fn prefix_tsquery(query: &str, fields: SearchFields) -> Option<String> {
+    let weights = fields.weights();
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("{word}:*{weights}"))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" & "))
+}
+
+/// Full-text search over link titles and descriptions
+///
+/// Each word matches as a prefix, so "rus" finds "rust". Links whose title matches come
+/// before description-only hits, however often the description repeats the words; within
+/// each group they are ranked with `ts_rank_cd` over `search_vector`.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `query` - The free-text search query
+/// * `fields` - The fields to look in
+/// * `viewer_id` - The user searching; their private links are included alongside public ones
+/// * `limit` - Maximum number of links to return
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - Matching links ordered by relevance, or an error
+pub async fn search_links(
+    pool: &PgPool,
+    query: &str,
+    fields: SearchFields,
+    viewer_id: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<Link>, sqlx::Error> {
+    let Some(tsquery) = prefix_tsquery(query, fields) else {
+        return Ok(Vec::new());
+    };
+
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id,
+        to_tsquery('english', $1) query
+        WHERE l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND (l.visibility = 'public' OR l.user_id = $3)
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW() OR l.user_id = $3)
+            AND l.search_vector @@ query
+        ORDER BY
+            ts_filter(l.search_vector, '{a}') @@ query DESC,
+            ts_rank_cd(l.search_vector, query) DESC,
+            l.created_at DESC
+        LIMIT $2
+        "#,
+        tsquery,
+        limit,
+        viewer_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn check_user_exists(
+    pool: &PgPool,
+    email: &str,
+    username: &str,
+) -> Result<bool, sqlx::Error> {
+    let count = if username.is_empty() {
+        // Only check email
+        sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count
+            FROM users
+            WHERE LOWER(email) = LOWER($1)
+            "#,
+            email
+        )
+        .fetch_one(pool)
+        .await?
+        .count
+        .unwrap_or(0)
+    } else {
+        // Check both email and username
+        sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count
+            FROM users
+            WHERE LOWER(email) = LOWER($1) OR LOWER(username) = LOWER($2)
+            "#,
+            email,
+            username
+        )
+        .fetch_one(pool)
+        .await?
+        .count
+        .unwrap_or(0)
+    };
+
+    Ok(count > 0)
+}
+
+pub async fn get_user_id_by_username(
+    pool: &PgPool,
+    username: &str,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM users
+        WHERE LOWER(username) = LOWER($1)
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Builds a user's public profile with stats over their active public links
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `username` - The username to look up, ignoring case
+///
+/// # Returns
+/// * `Result<Option<UserProfile>, sqlx::Error>` - The profile, None if no such user, or an error
+pub async fn get_user_profile(
+    pool: &PgPool,
+    username: &str,
+) -> Result<Option<UserProfile>, sqlx::Error> {
+    sqlx::query_as!(
+        UserProfile,
+        r#"
+        SELECT
+            u.username,
+            u.created_at,
+            COUNT(l.id) as "public_link_count!",
+            COALESCE(SUM(l.click_count), 0)::bigint as "total_clicks!"
+        FROM users u
+        LEFT JOIN links l ON l.user_id = u.id
+            AND l.visibility = 'public'
+            AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            AND (l.publish_at IS NULL OR l.publish_at <= NOW())
+        WHERE LOWER(u.username) = LOWER($1)
+        GROUP BY u.id
+        "#,
+        username
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Lists users for administrators, newest first
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `limit` - Maximum number of users to return
+/// * `offset` - Number of users to skip
+///
+/// # Returns
+/// * `Result<Vec<UserSummary>, sqlx::Error>` - One page of users or an error
+pub async fn get_all_users(
+    pool: &PgPool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<UserSummary>, sqlx::Error> {
+    sqlx::query_as!(
+        UserSummary,
+        r#"
+        SELECT
+            id,
+            email,
+            username,
+            status as "status: UserStatus",
+            role as "role: UserRole",
+            is_verified,
+            link_quota,
+            created_at
+        FROM users
+        ORDER BY created_at DESC, id
+        LIMIT $1 OFFSET $2
+        "#,
+        limit,
+        offset
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Counts all registered users
+pub async fn get_users_count(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM users"#)
+        .fetch_one(pool)
+        .await
+}
+
+/// Deletes a user account together with everything it owns
+///
+/// Links, favorites, webhooks and idempotency keys are removed by their `ON DELETE CASCADE`
+/// foreign keys in the same statement, so a link never outlives its owner.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user to delete
+///
+/// # Returns
+/// * `Result<Option<i64>, sqlx::Error>` - The number of links deleted with the user,
+///   `None` if the user doesn't exist, or an error
+pub async fn delete_user(pool: &PgPool, user_id: Uuid) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        WITH owned_links AS (
+            SELECT COUNT(*) AS count FROM links WHERE user_id = $1
+        ),
+        deleted_user AS (
+            DELETE FROM users WHERE id = $1 RETURNING id
+        )
+        SELECT (SELECT count FROM owned_links) as "deleted_links!"
+        FROM deleted_user
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Counts a user's active links and reads their quota
+///
+/// Soft-deleted and expired links don't count towards the quota.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user
+///
+/// # Returns
+/// * `Result<Option<LinkQuota>, sqlx::Error>` - The user's quota, None if the user doesn't exist, or an error
+pub async fn get_link_quota(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<LinkQuota>, sqlx::Error> {
+    sqlx::query_as!(
+        LinkQuota,
+        r#"
+        SELECT
+            (
+                SELECT COUNT(*)
+                FROM links l
+                WHERE l.user_id = u.id
+                    AND l.deleted_at IS NULL
+                    AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            ) as "current!",
+            u.link_quota as "limit"
+        FROM users u
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Changes how many active links a user can own
+///
+/// Lowering the quota below the user's current count keeps their links but blocks new ones.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the user
+/// * `link_quota` - The new maximum number of active links
+///
+/// # Returns
+/// * `Result<Option<LinkQuota>, sqlx::Error>` - The updated quota, None if the user doesn't exist, or an error
+pub async fn set_link_quota(
+    pool: &PgPool,
+    user_id: Uuid,
+    link_quota: i32,
+) -> Result<Option<LinkQuota>, sqlx::Error> {
+    sqlx::query_as!(
+        LinkQuota,
+        r#"
+        UPDATE users u
+        SET link_quota = $2, updated_at = NOW()
+        WHERE u.id = $1
+        RETURNING
+            (
+                SELECT COUNT(*)
+                FROM links l
+                WHERE l.user_id = u.id
+                    AND l.deleted_at IS NULL
+                    AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            ) as "current!",
+            u.link_quota as "limit"
+        "#,
+        user_id,
+        link_quota
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn user_exists_by_id(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) as "exists!"
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+#[allow(dead_code)]
+pub async fn create_unverified_user(
+    pool: &PgPool,
+    email: &str,
+    username: &str,
+    password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO users (email, username, password_hash, is_verified)
+        VALUES ($1, $2, $3, false)
+        "#,
+        email,
+        username,
+        password_hash
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn complete_registration(pool: &PgPool, email: &str) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE users
+        SET 
+            is_verified = true,
+            status = 'active',
+            verified_at = NOW()
+        WHERE LOWER(email) = LOWER($1) AND is_verified = false
+        "#,
+        email
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
 }
 
 pub async fn is_user_verified(pool: &PgPool, email: &str) -> Result<bool, sqlx::Error> {
@@ -256,7 +3226,7 @@ pub async fn is_user_verified(pool: &PgPool, email: &str) -> Result<bool, sqlx::
         r#"
         SELECT is_verified
         FROM users
-        WHERE email = $1
+        WHERE LOWER(email) = LOWER($1)
         "#,
         email
     )
@@ -265,3 +3235,403 @@ pub async fn is_user_verified(pool: &PgPool, email: &str) -> Result<bool, sqlx::
 
     Ok(result.map(|r| r.is_verified).unwrap_or(false))
 }
+
+/// A claimed preview job along with the URL to fetch
+#[derive(Debug)]
+pub struct PreviewJob {
+    pub link_id: Uuid,
+    pub url: String,
+    /// Number of attempts including the current one
+    pub attempts: i32,
+}
+
+/// Queues a preview fetch for a link and marks its preview as pending
+///
+/// A link has at most one job: enqueueing again, for example after its URL changed,
+/// resets the existing job to run right away.
+pub async fn enqueue_preview_job(pool: &PgPool, link_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        WITH queued_job AS (
+            INSERT INTO preview_jobs (link_id)
+            VALUES ($1)
+            ON CONFLICT (link_id) DO UPDATE
+            SET status = 'pending', attempts = 0, last_error = NULL, run_after = NOW(), updated_at = NOW()
+            RETURNING link_id
+        )
+        UPDATE links
+        SET preview_status = 'pending'
+        WHERE id IN (SELECT link_id FROM queued_job) AND preview_status <> 'pending'
+        "#,
+        link_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Marks up to `limit` due jobs as running and returns them
+///
+/// `SKIP LOCKED` lets several workers poll the queue without claiming the same job.
+pub async fn claim_preview_jobs(pool: &PgPool, limit: i64) -> Result<Vec<PreviewJob>, sqlx::Error> {
+    sqlx::query_as!(
+        PreviewJob,
+        r#"
+        UPDATE preview_jobs j
+        SET status = 'running', attempts = j.attempts + 1, started_at = NOW(), updated_at = NOW()
+        FROM links l
+        WHERE j.link_id = l.id
+            AND j.link_id IN (
+                SELECT link_id
+                FROM preview_jobs
+                WHERE status = 'pending' AND run_after <= NOW()
+                ORDER BY run_after
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+        RETURNING j.link_id, l.url, j.attempts
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Removes a job whose preview was stored
+///
+/// Jobs re-queued while running are left alone so the newer request still runs.
+pub async fn complete_preview_job(pool: &PgPool, link_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM preview_jobs WHERE link_id = $1 AND status = 'running'",
+        link_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Records a failed attempt, scheduling a retry at `retry_at` or giving up when it is `None`
+///
+/// Giving up also marks the link's preview as failed.
+pub async fn fail_preview_job(
+    pool: &PgPool,
+    link_id: Uuid,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        WITH failed_job AS (
+            UPDATE preview_jobs
+            SET status = CASE WHEN $3::timestamptz IS NULL THEN 'failed' ELSE 'pending' END,
+                run_after = COALESCE($3, run_after),
+                last_error = $2,
+                updated_at = NOW()
+            WHERE link_id = $1 AND status = 'running'
+            RETURNING link_id, status
+        )
+        UPDATE links
+        SET preview_status = 'failed'
+        WHERE id IN (SELECT link_id FROM failed_job WHERE status = 'failed')
+        "#,
+        link_id,
+        error,
+        retry_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Puts jobs claimed before `started_before` and still running back in the queue
+///
+/// Jobs claimed more recently may belong to another worker that is still fetching them,
+/// so only those whose lease ran out are taken back.
+pub async fn requeue_running_preview_jobs(
+    pool: &PgPool,
+    started_before: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE preview_jobs
+        SET status = 'pending', started_at = NULL, updated_at = NOW()
+        WHERE status = 'running' AND (started_at IS NULL OR started_at < $1)
+        "#,
+        started_before
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// A link due for a health check
+#[derive(Debug)]
+pub struct HealthCheckTarget {
+    pub id: Uuid,
+    pub url: String,
+}
+
+/// Returns up to `limit` live links not checked since `checked_before`
+///
+/// Links that were never checked come first, then the ones checked longest ago.
+pub async fn get_links_due_for_health_check(
+    pool: &PgPool,
+    checked_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<HealthCheckTarget>, sqlx::Error> {
+    sqlx::query_as!(
+        HealthCheckTarget,
+        r#"
+        SELECT id, url as "url!"
+        FROM links
+        WHERE deleted_at IS NULL
+            AND (expires_at IS NULL OR expires_at > NOW())
+            AND (last_checked_at IS NULL OR last_checked_at < $1)
+        ORDER BY last_checked_at NULLS FIRST
+        LIMIT $2
+        "#,
+        checked_before,
+        limit
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Stores the outcome of a health check on a link
+///
+/// `reachable` is `Some(true)` when the target responded, `Some(false)` when the check
+/// failed and `None` when it was inconclusive. A link only turns dead after
+/// `dead_after_failures` failed checks in a row, so a single outage doesn't flag it.
+///
+/// # Returns
+/// * `Result<Option<(LinkHealth, DateTime<Utc>)>, sqlx::Error>` - The new health and check
+///   time, None if the link doesn't exist, or an error
+pub async fn record_link_health(
+    pool: &PgPool,
+    link_id: Uuid,
+    reachable: Option<bool>,
+    dead_after_failures: i32,
+) -> Result<Option<(LinkHealth, DateTime<Utc>)>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        UPDATE links
+        SET health = CASE
+                WHEN $2 THEN 'alive'::link_health
+                WHEN NOT $2 AND health_failures + 1 >= $3 THEN 'dead'::link_health
+                ELSE health
+            END,
+            health_failures = CASE
+                WHEN $2 THEN 0
+                WHEN NOT $2 THEN health_failures + 1
+                ELSE health_failures
+            END,
+            last_checked_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
+        RETURNING health as "health!: LinkHealth", last_checked_at as "last_checked_at!"
+        "#,
+        link_id,
+        reachable,
+        dead_after_failures
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| (row.health, row.last_checked_at)))
+}
+
+/// Whether an error was caused by the user already having a collection with that name
+pub fn is_collection_name_conflict(error: &sqlx::Error) -> bool {
+    error.as_database_error().is_some_and(|e| {
+        e.is_unique_violation() && e.constraint() == Some("idx_collections_user_id_name")
+    })
+}
+
+/// Creates an empty collection for a user
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+/// * `name` - The collection's name, unique per user ignoring case
+///
+/// # Returns
+/// * `Result<Collection, sqlx::Error>` - The created collection or an error
+pub async fn create_collection(
+    pool: &PgPool,
+    user_id: Uuid,
+    name: &str,
+) -> Result<Collection, sqlx::Error> {
+    sqlx::query_as!(
+        Collection,
+        r#"
+        INSERT INTO collections (user_id, name)
+        VALUES ($1, $2)
+        RETURNING id, user_id, name, 0::bigint as "link_count!", created_at
+        "#,
+        user_id,
+        name
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Lists a user's collections by name, with how many live links each holds
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The ID of the owner
+///
+/// # Returns
+/// * `Result<Vec<Collection>, sqlx::Error>` - The user's collections or an error
+pub async fn get_collections(pool: &PgPool, user_id: Uuid) -> Result<Vec<Collection>, sqlx::Error> {
+    sqlx::query_as!(
+        Collection,
+        r#"
+        SELECT
+            c.id,
+            c.user_id,
+            c.name,
+            (
+                SELECT COUNT(*)
+                FROM links l
+                WHERE l.collection_id = c.id
+                    AND l.deleted_at IS NULL
+                    AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            ) as "link_count!",
+            c.created_at
+        FROM collections c
+        WHERE c.user_id = $1
+        ORDER BY lower(c.name), c.id
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Retrieves one of a user's collections
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `collection_id` - The ID of the collection
+/// * `user_id` - The ID of the user who must own it
+///
+/// # Returns
+/// * `Result<Option<Collection>, sqlx::Error>` - The collection, None if it doesn't exist or
+///   belongs to someone else, or an error
+pub async fn get_collection(
+    pool: &PgPool,
+    collection_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<Collection>, sqlx::Error> {
+    sqlx::query_as!(
+        Collection,
+        r#"
+        SELECT
+            c.id,
+            c.user_id,
+            c.name,
+            (
+                SELECT COUNT(*)
+                FROM links l
+                WHERE l.collection_id = c.id
+                    AND l.deleted_at IS NULL
+                    AND (l.expires_at IS NULL OR l.expires_at > NOW())
+            ) as "link_count!",
+            c.created_at
+        FROM collections c
+        WHERE c.id = $1 AND c.user_id = $2
+        "#,
+        collection_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Deletes one of a user's collections
+///
+/// The links in it are kept; the foreign key clears their `collection_id`.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `collection_id` - The ID of the collection
+/// * `user_id` - The ID of the user who must own it
+///
+/// # Returns
+/// * `Result<bool, sqlx::Error>` - Whether a collection was deleted, or an error
+pub async fn delete_collection(
+    pool: &PgPool,
+    collection_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM collections WHERE id = $1 AND user_id = $2",
+        collection_id,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Lists the live links filed under a collection, newest first
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `collection_id` - The ID of the collection
+///
+/// # Returns
+/// * `Result<Vec<Link>, sqlx::Error>` - The collection's links or an error
+pub async fn get_links_by_collection(
+    pool: &PgPool,
+    collection_id: Uuid,
+) -> Result<Vec<Link>, sqlx::Error> {
+    sqlx::query_as!(
+        Link,
+        r#"
+        SELECT
+            l.id,
+            l.url as "url!",
+            l.original_url as "original_url!",
+            l.title as "title!",
+            l.description as "description!",
+            l.user_id as "user_id?",
+            l.click_count as "click_count!",
+            l.created_at as "created_at!",
+            l.updated_at as "updated_at!",
+            l.preview as "preview: JsonLinkPreview",
+            l.tags as "tags!",
+            l.visibility as "visibility!: LinkVisibility",
+            l.slug as "slug!",
+            l.last_clicked_at,
+            l.expires_at,
+            l.health as "health!: LinkHealth",
+            l.last_checked_at,
+            l.preview_status as "preview_status!: PreviewStatus",
+            l.collection_id,
+            l.custom_image_url,
+            l.track_clicks as "track_clicks!",
+            l.notes,
+            l.publish_at,
+            (l.publish_at IS NOT NULL AND l.publish_at > NOW()) as "scheduled!",
+            COALESCE(
+                jsonb_build_object('username', u.username)::jsonb,
+                'null'::jsonb
+            ) as "user!: OptionalJsonUser"
+        FROM links l
+        LEFT JOIN users u ON l.user_id = u.id
+        WHERE l.collection_id = $1
+            AND l.deleted_at IS NULL
+            AND (l.expires_at IS NULL OR l.expires_at > NOW())
+        ORDER BY l.created_at DESC, l.id DESC
+        "#,
+        collection_id
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,156 @@
+use super::queries::with_transaction;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// What presenting a refresh token led to
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rotation {
+    /// The token was exchanged for the new one
+    Rotated { user_id: Uuid },
+    /// The token had already been exchanged, so its whole family is now revoked
+    Reused { user_id: Uuid },
+    /// The token is past its expiry
+    Expired,
+    /// The token was never issued or its family was revoked
+    Invalid,
+}
+
+/// Stores a newly issued refresh token
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `user_id` - The user the token signs in
+/// * `family_id` - Shared by every token rotated from the same login
+/// * `token_hash` - SHA-256 of the token
+/// * `expires_at` - When the token stops working
+///
+/// # Returns
+/// * `Result<(), sqlx::Error>` - Success or an error
+pub async fn create_refresh_token(
+    pool: &PgPool,
+    user_id: Uuid,
+    family_id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        user_id,
+        family_id,
+        token_hash,
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Exchanges a refresh token for a new one in the same family
+///
+/// The presented token is locked while it is checked, so of two requests racing with
+/// the same token only one rotates it and the other is treated as reuse. Reuse of an
+/// exchanged token means it was copied, so every token of its family is revoked.
+///
+/// # Arguments
+/// * `pool` - Database connection pool
+/// * `token_hash` - SHA-256 of the presented token
+/// * `new_token_hash` - SHA-256 of the token replacing it
+/// * `new_expires_at` - When the replacement stops working
+///
+/// # Returns
+/// * `Result<Rotation, sqlx::Error>` - What happened to the token, or an error
+pub async fn rotate_refresh_token(
+    pool: &PgPool,
+    token_hash: &str,
+    new_token_hash: &str,
+    new_expires_at: DateTime<Utc>,
+) -> Result<Rotation, sqlx::Error> {
+    with_transaction(pool, async |conn| {
+        let Some(token) = sqlx::query!(
+            r#"
+            SELECT user_id, family_id, expires_at, rotated_at, revoked_at
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            FOR UPDATE
+            "#,
+            token_hash
+        )
+        .fetch_optional(&mut *conn)
+        .await?
+        else {
+            return Ok(Rotation::Invalid);
+        };
+
+        if token.revoked_at.is_some() {
+            return Ok(Rotation::Invalid);
+        }
+
+        if token.rotated_at.is_some() {
+            sqlx::query!(
+                r#"
+                UPDATE refresh_tokens
+                SET revoked_at = NOW()
+                WHERE family_id = $1 AND revoked_at IS NULL
+                "#,
+                token.family_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            return Ok(Rotation::Reused {
+                user_id: token.user_id,
+            });
+        }
+
+        if token.expires_at <= Utc::now() {
+            return Ok(Rotation::Expired);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET rotated_at = NOW()
+            WHERE token_hash = $1
+            "#,
+            token_hash
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            token.user_id,
+            token.family_id,
+            new_token_hash,
+            new_expires_at
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(Rotation::Rotated {
+            user_id: token.user_id,
+        })
+    })
+    .await
+}
+
+/// Deletes refresh tokens past their expiry
+///
+/// Exchanged tokens are kept until then so their reuse is still recognized.
+///
+/// # Returns
+/// * `Result<u64, sqlx::Error>` - The number of tokens removed, or an error
+pub async fn delete_expired_refresh_tokens(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM refresh_tokens WHERE expires_at <= NOW()")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,9 +1,11 @@
 use crate::{
-    api::{ApiResponse, ErrorResponse},
+    api::{extract::ApiJson, ApiResponse, ErrorResponse},
     auth::routes::AppState,
     models::auth::{
-        LoginRequest, RegisterRequest, ResendOtpRequest, User, UserStatus, VerifyEmailRequest,
+        normalize_email, LoginRequest, RefreshRequest, RegisterRequest, ResendOtpRequest, User,
+        UserStatus, VerifyEmailRequest,
     },
+    services::auth::{LoginError, RefreshError},
 };
 use axum::{
     extract::State,
@@ -19,8 +21,10 @@ use validator::Validate;
 /// Register a new user
 pub async fn register(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterRequest>,
+    ApiJson(mut payload): ApiJson<RegisterRequest>,
 ) -> impl IntoResponse {
+    payload.email = normalize_email(&payload.email);
+
     // Validate the request first - this must be synchronous
     if let Err(validation_errors) = payload.validate() {
         let error = ErrorResponse::new(format!("Validation error: {validation_errors}"))
@@ -36,13 +40,14 @@ pub async fn register(
             id, email, username, password_hash, 
             gender as "gender: _",
             status as "status: _",
+            role as "role: _",
             is_verified,
             verification_attempts,
             verified_at,
             created_at, 
             updated_at
         FROM users
-        WHERE email = $1 OR username = $2
+        WHERE LOWER(email) = LOWER($1) OR LOWER(username) = LOWER($2)
         "#,
         payload.email,
         payload.username
@@ -123,8 +128,8 @@ pub async fn register(
             (StatusCode::CREATED, Json(response)).into_response()
         }
         Err(e) => {
-            let error =
-                ErrorResponse::new(format!("Database error: {e}")).with_code("DATABASE_ERROR");
+            tracing::error!("Failed to check for an existing user: {e}");
+            let error = ErrorResponse::internal("Database error", &e).with_code("DATABASE_ERROR");
             (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
         }
     }
@@ -133,8 +138,10 @@ pub async fn register(
 /// Login user
 pub async fn login(
     State(state): State<AppState>,
-    Json(payload): Json<LoginRequest>,
+    ApiJson(mut payload): ApiJson<LoginRequest>,
 ) -> impl IntoResponse {
+    payload.email = normalize_email(&payload.email);
+
     match state
         .auth_service
         .login(&payload.email, &payload.password)
@@ -144,8 +151,45 @@ pub async fn login(
             let response = ApiResponse::success_with_message(auth_response, "Login successful");
             (StatusCode::OK, Json(response)).into_response()
         }
+        Err(LoginError::Database(e)) => {
+            tracing::error!("Failed to log in: {e}");
+            let error = ErrorResponse::internal("Login failed", &e).with_code("LOGIN_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+        Err(e) => {
+            let error = ErrorResponse::new(e.to_string()).with_code("LOGIN_ERROR");
+            (StatusCode::UNAUTHORIZED, Json(error)).into_response()
+        }
+    }
+}
+
+/// Refresh an access token
+///
+/// Exchanges a refresh token for a new access token and a new refresh token; the old
+/// refresh token stops working. Reusing an exchanged refresh token signs out every session
+/// from the same login.
+pub async fn refresh(
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<RefreshRequest>,
+) -> impl IntoResponse {
+    match state.auth_service.refresh(&payload.refresh_token).await {
+        Ok(auth_response) => {
+            let response = ApiResponse::success_with_message(auth_response, "Token refreshed");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(RefreshError::Database(e)) => {
+            tracing::error!("Failed to refresh token: {e}");
+            let error = ErrorResponse::internal("Database error", &e).with_code("DATABASE_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
         Err(e) => {
-            let error = ErrorResponse::new(format!("Login failed: {e}")).with_code("LOGIN_ERROR");
+            let code = match e {
+                RefreshError::Expired => "REFRESH_TOKEN_EXPIRED",
+                RefreshError::Reused => "REFRESH_TOKEN_REUSED",
+                RefreshError::Inactive => "ACCOUNT_INACTIVE",
+                RefreshError::Invalid | RefreshError::Database(_) => "INVALID_REFRESH_TOKEN",
+            };
+            let error = ErrorResponse::new(e.to_string()).with_code(code);
             (StatusCode::UNAUTHORIZED, Json(error)).into_response()
         }
     }
@@ -154,8 +198,10 @@ pub async fn login(
 /// Verify email with OTP
 pub async fn verify_email(
     State(state): State<AppState>,
-    Json(payload): Json<VerifyEmailRequest>,
+    ApiJson(mut payload): ApiJson<VerifyEmailRequest>,
 ) -> impl IntoResponse {
+    payload.email = normalize_email(&payload.email);
+
     // Log verification attempt
     println!("Starting email verification for: {}", payload.email);
 
@@ -196,9 +242,9 @@ pub async fn verify_email(
             (StatusCode::OK, Json(response)).into_response()
         }
         Err(e) => {
-            println!("Verification failed for {0}: {e}", payload.email);
-            let error = ErrorResponse::new(format!("Verification failed: {e}"))
-                .with_code("VERIFICATION_ERROR");
+            tracing::error!("Verification failed for {0}: {e}", payload.email);
+            let error =
+                ErrorResponse::internal("Verification failed", &e).with_code("VERIFICATION_ERROR");
             (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
         }
     }
@@ -207,8 +253,10 @@ pub async fn verify_email(
 /// Resend OTP for email verification
 pub async fn resend_otp(
     State(state): State<AppState>,
-    Json(payload): Json<ResendOtpRequest>,
+    ApiJson(mut payload): ApiJson<ResendOtpRequest>,
 ) -> (StatusCode, Json<ApiResponse<serde_json::Value>>) {
+    payload.email = normalize_email(&payload.email);
+
     // Validate the request
     if let Err(validation_errors) = payload.validate() {
         let response = ApiResponse {
@@ -216,6 +264,7 @@ pub async fn resend_otp(
             message: format!("Validation error: {validation_errors}"),
             data: json!({ "code": "VALIDATION_ERROR" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::UNPROCESSABLE_ENTITY, Json(response));
@@ -229,13 +278,14 @@ pub async fn resend_otp(
             id, email, username, password_hash, 
             gender as "gender: _",
             status as "status: _",
+            role as "role: _",
             is_verified,
             verification_attempts,
             verified_at,
             created_at, 
             updated_at
         FROM users
-        WHERE email = $1
+        WHERE LOWER(email) = LOWER($1)
         "#,
         payload.email
     )
@@ -249,6 +299,7 @@ pub async fn resend_otp(
                 message: "User not found".to_string(),
                 data: json!({ "code": "USER_NOT_FOUND" }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             return (StatusCode::NOT_FOUND, Json(response));
@@ -259,6 +310,7 @@ pub async fn resend_otp(
                 message: format!("Database error: {e}"),
                 data: json!({ "code": "DATABASE_ERROR" }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             return (StatusCode::INTERNAL_SERVER_ERROR, Json(response));
@@ -272,6 +324,7 @@ pub async fn resend_otp(
             message: "User is not in pending verification state".to_string(),
             data: json!({ "code": "INVALID_STATE" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::BAD_REQUEST, Json(response));
@@ -296,6 +349,7 @@ pub async fn resend_otp(
                 message: format!("Failed to send OTP: {e}"),
                 data: json!({ "code": "EMAIL_ERROR" }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             (StatusCode::INTERNAL_SERVER_ERROR, Json(response))
@@ -306,8 +360,10 @@ pub async fn resend_otp(
 /// Reset OTP attempts counter for an email
 pub async fn reset_otp_attempts(
     State(state): State<AppState>,
-    Json(payload): Json<ResendOtpRequest>,
+    ApiJson(mut payload): ApiJson<ResendOtpRequest>,
 ) -> impl IntoResponse {
+    payload.email = normalize_email(&payload.email);
+
     // Validate the request
     if let Err(validation_errors) = payload.validate() {
         let response = ApiResponse {
@@ -315,6 +371,7 @@ pub async fn reset_otp_attempts(
             message: format!("Validation error: {validation_errors}"),
             data: json!({ "code": "VALIDATION_ERROR" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::UNPROCESSABLE_ENTITY, Json(response));
@@ -328,13 +385,14 @@ pub async fn reset_otp_attempts(
             id, email, username, password_hash, 
             gender as "gender: _",
             status as "status: _",
+            role as "role: _",
             is_verified,
             verification_attempts,
             verified_at,
             created_at, 
             updated_at
         FROM users
-        WHERE email = $1
+        WHERE LOWER(email) = LOWER($1)
         "#,
         payload.email
     )
@@ -348,6 +406,7 @@ pub async fn reset_otp_attempts(
                 message: "User not found".to_string(),
                 data: json!({ "code": "USER_NOT_FOUND" }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             return (StatusCode::NOT_FOUND, Json(response));
@@ -358,6 +417,7 @@ pub async fn reset_otp_attempts(
                 message: format!("Database error: {e}"),
                 data: json!({ "code": "DATABASE_ERROR" }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             return (StatusCode::INTERNAL_SERVER_ERROR, Json(response));
@@ -375,6 +435,7 @@ pub async fn reset_otp_attempts(
             message: format!("Failed to reset attempts: {e}"),
             data: json!({ "code": "RESET_ERROR" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(response));
@@ -402,8 +463,10 @@ pub struct AdminResetOtpRequest {
 pub async fn admin_reset_otp_attempts(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<AdminResetOtpRequest>,
+    ApiJson(mut payload): ApiJson<AdminResetOtpRequest>,
 ) -> impl IntoResponse {
+    payload.email = normalize_email(&payload.email);
+
     // Validate the request
     if let Err(validation_errors) = payload.validate() {
         let response = ApiResponse {
@@ -411,6 +474,7 @@ pub async fn admin_reset_otp_attempts(
             message: format!("Validation error: {validation_errors}"),
             data: json!({ "code": "VALIDATION_ERROR" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::UNPROCESSABLE_ENTITY, Json(response));
@@ -426,6 +490,7 @@ pub async fn admin_reset_otp_attempts(
                     message: "Invalid admin token format".to_string(),
                     data: json!({ "code": "INVALID_TOKEN" }),
                     pagination: None,
+                    meta: None,
                     timestamp: chrono::Utc::now(),
                 };
                 return (StatusCode::UNAUTHORIZED, Json(response));
@@ -437,6 +502,7 @@ pub async fn admin_reset_otp_attempts(
                 message: "Missing admin token".to_string(),
                 data: json!({ "code": "MISSING_TOKEN" }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             return (StatusCode::UNAUTHORIZED, Json(response));
@@ -454,6 +520,7 @@ pub async fn admin_reset_otp_attempts(
             message: format!("Failed to reset attempts: {e}"),
             data: json!({ "code": "RESET_ERROR" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(response));
@@ -3,6 +3,7 @@ pub mod auth;
 pub mod database;
 pub mod handlers;
 pub mod logging;
+pub mod metrics;
 pub mod middleware;
 pub mod models;
 pub mod routes;
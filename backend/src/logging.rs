@@ -52,11 +52,37 @@ pub fn init_logging() {
     tracing::info!("Logging system initialized");
 }
 
+/// Header carrying the request ID, read from clients and echoed in responses
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Identifier of the current request, available to handlers as a request extension
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    /// ID of the request being handled, set by the request logger around each handler
+    pub static CURRENT_REQUEST_ID: String;
+}
+
+/// ID of the request the current task is handling, if it runs inside the request logger
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(Clone::clone).ok()
+}
+
 /// Create a request ID for tracing
 pub fn generate_request_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// Reuses a client-supplied request ID if it is a UUID, so IDs can be correlated
+/// across services, and generates a fresh one otherwise
+pub fn request_id_from_header(value: Option<&str>) -> String {
+    value
+        .and_then(|value| Uuid::parse_str(value.trim()).ok())
+        .map(|id| id.to_string())
+        .unwrap_or_else(generate_request_id)
+}
+
 /// Log a request with timing information
 pub fn log_request(method: &str, path: &str, status: u16, duration: Duration, request_id: &str) {
     tracing::info!(
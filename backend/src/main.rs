@@ -1,26 +1,59 @@
 use backend::{
     api::docs::ApiDoc,
-    auth::{self},
-    database,
+    auth, database,
     logging::init_logging,
-    middleware::{auth::auth, request_logger::request_logger},
+    metrics::{create_metrics_router, init_metrics},
+    middleware::{
+        body_limit::payload_too_large,
+        compression::compression_layer,
+        cors::{cors_layer, parse_allowed_origins},
+        metrics::track_metrics,
+        request_logger::request_logger,
+    },
     routes,
-    services::auth::AuthService,
+    services::{auth::AuthService, click_buffer, link_health, preview_jobs},
 };
 
-use axum::routing::get;
-use axum::{
-    http::{HeaderName, Method},
-    middleware::{from_fn, from_fn_with_state},
-    Router,
-};
+use axum::{middleware::from_fn, Router};
 use dotenv::dotenv;
 use std::env;
 use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
+use std::time::Duration;
+use tokio::signal;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+/// How long shutdown waits for background preview fetches before dropping them
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Resolves on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutdown signal received, no longer accepting connections");
+}
+
 #[tokio::main]
 async fn main() {
     // Load environment variables
@@ -29,6 +62,9 @@ async fn main() {
     // Initialize logging
     init_logging();
 
+    // Install the metrics recorder before anything records a metric
+    let metrics_handle = init_metrics();
+
     // Database connection
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let pool = database::create_pool(&database_url).await;
@@ -39,27 +75,86 @@ async fn main() {
         std::process::exit(1);
     }
 
+    database::spawn_idempotency_key_cleanup(pool.clone());
+    database::spawn_refresh_token_cleanup(pool.clone());
+    database::spawn_expired_link_cleanup(pool.clone());
+
     // JWT secret
     let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
     let auth_service = AuthService::new(pool.clone(), jwt_secret.clone());
-    let frontend_request_url =
-        env::var("FRONTEND_REQUEST_URL").expect("FRONTEND_REQUEST_URL must be set");
-    let cors = CorsLayer::new()
-        .allow_origin([frontend_request_url.parse().unwrap()])
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([
-            HeaderName::from_static("authorization"),
-            HeaderName::from_static("content-type"),
-        ])
-        .allow_credentials(true);
+    // Comma-separated origins allowed to call the API, defaulting to the frontend's
+    let allowed_origins = env::var("CORS_ALLOWED_ORIGINS")
+        .ok()
+        .filter(|origins| !origins.trim().is_empty())
+        .or_else(|| env::var("FRONTEND_REQUEST_URL").ok())
+        .expect("CORS_ALLOWED_ORIGINS or FRONTEND_REQUEST_URL must be set");
+    let allowed_origins = parse_allowed_origins(&allowed_origins).unwrap_or_else(|e| panic!("{e}"));
+    tracing::info!("CORS allowed origins: {allowed_origins:?}");
+    let cors = cors_layer(allowed_origins);
+
+    // Link routes share one cache so writes invalidate what reads populated
+    let link_state = routes::LinkState::new(pool.clone());
+
+    // Preview fetches run in a tracked worker so shutdown can let the current batch finish
+    let preview_tasks = TaskTracker::new();
+    let shutdown = CancellationToken::new();
+    preview_jobs::spawn_preview_worker(
+        pool.clone(),
+        link_state.cache.clone(),
+        link_state.previews.clone(),
+        &preview_tasks,
+        shutdown.clone(),
+    );
+    link_health::spawn_link_health_checker(pool.clone(), link_state.cache.clone());
+    // Counted clicks are written in batches; the flusher writes what's left on shutdown
+    let click_flusher = click_buffer::spawn_click_flusher(
+        pool.clone(),
+        link_state.cache.clone(),
+        link_state.clicks.buffer.clone(),
+        shutdown.clone(),
+    );
+
+    let host = env::var("HOST").expect("HOST must be set");
+
+    // Metrics stay unauthenticated; a separate port keeps them off the public listener
+    let metrics_port = env::var("METRICS_PORT").ok().map(|port| {
+        port.parse::<u16>()
+            .expect("METRICS_PORT must be a valid number")
+    });
+    let metrics_router = create_metrics_router(metrics_handle);
 
     // Build our application with routes
-    let app = Router::new()
+    let mut app = Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
-        .route("/health", get(routes::health::root))
-        .merge(routes::create_ping_router(pool.clone()))
         .merge(auth::create_router(pool.clone()))
-        .merge(routes::create_protected_router(pool).layer(from_fn_with_state(auth_service, auth)))
+        .merge(routes::create_api_router(
+            pool.clone(),
+            auth_service,
+            link_state,
+        ));
+
+    match metrics_port {
+        Some(metrics_port) => {
+            let metrics_addr: SocketAddr = format!("{host}:{metrics_port}")
+                .parse()
+                .expect("Invalid HOST:METRICS_PORT combination");
+            let metrics_listener = tokio::net::TcpListener::bind(metrics_addr)
+                .await
+                .expect("Failed to bind the metrics address");
+            tracing::info!("Metrics listening on {metrics_addr}");
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(metrics_listener, metrics_router).await {
+                    tracing::error!("Metrics server failed: {e}");
+                }
+            });
+        }
+        None => app = app.merge(metrics_router),
+    }
+
+    let app = app
+        .layer(from_fn(payload_too_large))
+        .layer(from_fn(track_metrics))
+        .layer(compression_layer())
         .layer(cors)
         .layer(from_fn(request_logger));
 
@@ -68,7 +163,6 @@ async fn main() {
         .expect("PORT must be set")
         .parse::<u16>()
         .expect("PORT must be a valid number");
-    let host = env::var("HOST").expect("HOST must be set");
     let addr: SocketAddr = format!("{host}:{port}")
         .parse()
         .expect("Invalid HOST:PORT combination");
@@ -77,5 +171,32 @@ async fn main() {
         .expect("Failed to bind to address");
     tracing::info!("Server listening on {addr}");
 
-    axum::serve(listener, app).await.expect("Server failed");
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .expect("Server failed");
+
+    // In-flight requests are done; stop the worker from claiming more jobs and write the
+    // clicks they buffered
+    shutdown.cancel();
+    if let Err(e) = click_flusher.await {
+        tracing::error!("Click flusher failed: {e}");
+    }
+    preview_tasks.close();
+    let pending = preview_tasks.len();
+    if pending > 0 {
+        tracing::info!("Waiting up to {SHUTDOWN_DRAIN_TIMEOUT:?} for {pending} preview tasks");
+    }
+    let timed_out = tokio::time::timeout(SHUTDOWN_DRAIN_TIMEOUT, preview_tasks.wait())
+        .await
+        .is_err();
+    let dropped = if timed_out { preview_tasks.len() } else { 0 };
+    tracing::info!(
+        drained = pending.saturating_sub(dropped),
+        dropped = dropped,
+        "Preview tasks finished, shutting down"
+    );
 }
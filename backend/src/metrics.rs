@@ -0,0 +1,50 @@
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+/// Requests handled, labelled by method, route and status
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+/// Time spent handling requests, labelled like [`HTTP_REQUESTS_TOTAL`]
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+/// Preview fetches that produced a preview or found the stored one still current
+pub const PREVIEW_FETCH_SUCCESS: &str = "preview_fetch_success";
+/// Preview fetches that failed for any reason other than a timeout
+pub const PREVIEW_FETCH_FAILURE: &str = "preview_fetch_failure";
+/// Preview fetches that gave up after the fetch timeout
+pub const PREVIEW_TIMEOUT: &str = "preview_timeout";
+
+/// Upper bounds of the request duration histogram buckets, in seconds
+const REQUEST_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Installs the global Prometheus recorder
+///
+/// Must run once at startup, before anything records a metric; the returned handle
+/// renders everything recorded since.
+pub fn init_metrics() -> PrometheusHandle {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+            REQUEST_DURATION_BUCKETS,
+        )
+        .expect("request duration buckets are not empty")
+        .install_recorder()
+        .expect("Failed to install the Prometheus recorder")
+}
+
+/// Router serving `GET /metrics` in the Prometheus text exposition format
+pub fn create_metrics_router(handle: PrometheusHandle) -> Router {
+    Router::new()
+        .route("/metrics", get(render_metrics))
+        .with_state(handle)
+}
+
+async fn render_metrics(State(handle): State<PrometheusHandle>) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        handle.render(),
+    )
+}
@@ -1,20 +1,31 @@
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{header, HeaderName, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use jsonwebtoken::{decode, errors::ErrorKind, DecodingKey};
 use uuid::Uuid;
 
-use crate::{api::ErrorResponse, models::auth::Claims, services::auth::AuthService};
+use crate::{
+    api::ErrorResponse,
+    models::auth::{Claims, UserRole},
+    services::auth::{access_token_validation, AuthService},
+};
 
 #[derive(Clone, Debug)]
 pub struct AuthUser {
     pub id: Uuid,
     pub email: String,
     pub username: String,
+    pub role: UserRole,
+}
+
+impl AuthUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::Admin
+    }
 }
 
 impl From<Claims> for AuthUser {
@@ -23,42 +34,187 @@ impl From<Claims> for AuthUser {
             id: claims.sub,
             email: claims.email,
             username: claims.username,
+            role: claims.role,
         }
     }
 }
 
+/// Response for a request whose credentials are missing or invalid
+type Unauthorized = (StatusCode, [(HeaderName, &'static str); 1], ErrorResponse);
+
+/// 401 with code `UNAUTHORIZED` and the `WWW-Authenticate` challenge
+///
+/// Used for every authentication failure, so clients can tell "sign in again" apart
+/// from the 403 `FORBIDDEN` that handlers return when the user doesn't own a resource.
+fn unauthorized(message: impl Into<String>) -> Unauthorized {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        ErrorResponse::new(message).with_code("UNAUTHORIZED"),
+    )
+}
+
+/// 401 for a bearer token that failed validation
+///
+/// Expired tokens get the code `TOKEN_EXPIRED` instead, telling clients to get a new
+/// access token from /api/auth/refresh rather than sending the user back to login. Why
+/// validation failed is only logged, so clients can't probe the signing setup.
+fn rejected_token(error: jsonwebtoken::errors::Error) -> Unauthorized {
+    tracing::debug!("Rejected bearer token: {error}");
+    if matches!(error.kind(), ErrorKind::ExpiredSignature) {
+        let (status, headers, response) = unauthorized("Token has expired");
+        return (status, headers, response.with_code("TOKEN_EXPIRED"));
+    }
+    unauthorized("Invalid token")
+}
+
+/// Decodes and validates a bearer token into the authenticated user
+///
+/// Besides the signature, the token must be unexpired, already valid (`nbf`) and issued by
+/// this server's `JWT_ISSUER`.
+fn authenticate(
+    auth_service: &AuthService,
+    token: &str,
+) -> Result<AuthUser, jsonwebtoken::errors::Error> {
+    let jwt_secret = auth_service.get_jwt_secret();
+    let token_data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &access_token_validation(auth_service.get_jwt_issuer()),
+    )?;
+
+    Ok(AuthUser::from(token_data.claims))
+}
+
+/// The token from an `Authorization: Bearer <token>` header; the scheme is case-insensitive
+fn bearer_token(request: &Request<Body>) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|auth_header| auth_header.to_str().ok())
+        .and_then(|auth_str| auth_str.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("Bearer"))
+        .map(|(_, token)| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// Rejects requests without a valid bearer token before the handler runs
+///
+/// Handlers behind this layer can extract `Extension<AuthUser>` unconditionally and only
+/// need to check ownership themselves.
 pub async fn auth(
     State(auth_service): State<AuthService>,
     mut request: Request<Body>,
     next: Next,
-) -> Result<Response, (StatusCode, ErrorResponse)> {
+) -> Result<Response, Unauthorized> {
     // Get the token from the Authorization header
-    let token = request
-        .headers()
-        .get("Authorization")
-        .and_then(|auth_header| auth_header.to_str().ok())
-        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
-        .ok_or_else(|| {
-            let error = ErrorResponse::new("Missing or invalid authorization header")
-                .with_code("UNAUTHORIZED");
-            (StatusCode::UNAUTHORIZED, error)
-        })?;
-
-    // Validate the token
-    let jwt_secret = auth_service.get_jwt_secret();
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|e| {
-        let error = ErrorResponse::new(format!("Invalid token: {e}")).with_code("INVALID_TOKEN");
-        (StatusCode::UNAUTHORIZED, error)
-    })?;
+    let token = bearer_token(&request)
+        .ok_or_else(|| unauthorized("Missing or invalid authorization header"))?;
 
-    // Add the claims to the request extensions
-    let auth_user = AuthUser::from(token_data.claims);
+    // Validate the token and add the user to the request extensions
+    let auth_user = authenticate(&auth_service, token).map_err(rejected_token)?;
     request.extensions_mut().insert(auth_user);
 
     Ok(next.run(request).await)
 }
+
+/// Like [`auth`], but lets requests without an `Authorization` header through
+///
+/// Handlers behind this layer receive `Option<Extension<AuthUser>>`. A token
+/// that is present but invalid is still rejected with 401.
+pub async fn optional_auth(
+    State(auth_service): State<AuthService>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Result<Response, Unauthorized> {
+    if let Some(token) = bearer_token(&request) {
+        let auth_user = authenticate(&auth_service, token).map_err(rejected_token)?;
+        request.extensions_mut().insert(auth_user);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Rejects requests from users without `required` before the handler runs
+///
+/// Must be layered inside [`auth`] so the authenticated user is already in the request
+/// extensions. Admins pass every role check. Apply it with
+/// `route_layer(from_fn_with_state(UserRole::Admin, require_role))`.
+pub async fn require_role(
+    State(required): State<UserRole>,
+    request: Request<Body>,
+    next: Next,
+) -> Result<Response, (StatusCode, ErrorResponse)> {
+    let Some(user) = request.extensions().get::<AuthUser>() else {
+        let error = ErrorResponse::new("Authentication required").with_code("UNAUTHORIZED");
+        return Err((StatusCode::UNAUTHORIZED, error));
+    };
+
+    if user.role != required && !user.is_admin() {
+        let error = ErrorResponse::new("You don't have permission to access this resource")
+            .with_code("FORBIDDEN");
+        return Err((StatusCode::FORBIDDEN, error));
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app_requiring(role: UserRole) -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(from_fn_with_state(role, require_role))
+    }
+
+    fn user_with(role: UserRole) -> AuthUser {
+        AuthUser {
+            id: Uuid::new_v4(),
+            email: "mock@example.com".to_string(),
+            username: "mock".to_string(),
+            role,
+        }
+    }
+
+    async fn status_for(required: UserRole, user: Option<AuthUser>) -> StatusCode {
+        let mut request = Request::get("/").body(Body::empty()).unwrap();
+        if let Some(user) = user {
+            request.extensions_mut().insert(user);
+        }
+        app_requiring(required)
+            .oneshot(request)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn admin_route_rejects_regular_users() {
+        let status = status_for(UserRole::Admin, Some(user_with(UserRole::User))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn admin_route_admits_admins() {
+        let status = status_for(UserRole::Admin, Some(user_with(UserRole::Admin))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn admins_pass_every_role_check() {
+        let status = status_for(UserRole::User, Some(user_with(UserRole::Admin))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn missing_user_is_unauthorized() {
+        assert_eq!(
+            status_for(UserRole::User, None).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
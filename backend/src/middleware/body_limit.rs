@@ -0,0 +1,123 @@
+use axum::{
+    body::Body,
+    extract::DefaultBodyLimit,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::api::ErrorResponse;
+
+/// Largest request body accepted by routes without a limit of their own
+pub const DEFAULT_BODY_LIMIT: usize = 256 * 1024;
+/// Largest bookmarks file accepted by the import endpoint
+pub const IMPORT_BODY_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Layers rejecting request bodies larger than `limit` bytes with 413
+///
+/// axum's own 2 MiB default for extractors is turned off so `limit` is the only one that
+/// applies, whether the body is JSON or a multipart upload.
+pub fn body_limit(limit: usize) -> (DefaultBodyLimit, RequestBodyLimitLayer) {
+    (
+        DefaultBodyLimit::disable(),
+        RequestBodyLimitLayer::new(limit),
+    )
+}
+
+/// Gives 413 responses from [`body_limit`] the usual [`ErrorResponse`] envelope
+///
+/// Bodies announcing their size in `Content-Length` are turned away before reaching a
+/// handler, with a plain-text body; those responses are replaced here.
+pub async fn payload_too_large(req: Request<Body>, next: Next) -> Response {
+    let response = next.run(req).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE || is_json(&response) {
+        return response;
+    }
+
+    let error = ErrorResponse::new("Request body is too large").with_code("PAYLOAD_TOO_LARGE");
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(error)).into_response()
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::extract::ApiJson;
+    use axum::{
+        body::{to_bytes, Bytes},
+        middleware::from_fn,
+        routing::post,
+        Router,
+    };
+    use futures_util::stream;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const LIMIT: usize = 64;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                post(|ApiJson(body): ApiJson<Value>| async move { Json(body) }),
+            )
+            .layer(body_limit(LIMIT))
+            .layer(from_fn(payload_too_large))
+    }
+
+    fn json_post(body: Body) -> Request<Body> {
+        Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .unwrap()
+    }
+
+    fn oversized_json() -> String {
+        format!("{{\"title\": \"{}\"}}", "x".repeat(LIMIT))
+    }
+
+    async fn assert_payload_too_large(response: Response) {
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(is_json(&response));
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["success"], false);
+        assert_eq!(error["code"], "PAYLOAD_TOO_LARGE");
+    }
+
+    #[tokio::test]
+    async fn announced_oversized_body_gets_the_error_envelope() {
+        let request = json_post(Body::from(oversized_json()));
+        let response = app().oneshot(request).await.unwrap();
+        assert_payload_too_large(response).await;
+    }
+
+    #[tokio::test]
+    async fn streamed_oversized_body_gets_the_error_envelope() {
+        // Without a Content-Length the limit is only hit while the handler reads the body
+        let chunks = oversized_json()
+            .into_bytes()
+            .chunks(16)
+            .map(|chunk| Ok::<_, std::io::Error>(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let request = json_post(Body::from_stream(stream::iter(chunks)));
+        let response = app().oneshot(request).await.unwrap();
+        assert_payload_too_large(response).await;
+    }
+
+    #[tokio::test]
+    async fn body_within_the_limit_reaches_the_handler() {
+        let request = json_post(Body::from(r#"{"title": "short"}"#));
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,23 @@
+use tower_http::compression::{
+    predicate::{And, NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+/// Responses smaller than this are sent uncompressed; encoding them costs more than it saves
+const COMPRESSION_MIN_BYTES: u16 = 1024;
+
+type CompressionPredicate =
+    And<And<And<SizeAbove, NotForContentType>, NotForContentType>, NotForContentType>;
+
+/// Compresses responses with gzip or brotli, as the client's Accept-Encoding allows
+///
+/// Images such as QR codes and proxied previews are already compressed, so they are
+/// passed through untouched, as are small responses and streams.
+pub fn compression_layer() -> CompressionLayer<CompressionPredicate> {
+    CompressionLayer::new().compress_when(
+        SizeAbove::new(COMPRESSION_MIN_BYTES)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::SSE),
+    )
+}
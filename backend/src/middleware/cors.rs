@@ -0,0 +1,106 @@
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::CorsLayer;
+
+/// Parses a comma-separated list of origins such as
+/// `https://linksphere.app,http://localhost:5173`
+///
+/// Blank entries are skipped; an entry that isn't a valid header value is an error,
+/// so a typo in the configuration fails at startup instead of silently blocking clients.
+pub fn parse_allowed_origins(origins: &str) -> Result<Vec<HeaderValue>, String> {
+    origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            HeaderValue::from_str(origin.trim_end_matches('/'))
+                .map_err(|_| format!("Invalid CORS origin: {origin}"))
+        })
+        .collect()
+}
+
+/// CORS layer only answering requests from the given origins
+///
+/// Wildcards are never used because requests carry credentials. Requests from any
+/// other origin get no `Access-Control-Allow-Origin` header, so browsers block them.
+pub fn cors_layer(allowed_origins: Vec<HeaderValue>) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(allowed_origins)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static("idempotency-key"),
+            HeaderName::from_static("x-confirm-delete"),
+            HeaderName::from_static("x-request-id"),
+        ])
+        .expose_headers([
+            header::ETAG,
+            header::LINK,
+            header::RETRY_AFTER,
+            header::WWW_AUTHENTICATE,
+            HeaderName::from_static("x-request-id"),
+        ])
+        .allow_credentials(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    const ALLOWED: &str = "https://linksphere.app";
+
+    fn app() -> Router {
+        let origins = parse_allowed_origins(&format!(" {ALLOWED}/, http://localhost:5173,"))
+            .expect("Origins parse");
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(cors_layer(origins))
+    }
+
+    fn get_from(origin: &str) -> Request<Body> {
+        Request::get("/")
+            .header(header::ORIGIN, origin)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_skips_blanks_and_trailing_slashes() {
+        let origins = parse_allowed_origins("https://a.example/, ,http://localhost:5173").unwrap();
+        assert_eq!(origins, ["https://a.example", "http://localhost:5173"]);
+        assert!(parse_allowed_origins("https://ok.example,bad\norigin").is_err());
+    }
+
+    #[tokio::test]
+    async fn allowed_origin_gets_the_allow_origin_header() {
+        let response = app().oneshot(get_from(ALLOWED)).await.unwrap();
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            ALLOWED
+        );
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_CREDENTIALS],
+            "true"
+        );
+    }
+
+    #[tokio::test]
+    async fn other_origin_gets_no_allow_origin_header() {
+        let response = app()
+            .oneshot(get_from("https://evil.example"))
+            .await
+            .unwrap();
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
@@ -0,0 +1,37 @@
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{Request, Response},
+    middleware::Next,
+};
+use std::time::Instant;
+
+use crate::metrics::{HTTP_REQUESTS_TOTAL, HTTP_REQUEST_DURATION_SECONDS};
+
+/// Records the count and duration of every request
+///
+/// Requests are labelled with the route pattern, such as `/api/links/{id}`, rather than
+/// the raw path so link IDs don't each become a time series. Requests that match no
+/// route share the `unmatched` label.
+pub async fn track_metrics(req: Request<Body>, next: Next) -> Response<Body> {
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| String::from("unmatched"));
+    let method = req.method().to_string();
+    let start = Instant::now();
+
+    let response = next.run(req).await;
+
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    metrics::histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels)
+        .record(start.elapsed().as_secs_f64());
+
+    response
+}
@@ -1,2 +1,7 @@
 pub mod auth;
+pub mod body_limit;
+pub mod compression;
+pub mod cors;
+pub mod metrics;
+pub mod rate_limit;
 pub mod request_logger;
@@ -0,0 +1,288 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use moka::{future::Cache, ops::compute::Op};
+use std::{
+    env,
+    hash::Hash,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::{api::ErrorResponse, middleware::auth::AuthUser, services::analytics::client_ip};
+
+const DEFAULT_LINKS_PER_MINUTE: u32 = 30;
+const DEFAULT_ANONYMOUS_LINKS_PER_MINUTE: u32 = 5;
+const DEFAULT_CLICK_WINDOW_SECS: u64 = 60;
+/// Upper bound on remembered visitor/link pairs; the oldest are forgotten first
+const CLICK_LIMITER_CAPACITY: u64 = 100_000;
+/// Upper bound on tracked rate limit buckets; the least recently used are forgotten first
+const RATE_LIMITER_CAPACITY: u64 = 100_000;
+/// Buckets refill completely within a minute, so one idle that long is dropped
+const BUCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket limiter keyed by user ID, or by client IP for anonymous requests
+///
+/// Each key gets a bucket holding up to `capacity` tokens that refills
+/// continuously over a minute; every request consumes one token. A bucket left idle
+/// long enough to be full again is forgotten, and a fresh one takes its place.
+#[derive(Clone)]
+pub struct RateLimiter<K = Uuid> {
+    buckets: Cache<K, Bucket>,
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+impl<K: Eq + Hash + Clone + Send + Sync + 'static> RateLimiter<K> {
+    pub fn new(per_minute: u32) -> Self {
+        let capacity = f64::from(per_minute.max(1));
+        Self {
+            buckets: Cache::builder()
+                .max_capacity(RATE_LIMITER_CAPACITY)
+                .time_to_idle(BUCKET_IDLE_TIMEOUT)
+                .build(),
+            capacity,
+            refill_per_sec: capacity / 60.0,
+        }
+    }
+
+    /// Consumes a token for the key, or returns how long to wait for the next one
+    pub async fn check(&self, key: K) -> Result<(), Duration> {
+        let mut outcome = Ok(());
+        self.buckets
+            .entry(key)
+            .and_compute_with(|entry| {
+                let now = Instant::now();
+                let mut bucket = entry.map(|entry| entry.into_value()).unwrap_or(Bucket {
+                    tokens: self.capacity,
+                    last_refill: now,
+                });
+
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                } else {
+                    let wait = (1.0 - bucket.tokens) / self.refill_per_sec;
+                    outcome = Err(Duration::from_secs_f64(wait));
+                }
+                std::future::ready(Op::Put(bucket))
+            })
+            .await;
+        outcome
+    }
+}
+
+impl RateLimiter<Uuid> {
+    /// Builds the link creation limiter from `LINK_CREATE_RATE_LIMIT` (requests per minute)
+    pub fn from_env() -> Self {
+        let per_minute = env::var("LINK_CREATE_RATE_LIMIT")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_LINKS_PER_MINUTE);
+        Self::new(per_minute)
+    }
+}
+
+impl RateLimiter<String> {
+    /// Builds the anonymous link creation limiter from `ANONYMOUS_LINK_CREATE_RATE_LIMIT`
+    /// (requests per minute per client IP)
+    pub fn anonymous_from_env() -> Self {
+        let per_minute = env::var("ANONYMOUS_LINK_CREATE_RATE_LIMIT")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_ANONYMOUS_LINKS_PER_MINUTE);
+        Self::new(per_minute)
+    }
+}
+
+/// Counts at most one click per visitor per link within a time window
+///
+/// Visitors are told apart by their hashed client IP. Windows live in memory, so they
+/// reset on restart and aren't shared between instances; that only lets a few extra
+/// clicks through.
+#[derive(Clone)]
+pub struct ClickLimiter {
+    seen: Cache<(String, Uuid), ()>,
+}
+
+impl ClickLimiter {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            seen: Cache::builder()
+                .max_capacity(CLICK_LIMITER_CAPACITY)
+                .time_to_live(window)
+                .build(),
+        }
+    }
+
+    /// Builds the click limiter from `CLICK_RATE_LIMIT_WINDOW_SECS` (default 60)
+    pub fn from_env() -> Self {
+        let window_secs = env::var("CLICK_RATE_LIMIT_WINDOW_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CLICK_WINDOW_SECS);
+        Self::new(Duration::from_secs(window_secs.max(1)))
+    }
+
+    /// Whether a visitor's click on a link counts, starting a new window if it does
+    pub async fn check(&self, ip_hash: &str, link_id: Uuid) -> bool {
+        self.seen
+            .entry((ip_hash.to_string(), link_id))
+            .or_insert(())
+            .await
+            .is_fresh()
+    }
+}
+
+/// Rejects requests from users who exceeded their rate limit with 429
+///
+/// Must run after the `auth` middleware so the `AuthUser` extension is present.
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(user) = request.extensions().get::<AuthUser>() else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(user.id).await {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+/// Rejects requests from client IPs that exceeded their rate limit with 429
+///
+/// For routes that don't require authentication. Needs the server to be run with
+/// connect info; proxy headers only count when the socket address is a trusted proxy.
+pub async fn rate_limit_by_ip(
+    State(limiter): State<RateLimiter<String>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(client_ip(request.headers(), addr)).await {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let retry_after_secs = retry_after.as_secs_f64().ceil() as u64;
+    let error = ErrorResponse::new(format!(
+        "Too many requests. Try again in {retry_after_secs} seconds"
+    ))
+    .with_code("RATE_LIMITED");
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(error),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::auth::UserRole;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use tower::ServiceExt;
+
+    const LIMIT: u32 = 3;
+
+    fn app_limited_by_ip() -> Router {
+        Router::new()
+            .route("/", post(|| async { "ok" }))
+            .layer(from_fn_with_state(
+                RateLimiter::<String>::new(LIMIT),
+                rate_limit_by_ip,
+            ))
+    }
+
+    fn post_request() -> Request<Body> {
+        Request::post("/").body(Body::empty()).unwrap()
+    }
+
+    fn post_request_from(addr: SocketAddr) -> Request<Body> {
+        let mut request = post_request();
+        request.extensions_mut().insert(ConnectInfo(addr));
+        request
+    }
+
+    #[tokio::test]
+    async fn request_over_the_limit_gets_429_with_retry_after() {
+        let app = app_limited_by_ip();
+        let client = SocketAddr::from(([203, 0, 113, 7], 4000));
+
+        for _ in 0..LIMIT {
+            let response = app
+                .clone()
+                .oneshot(post_request_from(client))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(post_request_from(client))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Another client still has its own full bucket
+        let other = SocketAddr::from(([198, 51, 100, 9], 4000));
+        let response = app.oneshot(post_request_from(other)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn users_get_separate_buckets() {
+        let app = Router::new()
+            .route("/", post(|| async { "ok" }))
+            .layer(from_fn_with_state(RateLimiter::new(1), rate_limit));
+        let request_as = |id: Uuid| {
+            let mut request = post_request();
+            request.extensions_mut().insert(AuthUser {
+                id,
+                email: format!("{id}@example.com"),
+                username: id.simple().to_string(),
+                role: UserRole::User,
+            });
+            request
+        };
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let response = app.clone().oneshot(request_as(alice)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(request_as(alice)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let response = app.oneshot(request_as(bob)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
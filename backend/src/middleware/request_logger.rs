@@ -1,22 +1,37 @@
-use crate::logging::{generate_request_id, log_request};
+use crate::logging::{
+    log_request, request_id_from_header, RequestId, CURRENT_REQUEST_ID, REQUEST_ID_HEADER,
+};
 use axum::{
     body::Body,
-    http::{Request, Response},
+    http::{HeaderValue, Request, Response},
     middleware::Next,
 };
+use tracing::Instrument;
 
 pub async fn request_logger(req: Request<Body>, next: Next) -> Response<Body> {
-    let request_id = generate_request_id();
+    let request_id = request_id_from_header(
+        req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok()),
+    );
     let method = req.method().clone();
     let path = req.uri().path().to_string();
     let start = std::time::Instant::now();
 
     // Add request ID to extensions
     let mut req = req;
-    req.extensions_mut().insert(request_id.clone());
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    // Everything logged while handling the request, including spawned tasks that
+    // inherit the span, carries the request ID; error responses quote it too
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let mut response = CURRENT_REQUEST_ID
+        .scope(request_id.clone(), next.run(req).instrument(span))
+        .await;
 
-    // Process the request
-    let response = next.run(req).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
 
     // Log the request
     log_request(
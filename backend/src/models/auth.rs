@@ -27,6 +27,18 @@ pub enum UserStatus {
     PendingVerification,
 }
 
+/// What a user is allowed to do beyond managing their own links
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    #[default]
+    User,
+    Admin,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate, ToSchema, Clone)]
 pub struct User {
     #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
@@ -47,6 +59,7 @@ pub struct User {
     pub password_hash: String,
     pub gender: Gender,
     pub status: UserStatus,
+    pub role: UserRole,
     pub is_verified: bool,
     pub verification_attempts: i32,
     pub verified_at: Option<DateTime<Utc>>,
@@ -89,9 +102,20 @@ pub struct LoginRequest {
 pub struct AuthResponse {
     #[schema(example = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...")]
     pub token: String,
+    /// Exchanged at /api/auth/refresh for a new access token once `token` expires.
+    /// Works only once; every exchange returns a replacement
+    #[schema(example = "3f9a1c0e8b7d4a6f2e5c9b1d7a3f8e0c4b6d2a9f1e7c5b3d8a0f6e2c4b9d1a7f")]
+    pub refresh_token: String,
     pub user: User,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    /// Refresh token from the last login or refresh
+    #[schema(example = "3f9a1c0e8b7d4a6f2e5c9b1d7a3f8e0c4b6d2a9f1e7c5b3d8a0f6e2c4b9d1a7f")]
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct VerifyEmailRequest {
     #[validate(email(message = "Invalid email format"))]
@@ -114,8 +138,53 @@ pub struct ResendOtpRequest {
 pub struct Claims {
     pub sub: Uuid,
     pub exp: i64,
+    /// The token isn't accepted before this time
+    pub nbf: i64,
+    /// Who issued the token; must match `JWT_ISSUER`
+    pub iss: String,
+    pub email: String,
+    pub username: String,
+    /// Tokens issued before roles existed carry no role and are treated as regular users
+    #[serde(default)]
+    pub role: UserRole,
+}
+
+/// Account details shown to administrators
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSummary {
+    #[schema(example = "123e4567-e89b-12d3-a456-426614174000")]
+    pub id: Uuid,
+    #[schema(example = "user@example.com")]
     pub email: String,
+    #[schema(example = "john_doe")]
+    pub username: String,
+    pub status: UserStatus,
+    pub role: UserRole,
+    pub is_verified: bool,
+    /// Maximum number of active links the user can own
+    #[schema(example = 1000)]
+    pub link_quota: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What anyone can see about a user; never includes contact or account details
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserProfile {
+    #[schema(example = "john_doe")]
     pub username: String,
+    /// When the user joined
+    pub created_at: DateTime<Utc>,
+    /// Number of the user's active public links
+    #[schema(example = 42)]
+    pub public_link_count: i64,
+    /// Clicks across all of the user's active public links
+    #[schema(example = 1337)]
+    pub total_clicks: i64,
+}
+
+/// Canonical form used to store and look up email addresses.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
 }
 
 fn validate_username(username: &str) -> Result<(), validator::ValidationError> {
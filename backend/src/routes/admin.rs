@@ -0,0 +1,271 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use validator::Validate;
+
+use crate::{
+    api::{
+        extract::ApiJson,
+        models::{UpdateLinkQuotaRequest, ValidationErrorResponse},
+        ApiResponse, ErrorResponse, PaginationMeta,
+    },
+    database::{
+        audit::{get_audit_log, get_audit_log_count, AuditFilters},
+        models::AuditAction,
+        queries::{delete_user, get_all_users, get_users_count, set_link_quota},
+        LinkCache, PgPool,
+    },
+    middleware::auth::AuthUser,
+};
+
+const DEFAULT_USERS_PAGE_SIZE: u32 = 50;
+const MAX_USERS_PAGE_SIZE: u32 = 200;
+const DEFAULT_AUDIT_PAGE_SIZE: u32 = 50;
+const MAX_AUDIT_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct UsersQuery {
+    /// 1-based page number, defaults to 1
+    pub page: Option<u32>,
+    /// Users per page, defaults to 50 and is capped at 200
+    pub page_size: Option<u32>,
+}
+
+/// List all users
+///
+/// Returns a page of registered users, newest first. Only administrators can call it;
+/// the role check happens in the admin router's `require_role` layer.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn list_users(
+    State(pool): State<PgPool>,
+    Query(params): Query<UsersQuery>,
+) -> impl IntoResponse {
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params
+        .page_size
+        .unwrap_or(DEFAULT_USERS_PAGE_SIZE)
+        .clamp(1, MAX_USERS_PAGE_SIZE);
+    let offset = i64::from(page - 1) * i64::from(page_size);
+
+    let result = tokio::try_join!(
+        get_all_users(&pool, i64::from(page_size), offset),
+        get_users_count(&pool)
+    );
+
+    match result {
+        Ok((users, total)) => {
+            let total_items = u64::try_from(total).unwrap_or_default();
+            let pagination = PaginationMeta {
+                current_page: page,
+                page_size,
+                total_items,
+                total_pages: total_items.div_ceil(u64::from(page_size)) as u32,
+            };
+            let response = ApiResponse::success(users).with_pagination(pagination);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch users: {e}");
+            let error =
+                ErrorResponse::internal("Failed to fetch users", &e).with_code("USERS_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Outcome of deleting a user account
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedUser {
+    /// Number of links removed along with the account
+    pub deleted_links: i64,
+}
+
+/// Delete a user
+///
+/// Permanently removes an account along with its links, favorites and webhooks, so no
+/// link is ever left without an owner. Administrators can't delete their own account here.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn delete_user_handler(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(admin): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if user_id == admin.id {
+        let error = ErrorResponse::new("Administrators can't delete their own account")
+            .with_code("CANNOT_DELETE_SELF");
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    match delete_user(&pool, user_id).await {
+        Ok(Some(deleted_links)) => {
+            // The user's links may be cached by ID and are gone now
+            cache.invalidate_all();
+            tracing::info!(
+                user_id = %user_id,
+                admin_id = %admin.id,
+                deleted_links = deleted_links,
+                "User deleted"
+            );
+            let response = ApiResponse::success_with_message(
+                DeletedUser { deleted_links },
+                "User deleted successfully",
+            );
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("User not found").with_code("NOT_FOUND");
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to delete user: {e}");
+            let error =
+                ErrorResponse::internal("Failed to delete user", &e).with_code("USER_DELETE_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Change a user's link quota
+///
+/// Sets how many active links the user can own. Lowering it below the user's current
+/// count keeps their links but blocks new ones until they delete some.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn update_link_quota(
+    State(pool): State<PgPool>,
+    Extension(admin): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+    ApiJson(payload): ApiJson<UpdateLinkQuotaRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    match set_link_quota(&pool, user_id, payload.link_quota).await {
+        Ok(Some(quota)) => {
+            tracing::info!(
+                user_id = %user_id,
+                admin_id = %admin.id,
+                link_quota = quota.limit,
+                "Link quota changed"
+            );
+            let response = ApiResponse::success_with_message(quota, "Link quota updated");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("User not found").with_code("NOT_FOUND");
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to update link quota: {e}");
+            let error = ErrorResponse::internal("Failed to update link quota", &e)
+                .with_code("QUOTA_UPDATE_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Connection pool usage at the time of the request
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PoolStats {
+    /// Open connections, idle or in use
+    #[schema(example = 5)]
+    pub size: u32,
+    /// Open connections waiting to be used
+    #[schema(example = 2)]
+    pub idle: u32,
+    /// Connections currently checked out by requests or background tasks
+    #[schema(example = 3)]
+    pub num_acquired: u32,
+    /// Upper bound on open connections, from `DATABASE_MAX_CONNECTIONS`
+    #[schema(example = 5)]
+    pub max_connections: u32,
+    /// Connections kept open even when idle, from `DATABASE_MIN_CONNECTIONS`
+    #[schema(example = 0)]
+    pub min_connections: u32,
+}
+
+/// Get database pool statistics
+///
+/// Shows how many connections are open and in use, to spot the pool running out of
+/// connections. `num_acquired` staying at `max_connections` means requests are queueing.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn pool_stats(State(pool): State<PgPool>) -> impl IntoResponse {
+    let size = pool.size();
+    let idle = u32::try_from(pool.num_idle()).unwrap_or(u32::MAX).min(size);
+    let options = pool.options();
+    let stats = PoolStats {
+        size,
+        idle,
+        num_acquired: size - idle,
+        max_connections: options.get_max_connections(),
+        min_connections: options.get_min_connections(),
+    };
+    (StatusCode::OK, Json(ApiResponse::success(stats))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    /// Only include changes made by this user
+    pub actor_id: Option<Uuid>,
+    /// Only include changes of this kind
+    pub action: Option<AuditAction>,
+    /// 1-based page number, defaults to 1
+    pub page: Option<u32>,
+    /// Entries per page, defaults to 50 and is capped at 200
+    pub page_size: Option<u32>,
+}
+
+/// List the audit log
+///
+/// Returns a page of recorded link updates, deletions and transfers, newest first,
+/// optionally narrowed down to one actor or one action.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn list_audit_log(
+    State(pool): State<PgPool>,
+    Query(params): Query<AuditQuery>,
+) -> impl IntoResponse {
+    let page = params.page.unwrap_or(1).max(1);
+    let page_size = params
+        .page_size
+        .unwrap_or(DEFAULT_AUDIT_PAGE_SIZE)
+        .clamp(1, MAX_AUDIT_PAGE_SIZE);
+    let offset = i64::from(page - 1) * i64::from(page_size);
+    let filters = AuditFilters {
+        actor_id: params.actor_id,
+        action: params.action,
+    };
+
+    let result = tokio::try_join!(
+        get_audit_log(&pool, &filters, i64::from(page_size), offset),
+        get_audit_log_count(&pool, &filters)
+    );
+
+    match result {
+        Ok((entries, total)) => {
+            let total_items = u64::try_from(total).unwrap_or_default();
+            let pagination = PaginationMeta {
+                current_page: page,
+                page_size,
+                total_items,
+                total_pages: total_items.div_ceil(u64::from(page_size)) as u32,
+            };
+            let response = ApiResponse::success(entries).with_pagination(pagination);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch audit log: {e}");
+            let error = ErrorResponse::internal("Failed to fetch audit log", &e)
+                .with_code("AUDIT_LOG_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    api::{
+        extract::ApiJson,
+        models::{CreateCollectionRequest, ValidationErrorResponse},
+        ApiResponse, ErrorResponse,
+    },
+    database::{
+        queries::{
+            create_collection, delete_collection, get_collection, get_collections,
+            get_links_by_collection, is_collection_name_conflict,
+        },
+        LinkCache, PgPool,
+    },
+    middleware::auth::AuthUser,
+};
+
+/// Create a collection
+///
+/// Adds an empty collection (folder) for organizing the authenticated user's links.
+/// Names are unique per user, ignoring case.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn create_collection_handler(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    ApiJson(payload): ApiJson<CreateCollectionRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    match create_collection(&pool, user.id, payload.name.trim()).await {
+        Ok(collection) => {
+            let response =
+                ApiResponse::success_with_message(collection, "Collection created successfully");
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(e) if is_collection_name_conflict(&e) => {
+            let error = ErrorResponse::new("You already have a collection with this name")
+                .with_code("COLLECTION_EXISTS");
+            (StatusCode::CONFLICT, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to create collection: {e}");
+            let error = ErrorResponse::internal("Failed to create collection", &e)
+                .with_code("COLLECTION_CREATE_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// List collections
+///
+/// Returns the authenticated user's collections sorted by name, each with the number
+/// of active links in it.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn list_collections(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    match get_collections(&pool, user.id).await {
+        Ok(collections) => {
+            (StatusCode::OK, Json(ApiResponse::success(collections))).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to fetch collections: {e}");
+            let error = ErrorResponse::internal("Failed to fetch collections", &e)
+                .with_code("COLLECTION_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Delete a collection
+///
+/// Removes one of the authenticated user's collections. The links in it are kept and
+/// simply no longer belong to a collection.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn delete_collection_handler(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    Path(collection_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match delete_collection(&pool, collection_id, user.id).await {
+        Ok(true) => {
+            // Cached links may still point at the deleted collection
+            cache.invalidate_all();
+            let response = ApiResponse::success_with_message((), "Collection deleted successfully");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(false) => {
+            let error = ErrorResponse::new("Collection not found").with_code("NOT_FOUND");
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(collection_id = %collection_id, "Failed to delete collection: {e}");
+            let error = ErrorResponse::internal("Failed to delete collection", &e)
+                .with_code("COLLECTION_DELETE_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// List the links in a collection
+///
+/// Returns the active links filed under one of the authenticated user's collections,
+/// newest first.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_collection_links(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Path(collection_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match get_collection(&pool, collection_id, user.id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let error = ErrorResponse::new("Collection not found").with_code("NOT_FOUND");
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(collection_id = %collection_id, "Failed to fetch collection: {e}");
+            let error = ErrorResponse::internal("Failed to fetch collection", &e)
+                .with_code("COLLECTION_FETCH_ERROR");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    match get_links_by_collection(&pool, collection_id).await {
+        Ok(links) => (StatusCode::OK, Json(ApiResponse::success(links))).into_response(),
+        Err(e) => {
+            tracing::error!(collection_id = %collection_id, "Failed to fetch collection links: {e}");
+            let error =
+                ErrorResponse::internal("Failed to fetch links", &e).with_code("LINK_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
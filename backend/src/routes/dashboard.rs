@@ -0,0 +1,77 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use chrono::{TimeDelta, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    api::{ApiResponse, ErrorResponse},
+    database::{
+        models::Link,
+        queries::{
+            get_link_totals, get_most_clicked_links_by_user, get_recent_links_by_user,
+            get_user_click_count_since,
+        },
+        PgPool,
+    },
+    middleware::auth::AuthUser,
+};
+
+/// Number of links in each of the dashboard's lists
+const DASHBOARD_LIST_SIZE: i64 = 5;
+/// Window of the dashboard's recent click count
+const RECENT_CLICKS_WINDOW: TimeDelta = TimeDelta::days(7);
+
+/// Everything the home page shows about the user's links
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Dashboard {
+    /// Links that are neither deleted nor expired
+    #[schema(example = 42)]
+    pub total_links: i64,
+    /// Clicks those links received over their lifetime
+    #[schema(example = 1234)]
+    pub total_clicks: i64,
+    /// Clicks on the user's links in the last 7 days, leaving out bots
+    #[schema(example = 87)]
+    pub clicks_last_7_days: i64,
+    /// The 5 most clicked links, most clicked first
+    pub top_links: Vec<Link>,
+    /// The 5 most recently created links, newest first
+    pub recent_links: Vec<Link>,
+}
+
+/// Get the current user's dashboard
+///
+/// Returns link and click totals, the most clicked and most recent links, and the clicks
+/// of the last 7 days in one response. The queries run concurrently.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_dashboard(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let since = Utc::now() - RECENT_CLICKS_WINDOW;
+    let result = tokio::try_join!(
+        get_link_totals(&pool, user.id),
+        get_user_click_count_since(&pool, user.id, since),
+        get_most_clicked_links_by_user(&pool, user.id, DASHBOARD_LIST_SIZE),
+        get_recent_links_by_user(&pool, user.id, DASHBOARD_LIST_SIZE),
+    );
+
+    match result {
+        Ok((totals, clicks_last_7_days, top_links, recent_links)) => {
+            let dashboard = Dashboard {
+                total_links: totals.links,
+                total_clicks: totals.clicks,
+                clicks_last_7_days,
+                top_links,
+                recent_links,
+            };
+            (StatusCode::OK, Json(ApiResponse::success(dashboard))).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to load dashboard: {e}");
+            let error = ErrorResponse::internal("Failed to load dashboard", &e)
+                .with_code("DASHBOARD_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
@@ -8,9 +8,12 @@ use axum::{
 use serde::Serialize;
 use serde_json::json;
 use sqlx::PgPool;
-use std::env;
+use std::{env, time::Duration};
 use utoipa::ToSchema;
 
+/// How long the readiness probe waits for the database before reporting it unavailable
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[derive(Serialize, ToSchema)]
 struct HealthStatus {
     name: String,
@@ -33,6 +36,37 @@ pub async fn root() -> impl IntoResponse {
     (StatusCode::OK, axum::Json(response)).into_response()
 }
 
+/// Readiness probe
+///
+/// Returns 200 only when the database answers a trivial query, 503 otherwise.
+/// Unauthenticated so orchestrators can call it.
+pub async fn ready(State(pool): State<PgPool>) -> impl IntoResponse {
+    let check = tokio::time::timeout(READY_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(&pool));
+
+    match check.await {
+        Ok(Ok(_)) => {
+            let response = ApiResponse::success_with_message(
+                json!({ "status": "ready", "database": "connected" }),
+                "Service is ready",
+            );
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Database is unavailable: {e}");
+            let error =
+                ErrorResponse::internal("Database is unavailable", &e).with_code("DB_UNAVAILABLE");
+            (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response()
+        }
+        Err(_) => {
+            let error = ErrorResponse::new(format!(
+                "Database did not respond within {READY_CHECK_TIMEOUT:?}"
+            ))
+            .with_code("DB_UNAVAILABLE");
+            (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response()
+        }
+    }
+}
+
 /// Health check endpoint protected by admin token
 #[utoipa::path(
     get,
@@ -61,6 +95,7 @@ pub async fn health_check(State(pool): State<PgPool>, headers: HeaderMap) -> imp
             message: "Missing or invalid authorization header".to_string(),
             data: json!({ "code": "UNAUTHORIZED" }),
             pagination: None,
+            meta: None,
             timestamp: chrono::Utc::now(),
         };
         return (StatusCode::UNAUTHORIZED, Json(response));
@@ -76,6 +111,7 @@ pub async fn health_check(State(pool): State<PgPool>, headers: HeaderMap) -> imp
                     "database": "connected"
                 }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             (StatusCode::OK, Json(response))
@@ -90,6 +126,7 @@ pub async fn health_check(State(pool): State<PgPool>, headers: HeaderMap) -> imp
                     "error": e.to_string()
                 }),
                 pagination: None,
+                meta: None,
                 timestamp: chrono::Utc::now(),
             };
             (StatusCode::SERVICE_UNAVAILABLE, Json(response))
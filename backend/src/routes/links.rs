@@ -1,32 +1,184 @@
 use axum::{
-    extract::{Extension, Path, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{ConnectInfo, Extension, Multipart, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
     Json,
 };
 
-use crate::database::queries::{create_link, increment_click_count};
+use crate::database::queries::{
+    claim_idempotency_key, complete_idempotency_key, count_links_with_title, create_link,
+    find_link_by_url, get_click_count, get_click_stats, get_clicks_for_link, get_collection,
+    get_idempotency_key, get_link_by_slug, get_link_quota, get_link_thumbnail, get_links_by_ids,
+    get_links_by_user, get_links_changed_since, get_links_count, get_preview_validators,
+    get_tag_counts, get_unique_click_count, is_slug_conflict, mark_preview_unchanged, patch_link,
+    record_click, release_idempotency_key, update_link, update_link_preview, BufferedClick,
+    ClickBucket, ClickFilters, LinkFilters, LinkPatch, LinkSort, LinkUpdate, NewLink, SearchFields,
+};
 use crate::{
-    api::{models::CreateLinkRequest, ApiResponse, ErrorResponse},
-    database::{self, models::Link, PgPool},
-    middleware::auth::AuthUser,
-    services::link_preview::fetch_link_preview,
+    api::{
+        extract::ApiJson,
+        models::{
+            normalize_tags, parse_link_url, BatchLinksRequest, ClaimLinkRequest, CreateLinkRequest,
+            PaginatedResponse, TransferLinkRequest, UndoDeleteRequest, UpdateLinkRequest,
+            ValidationErrorResponse, MAX_TAGS, MAX_TAG_LENGTH, MAX_TITLE_LENGTH,
+        },
+        ApiResponse, ErrorCode, ErrorResponse,
+    },
+    database::{
+        self,
+        models::{
+            ClickEvent, Link, LinkHealth, LinkPreview, LinkQuota, LinkStats, LinkVisibility,
+            PreviewStatus, PreviewValidators, ThumbnailVariant,
+        },
+        pagination::Cursor,
+        LinkCache, PgPool,
+    },
+    middleware::{auth::AuthUser, body_limit::IMPORT_BODY_LIMIT},
+    routes::ClickTracking,
+    services::{
+        analytics::{client_ip, hash_ip, is_bot_user_agent},
+        bookmarks::parse_bookmarks_file,
+        link_health::{check_url, find_unreachable, record_check},
+        link_preview::{fetch_link_preview, LinkPreviewError, PreviewFetch},
+        markdown::render_markdown,
+        preview_image::{get_preview_image, PreviewImageError, ProxiedImage},
+        preview_jobs::{add_thumbnails, PreviewQueue},
+        qr::{link_qr_png, DEFAULT_QR_SIZE, MAX_QR_SIZE, MIN_QR_SIZE},
+        undo::{create_undo_token, verify_undo_token},
+        url::normalize_url,
+        webhooks::{dispatch_link_event, WebhookEvent},
+    },
 };
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, net::SocketAddr};
+use tokio::sync::mpsc;
+use url::form_urlencoded;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-type LinkResponse = ApiResponse<Link>;
-type LinksResponse = ApiResponse<Vec<Link>>;
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// How long an anonymous link lives unless someone claims it
+const UNCLAIMED_LINK_TTL: TimeDelta = TimeDelta::hours(24);
+
+/// Number of serialized links buffered ahead of a slow export download
+const EXPORT_CHANNEL_CAPACITY: usize = 16;
+
+type LinksResponse = PaginatedResponse<Link>;
+type LinkStatsResponse = ApiResponse<LinkStats>;
+
+/// Query parameters for listing links
+///
+/// `tag` may be repeated and is read from the raw query string by [`tag_params`].
+#[derive(Debug, Deserialize)]
+pub struct LinksQuery {
+    /// Only return links created at or after this RFC3339 timestamp
+    pub created_after: Option<String>,
+    /// Only return links created at or before this RFC3339 timestamp
+    pub created_before: Option<String>,
+    /// One of `created_asc`, `created_desc` (default), `clicks_desc`, `title_asc`, `recently_clicked`
+    pub sort: Option<String>,
+    /// One of `alive`, `dead`, `unknown`; only the caller's own links are returned
+    pub health: Option<String>,
+    /// Page size, 1 to 200; without it or `cursor` every matching link is returned
+    pub limit: Option<i64>,
+    /// `next_cursor` from the previous page, or a cursor from the `Link` header
+    pub cursor: Option<String>,
+}
+
+/// Every `tag` query parameter, normalized like stored tags
+///
+/// `Query` can't collect repeated keys, so they are parsed from the query string directly.
+fn tag_params(uri: &Uri) -> Vec<String> {
+    let tags: Vec<String> = form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+        .filter(|(key, _)| key == "tag")
+        .map(|(_, value)| value.into_owned())
+        .collect();
+    normalize_tags(&tags)
+}
+
+const DEFAULT_LINKS_PAGE_SIZE: i64 = 50;
+const MAX_LINKS_PAGE_SIZE: i64 = 200;
+
+/// Builds an RFC 8288 `Link` header pointing at the next and previous pages
+///
+/// The targets are the request's own path and query with `cursor` swapped out, so every
+/// other filter carries over. They are relative references, resolved against the request URL.
+fn pagination_link_header(
+    uri: &Uri,
+    next_cursor: Option<&str>,
+    prev_cursor: Option<&str>,
+) -> Option<HeaderValue> {
+    let page_url = |cursor: &str| {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        for (key, value) in form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()) {
+            if key != "cursor" {
+                query.append_pair(&key, &value);
+            }
+        }
+        query.append_pair("cursor", cursor);
+        format!("{}?{}", uri.path(), query.finish())
+    };
+
+    let links: Vec<String> = [(next_cursor, "next"), (prev_cursor, "prev")]
+        .into_iter()
+        .filter_map(|(cursor, rel)| {
+            cursor.map(|cursor| format!("<{}>; rel=\"{rel}\"", page_url(cursor)))
+        })
+        .collect();
+    if links.is_empty() {
+        return None;
+    }
+    HeaderValue::from_str(&links.join(", ")).ok()
+}
+
+/// Parses an optional RFC3339 query parameter into a UTC timestamp
+fn parse_timestamp_param(
+    name: &str,
+    value: Option<&str>,
+) -> Result<Option<DateTime<Utc>>, ErrorResponse> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| Some(ts.with_timezone(&Utc)))
+        .map_err(|_| {
+            ErrorResponse::new(format!(
+                "Invalid `{name}` timestamp, expected RFC3339 (e.g. 2025-01-31T00:00:00Z)"
+            ))
+            .with_code(ErrorCode::InvalidTimestamp)
+        })
+}
+
 /// Get all links
 ///
-/// Returns a list of all links in the system
-/// Requires Authentication: Bearer token from /api/auth/login
+/// Returns a list of all links in the system, optionally filtered by tags and creation window.
+/// Unauthenticated callers only see public links; authenticated users also see their own private links.
+/// Optional Authentication: Bearer token from /api/auth/login
 #[utoipa::path(
     get,
     path = "/api/links",
+    params(
+        ("tag" = Option<Vec<String>>, Query, description = "Only return links carrying this tag, case-insensitive; repeat to require several tags"),
+        ("created_after" = Option<String>, Query, description = "Only return links created at or after this RFC3339 timestamp"),
+        ("created_before" = Option<String>, Query, description = "Only return links created at or before this RFC3339 timestamp"),
+        ("sort" = Option<String>, Query, description = "Sort order: created_asc, created_desc (default), clicks_desc, title_asc or recently_clicked"),
+        ("health" = Option<String>, Query, description = "Only return the caller's own links in this health state: alive, dead or unknown"),
+        ("limit" = Option<i64>, Query, description = "Page size, 1 to 200 (default 50); without it or cursor every matching link is returned"),
+        ("cursor" = Option<String>, Query, description = "next_cursor from the previous page, or a target from the Link header")
+    ),
     responses(
-        (status = 200, description = "Links retrieved successfully", body = LinksResponse),
-        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 200, description = "Links retrieved successfully; when paging, a `Link` header carries rel=\"next\" and rel=\"prev\" URLs", body = PaginatedResponse<Link>),
+        (status = 401, description = "Invalid JWT token, or `health` used without one", body = ErrorResponse),
+        (status = 422, description = "Invalid timestamp filter, sort order, health or cursor", body = ErrorResponse),
         (status = 500, description = "Server error", body = ErrorResponse)
     ),
     security(
@@ -34,250 +186,2961 @@ type LinksResponse = ApiResponse<Vec<Link>>;
     ),
     tag = "links"
 )]
-pub async fn get_links(State(pool): State<PgPool>) -> impl IntoResponse {
-    match database::get_all_links(&pool).await {
-        Ok(links) => {
-            let response = ApiResponse::success(links);
+pub async fn get_links(
+    State(pool): State<PgPool>,
+    user: Option<Extension<AuthUser>>,
+    uri: Uri,
+    Query(params): Query<LinksQuery>,
+) -> impl IntoResponse {
+    let created_after =
+        match parse_timestamp_param("created_after", params.created_after.as_deref()) {
+            Ok(ts) => ts,
+            Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+        };
+    let created_before =
+        match parse_timestamp_param("created_before", params.created_before.as_deref()) {
+            Ok(ts) => ts,
+            Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+        };
+    let sort = match params
+        .sort
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        None => LinkSort::default(),
+        Some(value) => match value.parse::<LinkSort>() {
+            Ok(sort) => sort,
+            Err(()) => {
+                let error = ErrorResponse::new(format!(
+                    "Invalid sort `{value}`, expected one of created_asc, created_desc, clicks_desc, title_asc, recently_clicked"
+                ))
+                .with_code(ErrorCode::InvalidSort);
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+            }
+        },
+    };
+
+    let viewer_id = user.map(|Extension(user)| user.id);
+    let health = match params
+        .health
+        .as_deref()
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+    {
+        None => None,
+        Some(_) if viewer_id.is_none() => {
+            let error = ErrorResponse::new("Sign in to filter your links by health")
+                .with_code(ErrorCode::Unauthorized);
+            return (StatusCode::UNAUTHORIZED, Json(error)).into_response();
+        }
+        Some(value) => match value.parse::<LinkHealth>() {
+            Ok(health) => Some(health),
+            Err(()) => {
+                let error = ErrorResponse::new(format!(
+                    "Invalid health `{value}`, expected one of alive, dead, unknown"
+                ))
+                .with_code(ErrorCode::InvalidHealth);
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+            }
+        },
+    };
+
+    // Cursors are opaque to clients; they name the link a page starts after or ends before
+    let paging = params.limit.is_some() || params.cursor.is_some();
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LINKS_PAGE_SIZE)
+        .clamp(1, MAX_LINKS_PAGE_SIZE);
+    let cursor = match params.cursor.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(cursor) => match cursor.parse::<Cursor>() {
+            Ok(cursor) => Some(cursor),
+            Err(()) => {
+                let error = ErrorResponse::new(
+                    "Invalid `cursor`, pass `next_cursor` from the previous page",
+                )
+                .with_code(ErrorCode::InvalidCursor);
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+            }
+        },
+    };
+
+    let filters = LinkFilters {
+        tags: tag_params(&uri),
+        viewer_id,
+        created_after,
+        created_before,
+        health,
+        sort,
+        limit: paging.then_some(limit),
+        cursor,
+    };
+
+    let result = tokio::try_join!(
+        database::get_all_links(&pool, &filters),
+        get_links_count(&pool, &filters)
+    );
+
+    match result {
+        Ok((page, total)) if paging => {
+            let next_cursor = page.next_cursor.map(|cursor| cursor.to_string());
+            let prev_cursor = page.prev_cursor.map(|cursor| cursor.to_string());
+            let link_header =
+                pagination_link_header(&uri, next_cursor.as_deref(), prev_cursor.as_deref());
+
+            let response: LinksResponse =
+                PaginatedResponse::new(redact_links(page.items, viewer_id), total)
+                    .with_next_cursor(next_cursor);
+            let mut response = (StatusCode::OK, Json(response)).into_response();
+            if let Some(link_header) = link_header {
+                response.headers_mut().insert(header::LINK, link_header);
+            }
+            response
+        }
+        Ok((page, total)) => {
+            let response: LinksResponse =
+                PaginatedResponse::new(redact_links(page.items, viewer_id), total);
             (StatusCode::OK, Json(response)).into_response()
         }
         Err(e) => {
-            let error = ErrorResponse::new(format!("Failed to fetch links: {e}"))
-                .with_code("LINKS_FETCH_ERROR");
+            tracing::error!("Failed to fetch links: {e}");
+            let error = ErrorResponse::internal("Failed to fetch links", &e)
+                .with_code(ErrorCode::LinksFetchError);
             (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
         }
     }
 }
 
-/// Create a new link
-///
-/// Creates a new link with the provided details. The user ID is automatically extracted from the JWT token.
-/// Requires Authentication: Bearer token from /api/auth/login
+/// List tags
 ///
-#[utoipa::path(
-    post,
-    path = "/api/links",
-    request_body = CreateLinkRequest,
-    responses(
-        (status = 201, description = "Link created successfully", body = LinkResponse),
-        (status = 422, description = "Invalid request data (URL format, title/description length)", body = ErrorResponse),
-        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
-        (status = 500, description = "Server error", body = ErrorResponse)
-    ),
-    security(
-        ("bearer_auth" = [])
-    ),
-    tag = "links"
-)]
-pub async fn handle_create_link(
+/// Returns every tag in use with the number of links carrying it, most used first.
+/// Only public links are counted for unauthenticated callers; authenticated users also
+/// see the tags on their own private links.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_tags(
     State(pool): State<PgPool>,
-    Extension(user): Extension<AuthUser>,
-    Json(payload): Json<CreateLinkRequest>,
+    user: Option<Extension<AuthUser>>,
 ) -> impl IntoResponse {
-    // Validate the request payload
-    if let Err(validation_errors) = payload.validate() {
-        let error = ErrorResponse::new(format!("Validation error: {validation_errors}"))
-            .with_code("VALIDATION_ERROR");
-        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
-    }
+    let viewer_id = user.map(|Extension(user)| user.id);
 
-    // Validate URL format
-    if let Err(url_error) = payload.validate_url() {
-        let error =
-            ErrorResponse::new(format!("Invalid URL format: {url_error}")).with_code("INVALID_URL");
-        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    match get_tag_counts(&pool, viewer_id).await {
+        Ok(tags) => {
+            let response = ApiResponse::success(tags);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch tags: {e}");
+            let error = ErrorResponse::internal("Failed to fetch tags", &e)
+                .with_code(ErrorCode::TagsFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
     }
+}
 
-    // Create the link first without preview
-    let link = match create_link(
-        &pool,
-        payload.url.clone(),
-        payload.title,
-        payload.description,
-        user.id,
-        None, // No preview initially
-    )
-    .await
-    {
-        Ok(link) => link,
-        Err(e) => {
-            let error = ErrorResponse::new(format!("Failed to create link: {e}"))
-                .with_code("LINK_CREATE_ERROR");
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+#[derive(Debug, Deserialize)]
+pub struct LinkQuery {
+    /// `html` to also return the description rendered from markdown
+    pub render: Option<String>,
+}
+
+/// A link with its markdown description rendered to sanitized HTML
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RenderedLink {
+    #[serde(flatten)]
+    pub link: Link,
+    /// The description rendered to HTML, with scripts and event handlers stripped
+    #[schema(example = "<p>The home page of the <em>Rust</em> programming language</p>")]
+    pub description_html: String,
+}
+
+/// Get a link by ID
+///
+/// Private links are only returned to their owner; anyone else gets a 404 so
+/// the link's existence isn't leaked. With `render=html` the response also carries
+/// `description_html`, the markdown description rendered to sanitized HTML.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_link_by_id_handler(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    user: Option<Extension<AuthUser>>,
+    Path(link_id): Path<Uuid>,
+    Query(params): Query<LinkQuery>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let render_html = match params.render.as_deref().map(str::trim) {
+        None | Some("") => false,
+        Some("html") => true,
+        Some(value) => {
+            let error = ErrorResponse::new(format!("Invalid render `{value}`, expected html"))
+                .with_code(ErrorCode::InvalidRender);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
         }
     };
+    let viewer_id = user.map(|Extension(user)| user.id);
 
-    // Spawn a task to fetch and update the preview asynchronously
-    let pool_clone = pool.clone();
-    let url = payload.url.clone();
-    let link_id = link.id;
+    match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.is_visible_to(viewer_id) => {
+            let etag = link_etag(&link);
+            if etag_matches(&headers, &etag) {
+                return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+            }
 
-    tokio::spawn(async move {
-        if let Ok(preview) = fetch_link_preview(&url).await {
-            // Update the link with the preview
-            let _ = sqlx::query!(
-                r#"
-                UPDATE links 
-                SET preview = $1 
-                WHERE id = $2
-                "#,
-                serde_json::to_value(preview).ok() as _,
-                link_id
-            )
-            .execute(&pool_clone)
-            .await;
+            let link = link.redacted_for(viewer_id);
+            if render_html {
+                let description_html = render_markdown(&link.description);
+                let response = ApiResponse::success(RenderedLink {
+                    link,
+                    description_html,
+                });
+                return (StatusCode::OK, [(header::ETAG, etag)], Json(response)).into_response();
+            }
+            let response = ApiResponse::success(link);
+            (StatusCode::OK, [(header::ETAG, etag)], Json(response)).into_response()
         }
-    });
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
 
-    // Return the created link immediately
-    let response = ApiResponse::success_with_message(link, "Link created successfully");
-    (StatusCode::CREATED, Json(response)).into_response()
+/// Weak ETag for a link, derived from its ID and last modification time
+///
+/// Every write to a link bumps `updated_at`, so the tag changes whenever the link does.
+/// It is weak because the response envelope carries a fresh timestamp on each request.
+fn link_etag(link: &Link) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(link.id.as_bytes());
+    hasher.update(link.updated_at.timestamp_micros().to_be_bytes());
+    format!("W/\"{}\"", hex::encode(&hasher.finalize()[..16]))
 }
 
-/// Track a link click
+/// Whether the `If-None-Match` header lists the given ETag, using weak comparison
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == opaque(etag))
+}
+
+/// Strips owner-only fields from the links `viewer_id` doesn't own
+fn redact_links(links: Vec<Link>, viewer_id: Option<Uuid>) -> Vec<Link> {
+    links
+        .into_iter()
+        .map(|link| link.redacted_for(viewer_id))
+        .collect()
+}
+
+/// Number of suggestions returned by the related links endpoint
+const RELATED_LINKS_LIMIT: i64 = 5;
+
+/// Get links related to a link
 ///
-/// Increments the click count for a link
-pub async fn track_click(
+/// Suggests up to 5 other public links sharing the most tags with the given link, or the
+/// most title words when it has no tags. Private links are only available to their owner.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_related_links(
     State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    user: Option<Extension<AuthUser>>,
     Path(link_id): Path<Uuid>,
 ) -> impl IntoResponse {
-    match increment_click_count(&pool, link_id).await {
+    let viewer_id = user.map(|Extension(user)| user.id);
+
+    match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.is_visible_to(viewer_id) => {}
         Ok(_) => {
-            let response = ApiResponse::success(());
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    match database::queries::get_related_links(&pool, link_id, RELATED_LINKS_LIMIT).await {
+        Ok(links) => {
+            let response = ApiResponse::success(redact_links(links, viewer_id));
             (StatusCode::OK, Json(response)).into_response()
         }
         Err(e) => {
-            let error = ErrorResponse::new(format!("Failed to track click: {e}"))
-                .with_code("CLICK_TRACK_ERROR");
+            tracing::error!(link_id = %link_id, "Failed to fetch related links: {e}");
+            let error = ErrorResponse::internal("Failed to fetch related links", &e)
+                .with_code(ErrorCode::LinksFetchError);
             (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
         }
     }
 }
 
-/// Delete a link
-///
-/// Delete a link by its ID. This operation requires authentication and can only be performed by the link's owner.
+const DEFAULT_TRENDING_LIMIT: i64 = 10;
+const MAX_TRENDING_LIMIT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct TrendingQuery {
+    /// Number of links to return, 1 to 50 (default 10)
+    pub limit: Option<i64>,
+}
+
+/// Get trending links
 ///
-/// # OpenAPI Specification
-/// ```yaml
-/// /api/links/{id}:
-///   delete:
-///     summary: Delete a link
-///     description: Delete a link by its ID. Only the owner of the link can delete it.
-///     tags:
-///       - links
-///     security:
-///       - bearerAuth: []
-///     parameters:
-///       - name: id
-///         in: path
-///         required: true
-///         description: Numeric ID of the link to delete
-///         schema:
-///           type: integer
-///           format: int32
-///     responses:
-///       200:
-///         description: Link successfully deleted
-///         content:
-///           application/json:
-///             schema:
-///               type: object
-///               properties:
-///                 success:
-///                   type: boolean
-///                   example: true
-///                 message:
-///                   type: string
-///                   example: Link deleted successfully
-///       401:
-///         description: Unauthorized - Missing or invalid JWT token
-///         content:
-///           application/json:
-///             schema:
-///               type: object
-///               properties:
-///                 error:
-///                   type: string
-///                   example: Missing or invalid authorization header
-///                 code:
-///                   type: string
-///                   example: UNAUTHORIZED
-///       403:
-///         description: Forbidden - User doesn't own the link
-///         content:
-///           application/json:
-///             schema:
-///               type: object
-///               properties:
-///                 error:
-///                   type: string
-///                   example: You don't have permission to delete this link
-///                 code:
-///                   type: string
-///                   example: FORBIDDEN
-///       404:
-///         description: Link not found
-///         content:
-///           application/json:
-///             schema:
-///               type: object
-///               properties:
-///                 error:
-///                   type: string
-///                   example: Link not found
-///                 code:
-///                   type: string
-///                   example: NOT_FOUND
-///       500:
-///         description: Internal server error
-///         content:
-///           application/json:
-///             schema:
-///               type: object
-///               properties:
-///                 error:
-///                   type: string
-///                   example: Failed to delete link
-///                 code:
-///                   type: string
-///                   example: LINK_DELETE_ERROR
-/// ```
-pub async fn delete_link(
+/// Returns the public links with the highest time-decayed click score,
+/// `click_count / (hours_since_created + 2) ^ 1.5`, so recent links that are picking up
+/// clicks rank above old links that simply had years to collect them.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_trending_links(
     State(pool): State<PgPool>,
-    Extension(user): Extension<AuthUser>,
-    Path(link_id): Path<Uuid>,
+    Query(params): Query<TrendingQuery>,
 ) -> impl IntoResponse {
-    // First check if the link exists and belongs to the user
-    match database::queries::get_link_by_id(&pool, link_id).await {
-        Ok(Some(link)) => {
-            if link.user_id != user.id {
-                let error = ErrorResponse::new("You don't have permission to delete this link")
-                    .with_code("FORBIDDEN");
-                return (StatusCode::FORBIDDEN, Json(error)).into_response();
-            }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_TRENDING_LIMIT)
+        .clamp(1, MAX_TRENDING_LIMIT);
 
-            // If the user owns the link, proceed with deletion
-            match database::queries::delete_link(&pool, link_id).await {
-                Ok(_) => {
-                    let response =
-                        ApiResponse::success_with_message((), "Link deleted successfully");
-                    (StatusCode::OK, Json(response)).into_response()
-                }
-                Err(e) => {
-                    let error = ErrorResponse::new(format!("Failed to delete link: {e}"))
-                        .with_code("LINK_DELETE_ERROR");
-                    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
-                }
-            }
-        }
-        Ok(None) => {
-            let error = ErrorResponse::new("Link not found").with_code("NOT_FOUND");
-            (StatusCode::NOT_FOUND, Json(error)).into_response()
+    match database::queries::get_trending_links(&pool, limit).await {
+        Ok(links) => {
+            let response = ApiResponse::success(redact_links(links, None));
+            (StatusCode::OK, Json(response)).into_response()
         }
         Err(e) => {
-            let error = ErrorResponse::new(format!("Failed to fetch link: {e}"))
-                .with_code("LINK_FETCH_ERROR");
+            tracing::error!("Failed to fetch trending links: {e}");
+            let error = ErrorResponse::internal("Failed to fetch trending links", &e)
+                .with_code(ErrorCode::LinksFetchError);
             (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
         }
     }
 }
+
+const DEFAULT_LEADERBOARD_LIMIT: i64 = 10;
+const MAX_LEADERBOARD_LIMIT: i64 = 50;
+
+/// Window a leaderboard counts clicks over, ending now
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardPeriod {
+    #[default]
+    Week,
+    Month,
+}
+
+impl LeaderboardPeriod {
+    fn window(self) -> TimeDelta {
+        match self {
+            LeaderboardPeriod::Week => TimeDelta::days(7),
+            LeaderboardPeriod::Month => TimeDelta::days(30),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    #[serde(default)]
+    pub period: LeaderboardPeriod,
+    /// Number of links to return, 1 to 50 (default 10)
+    pub limit: Option<i64>,
+}
+
+/// Get the current user's most clicked links of the week or month
+///
+/// Ranks the caller's links by the clicks they received in the last 7 days (`week`, the
+/// default) or 30 days (`month`), not by their all-time click count. Bot clicks don't count,
+/// and links without clicks in the period are left out.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_link_leaderboard(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<LeaderboardQuery>,
+) -> impl IntoResponse {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+        .clamp(1, MAX_LEADERBOARD_LIMIT);
+    let since = Utc::now() - params.period.window();
+
+    match database::queries::get_user_leaderboard(&pool, user.id, since, limit).await {
+        Ok(entries) => {
+            let response = ApiResponse::success(entries);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to fetch link leaderboard: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link leaderboard", &e)
+                .with_code(ErrorCode::LinksFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+/// Get several links by ID
+///
+/// Returns the requested links in the order their IDs were given. IDs that don't
+/// exist, or belong to private links of other users, are left out.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_links_batch(
+    State(pool): State<PgPool>,
+    user: Option<Extension<AuthUser>>,
+    ApiJson(payload): ApiJson<BatchLinksRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let viewer_id = user.map(|Extension(user)| user.id);
+
+    match get_links_by_ids(&pool, &payload.ids).await {
+        Ok(links) => {
+            let mut by_id: HashMap<Uuid, Link> = links
+                .into_iter()
+                .filter(|link| link.is_visible_to(viewer_id))
+                .map(|link| (link.id, link))
+                .collect();
+            let ordered: Vec<Link> = payload
+                .ids
+                .iter()
+                .filter_map(|id| by_id.remove(id))
+                .map(|link| link.redacted_for(viewer_id))
+                .collect();
+
+            let response = ApiResponse::success(ordered);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch links: {e}");
+            let error = ErrorResponse::internal("Failed to fetch links", &e)
+                .with_code(ErrorCode::LinksFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QrQuery {
+    /// Width and height of the image in pixels, clamped to 64..=1024
+    pub size: Option<u32>,
+}
+
+/// Get a QR code for a link
+///
+/// Returns a PNG QR code encoding the link's URL. Private links are only available to their owner.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_link_qr(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    user: Option<Extension<AuthUser>>,
+    Path(link_id): Path<Uuid>,
+    Query(params): Query<QrQuery>,
+) -> impl IntoResponse {
+    let viewer_id = user.map(|Extension(user)| user.id);
+
+    let link = match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.is_visible_to(viewer_id) => link,
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    let size = params
+        .size
+        .unwrap_or(DEFAULT_QR_SIZE)
+        .clamp(MIN_QR_SIZE, MAX_QR_SIZE);
+
+    match link_qr_png(link.id, &link.url, size) {
+        Ok(png) => (StatusCode::OK, [(header::CONTENT_TYPE, "image/png")], png).into_response(),
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to render QR code: {e}");
+            let error = ErrorResponse::internal("Failed to render QR code", &e)
+                .with_code(ErrorCode::QrRenderError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// How long browsers and CDNs may cache a proxied preview image
+const PREVIEW_IMAGE_MAX_AGE_SECS: u32 = 24 * 60 * 60;
+
+/// Get a link's preview image
+///
+/// Serves the owner's custom image, or else the preview's image, through the server so
+/// clients never contact the third-party host. Images are cached in memory, must have an image content type and are capped in size.
+/// With `variant`, the preview's image is served scaled down to 320px (`small`) or 1280px
+/// (`large`) wide; images already narrower, and custom images, are served as they are.
+/// Private links are only available to their owner.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn get_link_preview_image(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    user: Option<Extension<AuthUser>>,
+    Path(link_id): Path<Uuid>,
+    Query(params): Query<PreviewImageQuery>,
+) -> impl IntoResponse {
+    let viewer_id = user.map(|Extension(user)| user.id);
+
+    let link = match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.is_visible_to(viewer_id) => link,
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    let Some(image_url) = link.image_url() else {
+        let error =
+            ErrorResponse::new("Link has no preview image").with_code(ErrorCode::NoPreviewImage);
+        return (StatusCode::NOT_FOUND, Json(error)).into_response();
+    };
+
+    // Thumbnails are made from the preview's image, never from a custom one
+    let variant = params.variant.filter(|_| link.custom_image_url.is_none());
+    if let Some(variant) = variant {
+        match get_link_thumbnail(&pool, link_id, variant).await {
+            Ok(Some(thumbnail)) => {
+                let image = ProxiedImage {
+                    content_type: thumbnail.content_type,
+                    bytes: thumbnail.data.into(),
+                };
+                return preview_image_response(link.visibility, image);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!(link_id = %link_id, "Failed to fetch thumbnail: {e}");
+                let error = ErrorResponse::internal("Failed to fetch thumbnail", &e)
+                    .with_code(ErrorCode::LinkFetchError);
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+        }
+    }
+
+    match get_preview_image(image_url).await {
+        Ok(image) => preview_image_response(link.visibility, image),
+        Err(e @ PreviewImageError::BlockedHost(_)) => {
+            let error = ErrorResponse::new(e.to_string()).with_code(ErrorCode::BlockedHost);
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response()
+        }
+        Err(e @ PreviewImageError::NotAnImage(_)) => {
+            let error = ErrorResponse::new(e.to_string()).with_code(ErrorCode::NotAnImage);
+            (StatusCode::BAD_GATEWAY, Json(error)).into_response()
+        }
+        Err(e @ PreviewImageError::TooLarge) => {
+            let error = ErrorResponse::new(e.to_string()).with_code(ErrorCode::ImageTooLarge);
+            (StatusCode::BAD_GATEWAY, Json(error)).into_response()
+        }
+        Err(e) => {
+            let error =
+                ErrorResponse::new(e.to_string()).with_code(ErrorCode::PreviewImageFetchFailed);
+            (StatusCode::BAD_GATEWAY, Json(error)).into_response()
+        }
+    }
+}
+
+/// Responds with a proxied image, cacheable as widely as the link is visible
+fn preview_image_response(visibility: LinkVisibility, image: ProxiedImage) -> Response {
+    let visibility = match visibility {
+        LinkVisibility::Public => "public",
+        LinkVisibility::Private => "private",
+    };
+    let cache_control = format!("{visibility}, max-age={PREVIEW_IMAGE_MAX_AGE_SECS}");
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, image.content_type),
+            (header::CACHE_CONTROL, cache_control),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            // SVGs can carry scripts; never let them run on our origin
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'; sandbox".to_string(),
+            ),
+        ],
+        image.bytes,
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewImageQuery {
+    /// Downscaled copy to serve instead of the full-size image
+    pub variant: Option<ThumbnailVariant>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+    /// Comma-separated fields to search in: title, description or both (default)
+    pub fields: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Search links
+///
+/// Full-text search over link titles and descriptions, ranked by relevance with title
+/// matches first. Words match as prefixes, so "rus" finds "rust".
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn search_links(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let query = params.q.as_deref().map(str::trim).unwrap_or_default();
+    if query.is_empty() {
+        let error = ErrorResponse::new("Search query must not be empty")
+            .with_code(ErrorCode::EmptySearchQuery);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let fields = match params
+        .fields
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+    {
+        None => SearchFields::default(),
+        Some(value) => match value.parse::<SearchFields>() {
+            Ok(fields) => fields,
+            Err(()) => {
+                let error = ErrorResponse::new(format!(
+                    "Invalid fields `{value}`, expected a comma-separated list of title and description"
+                ))
+                .with_code(ErrorCode::InvalidSearchFields);
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+            }
+        },
+    };
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+
+    match database::queries::search_links(&pool, query, fields, Some(user.id), limit).await {
+        Ok(links) => {
+            let response = ApiResponse::success(redact_links(links, Some(user.id)));
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to search links: {e}");
+            let error = ErrorResponse::internal("Failed to search links", &e)
+                .with_code(ErrorCode::LinksSearchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateLinkParams {
+    /// Skip duplicate-URL detection and save the link anyway
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// Check that the URL exists before saving the link
+    #[serde(default)]
+    pub verify: bool,
+}
+
+/// Create a new link
+///
+/// Creates a new link with the provided details. The user ID is automatically extracted from the JWT token.
+/// Requires Authentication: Bearer token from /api/auth/login
+///
+#[utoipa::path(
+    post,
+    path = "/api/links",
+    request_body = CreateLinkRequest,
+    params(
+        ("allow_duplicate" = Option<bool>, Query, description = "Save the link even if the URL was already saved"),
+        ("verify" = Option<bool>, Query, description = "Send a quick HEAD request first and refuse URLs that clearly don't exist (URL_UNREACHABLE); off by default"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Client-generated key; retries with the same key return the original link for 24 hours")
+    ),
+    responses(
+        (status = 200, description = "Link already created with this idempotency key", body = ApiResponse<Link>),
+        (status = 201, description = "Link created successfully", body = ApiResponse<Link>),
+        (status = 400, description = "Malformed Idempotency-Key header", body = ErrorResponse),
+        (status = 403, description = "Active link quota reached; details carry current and limit", body = ErrorResponse),
+        (status = 409, description = "URL already saved by this user, slug already in use, or idempotency key reused with a different request", body = ErrorResponse),
+        (status = 429, description = "Link creation rate limit exceeded", body = ErrorResponse),
+        (status = 422, description = "Invalid request data (URL format, title/description length, publish_at not in the future or not before expires_at), or with verify=true a URL that answered 404/410 or whose host doesn't resolve (URL_UNREACHABLE)", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid JWT token", body = ErrorResponse),
+        (status = 500, description = "Server error", body = ErrorResponse)
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    tag = "links"
+)]
+pub async fn handle_create_link(
+    State(pool): State<PgPool>,
+    State(previews): State<PreviewQueue>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<CreateLinkParams>,
+    headers: HeaderMap,
+    ApiJson(payload): ApiJson<CreateLinkRequest>,
+) -> impl IntoResponse {
+    // Validate the request payload
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    // Validate URL format
+    if let Err(url_error) = payload.validate_url() {
+        let error = ErrorResponse::new(url_error.to_string()).with_code(url_error.code());
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if payload
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        let error = ErrorResponse::new("expires_at must be in the future")
+            .with_code(ErrorCode::InvalidExpiry);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if let Some(publish_at) = payload.publish_at {
+        if publish_at <= Utc::now() {
+            let error = ErrorResponse::new("publish_at must be in the future")
+                .with_code(ErrorCode::InvalidPublishAt);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+        if payload
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= publish_at)
+        {
+            let error = ErrorResponse::new("publish_at must be before expires_at")
+                .with_code(ErrorCode::InvalidPublishAt);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    }
+
+    let url = match normalize_url(&payload.url) {
+        Ok(url) => url,
+        Err(url_error) => {
+            let error = ErrorResponse::new(format!("Invalid URL format: {url_error}"))
+                .with_code(ErrorCode::InvalidUrl);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    };
+
+    if params.verify {
+        if let Some(reason) = find_unreachable(&url).await {
+            let error = ErrorResponse::new(format!(
+                "The URL doesn't seem to exist: {reason}. Check it for typos, or send verify=false to save it anyway"
+            ))
+            .with_code(ErrorCode::UrlUnreachable);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    }
+
+    let idempotency_key = match idempotency_key(&headers) {
+        Ok(key) => key,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(error)).into_response(),
+    };
+
+    // A repeated key replays the original outcome instead of creating another link
+    if let Some(key) = &idempotency_key {
+        let fingerprint = request_fingerprint(&payload, &params);
+        match claim_idempotency_key(&pool, user.id, key, &fingerprint).await {
+            Ok(true) => {}
+            Ok(false) => return replay_idempotent_create(&pool, user.id, key, &fingerprint).await,
+            Err(e) => {
+                tracing::error!(user_id = %user.id, "Failed to store idempotency key: {e}");
+                let error = ErrorResponse::internal("Failed to store idempotency key", &e)
+                    .with_code(ErrorCode::IdempotencyKeyError);
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+        }
+    }
+
+    let link = match insert_new_link(&pool, user.id, url, payload, &params).await {
+        Ok(link) => link,
+        Err((status, error)) => {
+            if let Some(key) = &idempotency_key {
+                if let Err(e) = release_idempotency_key(&pool, user.id, key).await {
+                    tracing::warn!(user_id = %user.id, "Failed to release idempotency key: {e}");
+                }
+            }
+            return (status, Json(error)).into_response();
+        }
+    };
+
+    if let Some(key) = &idempotency_key {
+        if let Err(e) = complete_idempotency_key(&pool, user.id, key, link.id).await {
+            tracing::warn!(link_id = %link.id, "Failed to record idempotency key: {e}");
+        }
+    }
+
+    dispatch_link_event(pool.clone(), WebhookEvent::LinkCreated, &link);
+
+    // Fetch the preview in the background so the response isn't delayed
+    previews.enqueue(&pool, link.id).await;
+
+    // Return the created link immediately
+    let warnings = title_warnings(&pool, user.id, &link.title).await;
+    let response = ApiResponse::success_with_message(link, "Link created successfully")
+        .with_warnings(warnings);
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+/// Reads how many active links a user has and how many they may have
+async fn load_link_quota(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<LinkQuota, (StatusCode, ErrorResponse)> {
+    match get_link_quota(pool, user_id).await {
+        Ok(Some(quota)) => Ok(quota),
+        Ok(None) => {
+            let error = ErrorResponse::new("User not found").with_code(ErrorCode::UserNotFound);
+            Err((StatusCode::NOT_FOUND, error))
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to load link quota: {e}");
+            let error = ErrorResponse::internal("Failed to load link quota", &e)
+                .with_code(ErrorCode::QuotaFetchError);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error))
+        }
+    }
+}
+
+fn quota_exceeded(quota: &LinkQuota) -> ErrorResponse {
+    ErrorResponse::new(format!(
+        "You have reached your limit of {} active links",
+        quota.limit
+    ))
+    .with_code(ErrorCode::QuotaExceeded)
+    .with_details(json!({ "current": quota.current, "limit": quota.limit }))
+}
+
+/// Rejects a new link for users who already have as many active links as their quota allows
+async fn check_link_quota(pool: &PgPool, user_id: Uuid) -> Result<(), (StatusCode, ErrorResponse)> {
+    let quota = load_link_quota(pool, user_id).await?;
+    if quota.current >= i64::from(quota.limit) {
+        return Err((StatusCode::FORBIDDEN, quota_exceeded(&quota)));
+    }
+    Ok(())
+}
+
+/// Rejects a collection that doesn't exist or belongs to someone else
+async fn check_collection(
+    pool: &PgPool,
+    user_id: Uuid,
+    collection_id: Uuid,
+) -> Result<(), (StatusCode, ErrorResponse)> {
+    match get_collection(pool, collection_id, user_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => {
+            let error =
+                ErrorResponse::new("Collection not found").with_code(ErrorCode::InvalidCollection);
+            Err((StatusCode::UNPROCESSABLE_ENTITY, error))
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to fetch collection: {e}");
+            let error = ErrorResponse::internal("Failed to fetch collection", &e)
+                .with_code(ErrorCode::CollectionFetchError);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error))
+        }
+    }
+}
+
+/// Warning returned when the user has another link with the same title
+const DUPLICATE_TITLE_WARNING: &str = "duplicate_title";
+
+/// Warnings for a link that was just saved with `title`
+///
+/// A shared title never blocks the save; it only nudges the user. The count includes the
+/// saved link itself, and failing to count is only logged.
+async fn title_warnings(pool: &PgPool, user_id: Uuid, title: &str) -> Vec<String> {
+    match count_links_with_title(pool, user_id, title).await {
+        Ok(count) if count > 1 => vec![DUPLICATE_TITLE_WARNING.to_string()],
+        Ok(_) => Vec::new(),
+        Err(e) => {
+            tracing::warn!(user_id = %user_id, "Failed to check for duplicate titles: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// Runs the quota and duplicate checks and inserts a validated link
+async fn insert_new_link(
+    pool: &PgPool,
+    user_id: Uuid,
+    url: String,
+    payload: CreateLinkRequest,
+    params: &CreateLinkParams,
+) -> Result<Link, (StatusCode, ErrorResponse)> {
+    check_link_quota(pool, user_id).await?;
+
+    if let Some(collection_id) = payload.collection_id {
+        check_collection(pool, user_id, collection_id).await?;
+    }
+
+    // Reject URLs the user has already saved unless explicitly allowed
+    if !params.allow_duplicate {
+        match find_link_by_url(pool, user_id, &url).await {
+            Ok(Some(existing)) => {
+                let error = ErrorResponse::new("You have already saved this URL")
+                    .with_code(ErrorCode::DuplicateLink)
+                    .with_details(json!({ "existing_link_id": existing.id }));
+                return Err((StatusCode::CONFLICT, error));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!(user_id = %user_id, "Failed to check for duplicate link: {e}");
+                let error = ErrorResponse::internal("Failed to check for duplicate link", &e)
+                    .with_code(ErrorCode::LinkFetchError);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, error));
+            }
+        }
+    }
+
+    // Create the link first without preview
+    let new_link = NewLink {
+        url,
+        original_url: payload.url,
+        title: payload.title,
+        description: payload.description,
+        user_id: Some(user_id),
+        tags: normalize_tags(&payload.tags),
+        visibility: payload.visibility,
+        slug: payload.slug,
+        expires_at: payload.expires_at,
+        claim_token_hash: None,
+        collection_id: payload.collection_id,
+        track_clicks: payload.track_clicks,
+        notes: payload.notes,
+        publish_at: payload.publish_at,
+    };
+
+    create_link(pool, new_link, None).await.map_err(|e| {
+        if is_slug_conflict(&e) {
+            let error =
+                ErrorResponse::new("This slug is already in use").with_code(ErrorCode::SlugTaken);
+            (StatusCode::CONFLICT, error)
+        } else {
+            tracing::error!(user_id = %user_id, "Failed to create link: {e}");
+            let error = ErrorResponse::internal("Failed to create link", &e)
+                .with_code(ErrorCode::LinkCreateError);
+            (StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    })
+}
+
+/// A link created without an account, along with the token needed to claim it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnonymousLink {
+    pub link: Link,
+    /// One-time token for `POST /api/links/{id}/claim`; it is only shown once
+    #[schema(example = "9c1f0e3b6a2d4c8e7f5a1b3d9e0c2f4a6b8d0e2f4a6c8e0b2d4f6a8c0e2b4d6f")]
+    pub claim_token: String,
+}
+
+/// Create a link without an account
+///
+/// Saves a public link that nobody owns yet and returns a one-time claim token. Signing in
+/// and calling `POST /api/links/{id}/claim` with the token makes the link yours; links that
+/// aren't claimed within 24 hours are deleted. Creation is rate limited per client IP.
+pub async fn handle_create_anonymous_link(
+    State(pool): State<PgPool>,
+    State(previews): State<PreviewQueue>,
+    ApiJson(payload): ApiJson<CreateLinkRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if let Err(url_error) = payload.validate_url() {
+        let error = ErrorResponse::new(url_error.to_string()).with_code(url_error.code());
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if payload.expires_at.is_some() {
+        let error = ErrorResponse::new(
+            "Anonymous links expire 24 hours after creation unless claimed; set an expiry after claiming",
+        )
+        .with_code(ErrorCode::InvalidExpiry);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if payload.publish_at.is_some() {
+        let error =
+            ErrorResponse::new("Anonymous links are public right away and can't be scheduled")
+                .with_code(ErrorCode::InvalidPublishAt);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if payload.collection_id.is_some() {
+        let error =
+            ErrorResponse::new("Anonymous links can be filed in a collection after claiming")
+                .with_code(ErrorCode::InvalidCollection);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let url = match normalize_url(&payload.url) {
+        Ok(url) => url,
+        Err(url_error) => {
+            let error = ErrorResponse::new(format!("Invalid URL format: {url_error}"))
+                .with_code(ErrorCode::InvalidUrl);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    };
+
+    let claim_token = hex::encode(rand::random::<[u8; 32]>());
+    // Nobody could see a private link without an owner, so anonymous links are public
+    let new_link = NewLink {
+        url,
+        original_url: payload.url,
+        title: payload.title,
+        description: payload.description,
+        user_id: None,
+        tags: normalize_tags(&payload.tags),
+        visibility: LinkVisibility::Public,
+        slug: payload.slug,
+        expires_at: Some(Utc::now() + UNCLAIMED_LINK_TTL),
+        claim_token_hash: Some(hash_claim_token(&claim_token)),
+        collection_id: None,
+        track_clicks: payload.track_clicks,
+        notes: payload.notes,
+        publish_at: None,
+    };
+
+    let link = match create_link(&pool, new_link, None).await {
+        Ok(link) => link,
+        Err(e) if is_slug_conflict(&e) => {
+            let error =
+                ErrorResponse::new("This slug is already in use").with_code(ErrorCode::SlugTaken);
+            return (StatusCode::CONFLICT, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to create anonymous link: {e}");
+            let error = ErrorResponse::internal("Failed to create link", &e)
+                .with_code(ErrorCode::LinkCreateError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    previews.enqueue(&pool, link.id).await;
+
+    let response = ApiResponse::success_with_message(
+        AnonymousLink { link, claim_token },
+        "Link created successfully; keep the claim token to take ownership of it",
+    );
+    (StatusCode::CREATED, Json(response)).into_response()
+}
+
+/// Claim tokens are stored hashed so a database leak doesn't hand out unclaimed links
+fn hash_claim_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Reads the optional `Idempotency-Key` header
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ErrorResponse> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    match value.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH => {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(ErrorResponse::new(format!(
+            "Idempotency-Key must be 1 to {MAX_IDEMPOTENCY_KEY_LENGTH} visible ASCII characters"
+        ))
+        .with_code(ErrorCode::InvalidIdempotencyKey)),
+    }
+}
+
+/// Fingerprints a create request so a reused idempotency key can be matched to its original body
+fn request_fingerprint(payload: &CreateLinkRequest, params: &CreateLinkParams) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(payload).unwrap_or_default());
+    hasher.update([u8::from(params.allow_duplicate)]);
+    hex::encode(hasher.finalize())
+}
+
+/// Answers a create request whose idempotency key was already used
+async fn replay_idempotent_create(
+    pool: &PgPool,
+    user_id: Uuid,
+    key: &str,
+    fingerprint: &str,
+) -> axum::response::Response {
+    let record = match get_idempotency_key(pool, user_id, key).await {
+        Ok(Some(record)) => record,
+        // The key expired or was released in the meantime
+        Ok(None) => {
+            let error = ErrorResponse::new("Idempotency key is no longer valid, please retry")
+                .with_code(ErrorCode::IdempotencyKeyConflict);
+            return (StatusCode::CONFLICT, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to look up idempotency key: {e}");
+            let error = ErrorResponse::internal("Failed to look up idempotency key", &e)
+                .with_code(ErrorCode::IdempotencyKeyError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    if record.request_hash != fingerprint {
+        let error = ErrorResponse::new("Idempotency key was already used with a different request")
+            .with_code(ErrorCode::IdempotencyKeyReused);
+        return (StatusCode::CONFLICT, Json(error)).into_response();
+    }
+
+    let Some(link_id) = record.link_id else {
+        let error = ErrorResponse::new("A request with this idempotency key is still in progress")
+            .with_code(ErrorCode::IdempotencyKeyInProgress);
+        return (StatusCode::CONFLICT, Json(error)).into_response();
+    };
+
+    match database::queries::get_link_by_id(pool, link_id).await {
+        Ok(Some(link)) => {
+            let response = ApiResponse::success_with_message(link, "Link already created");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error =
+                ErrorResponse::new("The link created with this idempotency key was deleted")
+                    .with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Update a link
+///
+/// Replaces the URL, title, description and expiry of a link; leaving out `expires_at`
+/// makes the link permanent. Only the link's owner can update it. If the URL changes, the
+/// preview is fetched again in the background.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn update_link_handler(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    State(previews): State<PreviewQueue>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+    ApiJson(payload): ApiJson<CreateLinkRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if let Err(url_error) = payload.validate_url() {
+        let error = ErrorResponse::new(url_error.to_string()).with_code(url_error.code());
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if payload
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        let error = ErrorResponse::new("expires_at must be in the future")
+            .with_code(ErrorCode::InvalidExpiry);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let url = match normalize_url(&payload.url) {
+        Ok(url) => url,
+        Err(url_error) => {
+            let error = ErrorResponse::new(format!("Invalid URL format: {url_error}"))
+                .with_code(ErrorCode::InvalidUrl);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    };
+
+    let existing = match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => link,
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    if existing.user_id != Some(user.id) {
+        let error = ErrorResponse::new("You don't have permission to update this link")
+            .with_code(ErrorCode::Forbidden);
+        return (StatusCode::FORBIDDEN, Json(error)).into_response();
+    }
+
+    if let (Some(expires_at), Some(publish_at)) = (payload.expires_at, existing.publish_at) {
+        if expires_at <= publish_at {
+            let error = ErrorResponse::new("expires_at must be after the link's publish_at")
+                .with_code(ErrorCode::InvalidExpiry);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    }
+
+    let update = LinkUpdate {
+        url,
+        original_url: payload.url,
+        title: payload.title,
+        description: payload.description,
+        tags: normalize_tags(&payload.tags),
+        visibility: payload.visibility,
+        slug: payload.slug,
+        track_clicks: payload.track_clicks,
+        notes: payload.notes,
+        expires_at: payload.expires_at,
+    };
+
+    match update_link(&pool, link_id, update, user.id).await {
+        Ok(Some(link)) => {
+            cache.invalidate(link.id).await;
+            dispatch_link_event(pool.clone(), WebhookEvent::LinkUpdated, &link);
+            if link.url != existing.url {
+                previews.enqueue(&pool, link.id).await;
+            }
+            let warnings = title_warnings(&pool, user.id, &link.title).await;
+            let response = ApiResponse::success_with_message(link, "Link updated successfully")
+                .with_warnings(warnings);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) if is_slug_conflict(&e) => {
+            let error =
+                ErrorResponse::new("This slug is already in use").with_code(ErrorCode::SlugTaken);
+            (StatusCode::CONFLICT, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to update link: {e}");
+            let error = ErrorResponse::internal("Failed to update link", &e)
+                .with_code(ErrorCode::LinkUpdateError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Partially update a link
+///
+/// Changes only the fields present in the body; omitted fields keep their current values,
+/// so tags or visibility can be changed on their own. Making a link private hides it from
+/// public listings and feeds right away. Only the link's owner can update it. If the URL
+/// changes, the preview is fetched again.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn patch_link_handler(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    State(previews): State<PreviewQueue>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+    ApiJson(payload): ApiJson<UpdateLinkRequest>,
+) -> impl IntoResponse {
+    if payload.is_empty() {
+        let error = ErrorResponse::new("Provide at least one field to update")
+            .with_code(ErrorCode::EmptyUpdate);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if let Some(Err(url_error)) = payload.validate_url() {
+        let error = ErrorResponse::new(url_error.to_string()).with_code(url_error.code());
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    if let Some(Err(url_error)) = payload.validate_custom_image_url() {
+        let error = ErrorResponse::new(format!("Invalid custom image URL: {url_error}"))
+            .with_code(url_error.code());
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let url = match payload.url.as_deref().map(normalize_url).transpose() {
+        Ok(url) => url,
+        Err(url_error) => {
+            let error = ErrorResponse::new(format!("Invalid URL format: {url_error}"))
+                .with_code(ErrorCode::InvalidUrl);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    };
+
+    let existing = match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => link,
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    if existing.user_id != Some(user.id) {
+        let error = ErrorResponse::new("You don't have permission to update this link")
+            .with_code(ErrorCode::Forbidden);
+        return (StatusCode::FORBIDDEN, Json(error)).into_response();
+    }
+
+    if let Some(Some(collection_id)) = payload.collection_id {
+        if let Err((status, error)) = check_collection(&pool, user.id, collection_id).await {
+            return (status, Json(error)).into_response();
+        }
+    }
+
+    let sets_title = payload.title.is_some();
+    let patch = LinkPatch {
+        url,
+        original_url: payload.url,
+        title: payload.title,
+        description: payload.description,
+        tags: payload.tags.as_deref().map(normalize_tags),
+        visibility: payload.visibility,
+        slug: payload.slug,
+        collection_id: payload.collection_id,
+        custom_image_url: payload.custom_image_url,
+        track_clicks: payload.track_clicks,
+        notes: payload.notes,
+    };
+
+    match patch_link(&pool, link_id, patch, user.id).await {
+        Ok(Some(link)) => {
+            cache.invalidate(link.id).await;
+            dispatch_link_event(pool.clone(), WebhookEvent::LinkUpdated, &link);
+            if link.url != existing.url {
+                previews.enqueue(&pool, link.id).await;
+            }
+            let warnings = if sets_title {
+                title_warnings(&pool, user.id, &link.title).await
+            } else {
+                Vec::new()
+            };
+            let response = ApiResponse::success_with_message(link, "Link updated successfully")
+                .with_warnings(warnings);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) if is_slug_conflict(&e) => {
+            let error =
+                ErrorResponse::new("This slug is already in use").with_code(ErrorCode::SlugTaken);
+            (StatusCode::CONFLICT, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to update link: {e}");
+            let error = ErrorResponse::internal("Failed to update link", &e)
+                .with_code(ErrorCode::LinkUpdateError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Track a link click
+///
+/// Increments the click count for a link and records the click event
+/// (referrer, user agent and a salted hash of the client IP) for analytics.
+/// Clicks from crawlers and link-preview bots are recorded but not counted, and each
+/// visitor counts once per link per minute; the response reports either with `counted: false`.
+/// Links with click tracking turned off are neither counted nor recorded, and also report
+/// `counted: false`.
+pub async fn track_click(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    State(clicks): State<ClickTracking>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match count_click(&pool, &cache, &clicks, link_id, &headers, addr).await {
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::LinkNotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Ok(Some(click)) => {
+            let response = ApiResponse::success(click);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to track click: {e}");
+            let error = ErrorResponse::internal("Failed to track click", &e)
+                .with_code(ErrorCode::ClickTrackError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Transparent 1x1 GIF returned by the tracking pixel
+const TRACKING_PIXEL_GIF: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+/// Track a link click with an image
+///
+/// Counts a click like `POST /api/links/{id}/click` and responds with a transparent 1x1 GIF,
+/// so clicks can be tracked from emails and static pages that can only load images.
+/// The image is never cached, so every view reaches the server.
+pub async fn track_click_pixel(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    State(clicks): State<ClickTracking>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match count_click(&pool, &cache, &clicks, link_id, &headers, addr).await {
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::LinkNotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Ok(Some(_)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "image/gif"),
+                (
+                    header::CACHE_CONTROL,
+                    "no-store, no-cache, must-revalidate, max-age=0",
+                ),
+                (header::PRAGMA, "no-cache"),
+                (header::EXPIRES, "0"),
+            ],
+            TRACKING_PIXEL_GIF,
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to track click: {e}");
+            let error = ErrorResponse::internal("Failed to track click", &e)
+                .with_code(ErrorCode::ClickTrackError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Outcome of tracking a click
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackedClick {
+    /// The link's click count after this click
+    #[schema(example = 42)]
+    pub click_count: i64,
+    /// False when the click wasn't counted: the link has click tracking turned off, the click
+    /// came from a crawler or link-preview bot, or the same visitor already clicked the link
+    /// within the last minute
+    #[schema(example = true)]
+    pub counted: bool,
+}
+
+/// Counts a click on a link and records the click event for analytics
+///
+/// Every way of clicking a link goes through here, so they are all counted the same way.
+/// Nothing is counted or recorded for links with click tracking turned off.
+/// Clicks from bot user agents are recorded as bot events but leave the click count alone.
+/// Each visitor counts once per link per limiter window; their repeat clicks are
+/// neither counted nor recorded. Counted clicks go into the click buffer and are
+/// written with their events in the next flush, so the returned count, counted or not,
+/// includes clicks still waiting there. Returns None if the link doesn't exist. Failing to record a bot
+/// event is only logged.
+async fn count_click(
+    pool: &PgPool,
+    cache: &LinkCache,
+    clicks: &ClickTracking,
+    link_id: Uuid,
+    headers: &HeaderMap,
+    addr: SocketAddr,
+) -> Result<Option<TrackedClick>, sqlx::Error> {
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    let is_bot = is_bot_user_agent(user_agent);
+    let ip_hash = hash_ip(&client_ip(headers, &addr));
+    let referrer = headers
+        .get(header::REFERER)
+        .and_then(|value| value.to_str().ok());
+
+    let Some(link) = cache.get_link_by_id(pool, link_id).await? else {
+        return Ok(None);
+    };
+    let click_count = i64::from(link.click_count);
+    // Clicks still in the buffer count even when this one doesn't
+    let uncounted = TrackedClick {
+        click_count: click_count + clicks.buffer.waiting(link_id),
+        counted: false,
+    };
+    if !link.track_clicks {
+        return Ok(Some(uncounted));
+    }
+
+    if !is_bot {
+        if !clicks.limiter.check(&ip_hash, link_id).await {
+            // A repeat click; recording it would let one visitor flood the click log
+            return Ok(Some(uncounted));
+        }
+        let waiting = clicks.buffer.push(BufferedClick {
+            link_id,
+            referrer: referrer.map(str::to_string),
+            user_agent: user_agent.map(str::to_string),
+            ip_hash,
+            clicked_at: Utc::now(),
+        });
+        return Ok(Some(TrackedClick {
+            click_count: click_count + waiting,
+            counted: true,
+        }));
+    }
+
+    if let Err(e) = record_click(pool, link_id, referrer, user_agent, &ip_hash, is_bot).await {
+        tracing::warn!(link_id = %link_id, "Failed to record click event: {e}");
+    }
+
+    Ok(Some(uncounted))
+}
+
+/// Follow a short link
+///
+/// Redirects to the target URL of the link with the given slug and counts the visit as a click.
+/// Private links only redirect for their owner.
+/// Optional Authentication: Bearer token from /api/auth/login
+pub async fn redirect_slug(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    State(clicks): State<ClickTracking>,
+    user: Option<Extension<AuthUser>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+) -> impl IntoResponse {
+    let viewer_id = user.map(|Extension(user)| user.id);
+
+    let link = match get_link_by_slug(&pool, &slug).await {
+        Ok(Some(link)) if link.is_visible_to(viewer_id) => link,
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(slug = %slug, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    // Counting the click must never block the redirect
+    if let Err(e) = count_click(&pool, &cache, &clicks, link.id, &headers, addr).await {
+        tracing::warn!(link_id = %link.id, "Failed to count short link click: {e}");
+    }
+
+    (StatusCode::FOUND, [(header::LOCATION, link.url)]).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClickStatsQuery {
+    #[serde(default)]
+    pub bucket: ClickBucket,
+    /// Only count clicks at or after this RFC3339 timestamp
+    pub from: Option<String>,
+    /// Only count clicks at or before this RFC3339 timestamp
+    pub to: Option<String>,
+}
+
+/// Get click statistics for a link
+///
+/// Returns total clicks and unique visitors, plus both grouped by hour, day (default), week
+/// or month, optionally within a time window. Only the link's owner can view them.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_link_stats(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+    Query(params): Query<ClickStatsQuery>,
+) -> impl IntoResponse {
+    let from = match parse_timestamp_param("from", params.from.as_deref()) {
+        Ok(ts) => ts,
+        Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+    };
+    let to = match parse_timestamp_param("to", params.to.as_deref()) {
+        Ok(ts) => ts,
+        Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+    };
+
+    match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.user_id != Some(user.id) => {
+            let error = ErrorResponse::new("You don't have permission to view these statistics")
+                .with_code(ErrorCode::Forbidden);
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    let result = tokio::try_join!(
+        get_click_count(&pool, link_id, from, to),
+        get_unique_click_count(&pool, link_id, from, to),
+        get_click_stats(&pool, link_id, params.bucket, from, to)
+    );
+
+    match result {
+        Ok((total_clicks, unique_visitors, buckets)) => {
+            let stats = LinkStats {
+                total_clicks,
+                unique_visitors,
+                buckets,
+            };
+            let response: LinkStatsResponse = ApiResponse::success(stats);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch click statistics: {e}");
+            let error = ErrorResponse::internal("Failed to fetch click statistics", &e)
+                .with_code(ErrorCode::ClickStatsError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+const DEFAULT_CLICKS_PAGE_SIZE: i64 = 50;
+const MAX_CLICKS_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct ClicksQuery {
+    /// Only return clicks at or after this RFC3339 timestamp
+    pub from: Option<String>,
+    /// Only return clicks at or before this RFC3339 timestamp
+    pub to: Option<String>,
+    /// Page size, 1 to 500 (default 50)
+    pub limit: Option<i64>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<Uuid>,
+}
+
+/// A page of click events
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClickEventsPage {
+    pub clicks: Vec<ClickEvent>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    pub next_cursor: Option<Uuid>,
+}
+
+/// Get raw click events for a link
+///
+/// Returns individual clicks, newest first, with cursor pagination and an optional
+/// time window. Only the link's owner can view them.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_link_clicks(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+    Query(params): Query<ClicksQuery>,
+) -> impl IntoResponse {
+    let from = match parse_timestamp_param("from", params.from.as_deref()) {
+        Ok(ts) => ts,
+        Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+    };
+    let to = match parse_timestamp_param("to", params.to.as_deref()) {
+        Ok(ts) => ts,
+        Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+    };
+
+    match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.user_id != Some(user.id) => {
+            let error = ErrorResponse::new("You don't have permission to view these clicks")
+                .with_code(ErrorCode::Forbidden);
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_CLICKS_PAGE_SIZE)
+        .clamp(1, MAX_CLICKS_PAGE_SIZE);
+    // Fetch one extra row to know whether another page follows
+    let filters = ClickFilters {
+        from,
+        to,
+        cursor: params.cursor,
+        limit: limit + 1,
+    };
+
+    match get_clicks_for_link(&pool, link_id, &filters).await {
+        Ok(mut clicks) => {
+            let next_cursor = if clicks.len() as i64 > limit {
+                clicks.truncate(limit as usize);
+                clicks.last().map(|click| click.id)
+            } else {
+                None
+            };
+            let response = ApiResponse::success(ClickEventsPage {
+                clicks,
+                next_cursor,
+            });
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch clicks: {e}");
+            let error = ErrorResponse::internal("Failed to fetch clicks", &e)
+                .with_code(ErrorCode::ClicksFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Refresh a link's preview
+///
+/// Re-fetches the preview metadata for a link's URL and stores it. Only the link's owner can refresh it.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn refresh_link_preview(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let link = match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => link,
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    if link.user_id != Some(user.id) {
+        let error = ErrorResponse::new("You don't have permission to refresh this link")
+            .with_code(ErrorCode::Forbidden);
+        return (StatusCode::FORBIDDEN, Json(error)).into_response();
+    }
+
+    // Only a stored preview can be kept when the page turns out to be unchanged
+    let validators = if link.preview_status == PreviewStatus::Ready {
+        match get_preview_validators(&pool, link_id).await {
+            Ok(validators) => validators,
+            Err(e) => {
+                tracing::error!(link_id = %link_id, "Failed to fetch preview validators: {e}");
+                let error = ErrorResponse::internal("Failed to fetch link", &e)
+                    .with_code(ErrorCode::LinkFetchError);
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+        }
+    } else {
+        PreviewValidators::default()
+    };
+
+    let fetch = match fetch_link_preview(&link.url, &validators).await {
+        Ok(fetch) => fetch,
+        Err(e @ LinkPreviewError::BlockedHost(_)) => {
+            let error = ErrorResponse::new(format!("Failed to fetch link preview: {e}"))
+                .with_code(ErrorCode::BlockedHost);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+        Err(e @ LinkPreviewError::Disallowed(_)) => {
+            let error = ErrorResponse::new(format!("Failed to fetch link preview: {e}"))
+                .with_code(ErrorCode::PreviewDisallowed);
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+        Err(e @ LinkPreviewError::TooManyRedirects(_)) => {
+            let error = ErrorResponse::new(format!("Failed to fetch link preview: {e}"))
+                .with_code(ErrorCode::TooManyRedirects);
+            return (StatusCode::BAD_GATEWAY, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::warn!(link_id = %link_id, "Failed to fetch link preview: {e}");
+            let error = ErrorResponse::new(format!("Failed to fetch link preview: {e}"))
+                .with_code(ErrorCode::PreviewFetchFailed);
+            return (StatusCode::BAD_GATEWAY, Json(error)).into_response();
+        }
+    };
+
+    let (result, message) = match fetch {
+        PreviewFetch::Fetched {
+            mut preview,
+            validators,
+        } => {
+            let thumbnails = add_thumbnails(link_id, &mut preview).await;
+            (
+                update_link_preview(&pool, link_id, Some(&*preview), &validators, &thumbnails)
+                    .await,
+                "Link preview refreshed",
+            )
+        }
+        PreviewFetch::NotModified => (
+            mark_preview_unchanged(&pool, link_id).await,
+            "Link preview is already up to date",
+        ),
+    };
+
+    match result {
+        Ok(Some(link)) => {
+            cache.invalidate(link.id).await;
+            let response = ApiResponse::success_with_message(link, message);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to update link preview: {e}");
+            let error = ErrorResponse::internal("Failed to update link preview", &e)
+                .with_code(ErrorCode::LinkUpdateError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Result of checking whether a link's target is still reachable
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkStatus {
+    /// The URL that was checked
+    #[schema(example = "https://www.rust-lang.org")]
+    pub url: String,
+    /// HTTP status the target answered with; absent when the request failed
+    #[schema(example = 200)]
+    pub status_code: Option<u16>,
+    /// Why the request failed, if it did
+    #[schema(example = "error sending request: dns error: failed to lookup address information")]
+    pub error: Option<String>,
+    /// The link's health after this check
+    pub health: LinkHealth,
+    /// When the check ran
+    #[schema(example = "2024-03-12T04:00:00Z")]
+    pub last_checked_at: DateTime<Utc>,
+}
+
+/// Check whether a link is still alive
+///
+/// Sends a HEAD request to the link's URL right away and reports the status it answered
+/// with. The result also counts towards the link's `health`, which only turns `dead` after
+/// several failed checks in a row. Only the link's owner can check it.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_link_status(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let link = match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => link,
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    if link.user_id != Some(user.id) {
+        let error = ErrorResponse::new("You don't have permission to check this link")
+            .with_code(ErrorCode::Forbidden);
+        return (StatusCode::FORBIDDEN, Json(error)).into_response();
+    }
+
+    let check = check_url(&link.url).await;
+    match record_check(&pool, &cache, link_id, &check).await {
+        Ok(Some((health, last_checked_at))) => {
+            let status = LinkStatus {
+                url: link.url,
+                status_code: check.status,
+                error: check.error,
+                health,
+                last_checked_at,
+            };
+            (StatusCode::OK, Json(ApiResponse::success(status))).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to record link health: {e}");
+            let error = ErrorResponse::internal("Failed to record link health", &e)
+                .with_code(ErrorCode::LinkUpdateError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Token for undoing a deletion right after it happened
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletedLink {
+    /// Pass to `POST /api/links/restore` to bring the link back
+    #[schema(example = "eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9...")]
+    pub undo_token: String,
+    /// When the undo token stops working, 30 seconds after the deletion
+    #[schema(example = "2024-03-10T15:00:30Z")]
+    pub undo_expires_at: DateTime<Utc>,
+}
+
+/// Delete a link
+///
+/// Delete a link by its ID. This operation requires authentication and can only be performed by the link's owner.
+/// The response carries an undo token that restores the link through `POST /api/links/restore`
+/// within 30 seconds, for an "Undo" action in the UI.
+///
+/// # OpenAPI Specification
+/// ```yaml
+/// /api/links/{id}:
+///   delete:
+///     summary: Delete a link
+///     description: Delete a link by its ID. Only the owner of the link can delete it.
+///     tags:
+///       - links
+///     security:
+///       - bearerAuth: []
+///     parameters:
+///       - name: id
+///         in: path
+///         required: true
+///         description: Numeric ID of the link to delete
+///         schema:
+///           type: integer
+///           format: int32
+///     responses:
+///       200:
+///         description: Link successfully deleted
+///         content:
+///           application/json:
+///             schema:
+///               type: object
+///               properties:
+///                 success:
+///                   type: boolean
+///                   example: true
+///                 message:
+///                   type: string
+///                   example: Link deleted successfully
+///       401:
+///         description: Unauthorized - Missing or invalid JWT token
+///         content:
+///           application/json:
+///             schema:
+///               type: object
+///               properties:
+///                 error:
+///                   type: string
+///                   example: Missing or invalid authorization header
+///                 code:
+///                   type: string
+///                   example: UNAUTHORIZED
+///       403:
+///         description: Forbidden - User doesn't own the link
+///         content:
+///           application/json:
+///             schema:
+///               type: object
+///               properties:
+///                 error:
+///                   type: string
+///                   example: You don't have permission to delete this link
+///                 code:
+///                   type: string
+///                   example: FORBIDDEN
+///       404:
+///         description: Link not found
+///         content:
+///           application/json:
+///             schema:
+///               type: object
+///               properties:
+///                 error:
+///                   type: string
+///                   example: Link not found
+///                 code:
+///                   type: string
+///                   example: NOT_FOUND
+///       500:
+///         description: Internal server error
+///         content:
+///           application/json:
+///             schema:
+///               type: object
+///               properties:
+///                 error:
+///                   type: string
+///                   example: Failed to delete link
+///                 code:
+///                   type: string
+///                   example: LINK_DELETE_ERROR
+/// ```
+pub async fn delete_link(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    // First check if the link exists and belongs to the user
+    match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => {
+            if link.user_id != Some(user.id) {
+                let error = ErrorResponse::new("You don't have permission to delete this link")
+                    .with_code(ErrorCode::Forbidden);
+                return (StatusCode::FORBIDDEN, Json(error)).into_response();
+            }
+
+            // If the user owns the link, proceed with deletion
+            match database::queries::delete_link(&pool, link_id, user.id).await {
+                Ok(_) => {
+                    cache.invalidate(link_id).await;
+                    dispatch_link_event(pool.clone(), WebhookEvent::LinkDeleted, &link);
+                    let (undo_token, undo_expires_at) = create_undo_token(link_id, user.id);
+                    let response = ApiResponse::success_with_message(
+                        DeletedLink {
+                            undo_token,
+                            undo_expires_at,
+                        },
+                        "Link deleted successfully",
+                    );
+                    (StatusCode::OK, Json(response)).into_response()
+                }
+                Err(e) => {
+                    tracing::error!(link_id = %link_id, "Failed to delete link: {e}");
+                    let error = ErrorResponse::internal("Failed to delete link", &e)
+                        .with_code(ErrorCode::LinkDeleteError);
+                    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+                }
+            }
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+const CONFIRM_DELETE_HEADER: &str = "x-confirm-delete";
+
+/// Delete all of the caller's links
+///
+/// Soft-deletes every link the user owns; each can still be restored during the grace period.
+/// Requires the `X-Confirm-Delete: true` header to guard against accidental calls.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn delete_all_links(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let confirmed = headers
+        .get(CONFIRM_DELETE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("true"));
+    if !confirmed {
+        let error = ErrorResponse::new("Send `X-Confirm-Delete: true` to delete all of your links")
+            .with_code(ErrorCode::ConfirmationRequired);
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+
+    match database::queries::delete_all_links_for_user(&pool, user.id).await {
+        Ok(deleted) => {
+            cache.invalidate_all();
+            let response = ApiResponse::success_with_message(
+                json!({ "deleted": deleted }),
+                "All links deleted successfully",
+            );
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to delete links: {e}");
+            let error = ErrorResponse::internal("Failed to delete links", &e)
+                .with_code(ErrorCode::LinkDeleteError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Restore a deleted link
+///
+/// Undoes a soft-delete while the link is still within the restore grace period.
+/// Only the link's owner can restore it.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn restore_link(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match database::queries::get_deleted_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => {
+            if link.user_id != Some(user.id) {
+                let error = ErrorResponse::new("You don't have permission to restore this link")
+                    .with_code(ErrorCode::Forbidden);
+                return (StatusCode::FORBIDDEN, Json(error)).into_response();
+            }
+
+            match database::queries::restore_link(&pool, link_id).await {
+                Ok(Some(link)) => {
+                    let response =
+                        ApiResponse::success_with_message(link, "Link restored successfully");
+                    (StatusCode::OK, Json(response)).into_response()
+                }
+                Ok(None) => {
+                    let error = ErrorResponse::new("Link not found or can no longer be restored")
+                        .with_code(ErrorCode::NotFound);
+                    (StatusCode::NOT_FOUND, Json(error)).into_response()
+                }
+                Err(e) => {
+                    tracing::error!(link_id = %link_id, "Failed to restore link: {e}");
+                    let error = ErrorResponse::internal("Failed to restore link", &e)
+                        .with_code(ErrorCode::LinkRestoreError);
+                    (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+                }
+            }
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found or can no longer be restored")
+                .with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Undo a link deletion
+///
+/// Restores a link with the `undo_token` its deletion returned. The token proves the caller
+/// deleted the link, so no bearer token is needed. It expires 30 seconds after the deletion;
+/// later on the owner can still restore the link by ID.
+pub async fn undo_delete_link(
+    State(pool): State<PgPool>,
+    ApiJson(payload): ApiJson<UndoDeleteRequest>,
+) -> impl IntoResponse {
+    let claims = match verify_undo_token(payload.token.trim()) {
+        Ok(claims) => claims,
+        Err(e) if matches!(e.kind(), jsonwebtoken::errors::ErrorKind::ExpiredSignature) => {
+            let error = ErrorResponse::new("The undo token has expired")
+                .with_code(ErrorCode::UndoTokenExpired);
+            return (StatusCode::GONE, Json(error)).into_response();
+        }
+        Err(_) => {
+            let error =
+                ErrorResponse::new("Invalid undo token").with_code(ErrorCode::InvalidUndoToken);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+    let link_id = claims.sub;
+
+    // The link may have been restored and handed to someone else since the token was issued
+    match database::queries::get_deleted_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.user_id == Some(claims.owner) => {}
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found or can no longer be restored")
+                .with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    match database::queries::restore_link(&pool, link_id).await {
+        Ok(Some(link)) => {
+            let response = ApiResponse::success_with_message(link, "Link restored successfully");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found or can no longer be restored")
+                .with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to restore link: {e}");
+            let error = ErrorResponse::internal("Failed to restore link", &e)
+                .with_code(ErrorCode::LinkRestoreError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Transfer a link to another user
+///
+/// Hands ownership of the link over to the user identified by `new_owner_id`.
+/// Only the link's current owner can transfer it, and the link counts against the new
+/// owner's quota. The link leaves its collection and loses its notes, which were private
+/// to the previous owner.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn transfer_link(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+    ApiJson(payload): ApiJson<TransferLinkRequest>,
+) -> impl IntoResponse {
+    match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) => {
+            if link.user_id != Some(user.id) {
+                let error = ErrorResponse::new("You don't have permission to transfer this link")
+                    .with_code(ErrorCode::Forbidden);
+                return (StatusCode::FORBIDDEN, Json(error)).into_response();
+            }
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    match database::queries::user_exists_by_id(&pool, payload.new_owner_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let error = ErrorResponse::new("Target user not found")
+                .with_code(ErrorCode::TargetUserNotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to look up target user: {e}");
+            let error = ErrorResponse::internal("Failed to look up target user", &e)
+                .with_code(ErrorCode::UserFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    // Handing a link back to its owner doesn't add to their links
+    if payload.new_owner_id != user.id {
+        if let Err((status, error)) = check_link_quota(&pool, payload.new_owner_id).await {
+            return (status, Json(error)).into_response();
+        }
+    }
+
+    match database::queries::transfer_link(&pool, link_id, user.id, payload.new_owner_id).await {
+        Ok(Some(link)) => {
+            cache.invalidate(link.id).await;
+            let response = ApiResponse::success_with_message(link, "Link transferred successfully");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to transfer link: {e}");
+            let error = ErrorResponse::internal("Failed to transfer link", &e)
+                .with_code(ErrorCode::LinkTransferError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Suffix marking the title of a duplicated link
+const COPY_TITLE_SUFFIX: &str = " (copy)";
+
+/// Duplicate a link
+///
+/// Creates a new link owned by the caller with the source's URL, title, description and
+/// tags, a fresh ID and slug, no clicks, and " (copy)" appended to the title. The stored
+/// preview is copied too, so it isn't fetched again. Owners can duplicate any of their
+/// links, keeping its visibility, collection and notes; other users can duplicate public
+/// links, and their copy starts out private, outside any collection and without notes. Expiry is never copied.
+/// Counts against the caller's link quota.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn duplicate_link(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    State(previews): State<PreviewQueue>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let source = match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.is_visible_to(Some(user.id)) => link,
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    if let Err((status, error)) = check_link_quota(&pool, user.id).await {
+        return (status, Json(error)).into_response();
+    }
+
+    // Shorten the title rather than fail when the suffix doesn't fit
+    let max_title = MAX_TITLE_LENGTH - COPY_TITLE_SUFFIX.chars().count();
+    let mut title: String = source.title.chars().take(max_title).collect();
+    title.push_str(COPY_TITLE_SUFFIX);
+
+    let is_owner = source.user_id == Some(user.id);
+    let new_link = NewLink {
+        url: source.url,
+        original_url: source.original_url,
+        title,
+        description: source.description,
+        user_id: Some(user.id),
+        tags: source.tags,
+        visibility: if is_owner {
+            source.visibility
+        } else {
+            LinkVisibility::Private
+        },
+        slug: None,
+        expires_at: None,
+        claim_token_hash: None,
+        collection_id: source.collection_id.filter(|_| is_owner),
+        // Turning tracking off is the owner's choice; a copy someone else makes starts with it on
+        track_clicks: source.track_clicks || !is_owner,
+        notes: source.notes.filter(|_| is_owner),
+        publish_at: source.publish_at.filter(|_| is_owner),
+    };
+
+    // Only a finished preview is worth copying; otherwise the copy fetches its own. The
+    // thumbnails are stored for the source link, so the copy shows its image unscaled.
+    let preview = source
+        .preview
+        .clone()
+        .filter(|_| source.preview_status == PreviewStatus::Ready)
+        .map(|preview| LinkPreview {
+            thumbnail_small: None,
+            thumbnail_large: None,
+            ..preview
+        });
+
+    match create_link(&pool, new_link, preview.as_ref()).await {
+        Ok(link) => {
+            dispatch_link_event(pool.clone(), WebhookEvent::LinkCreated, &link);
+            if preview.is_none() {
+                previews.enqueue(&pool, link.id).await;
+            }
+            let response = ApiResponse::success_with_message(link, "Link duplicated successfully");
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to duplicate link: {e}");
+            let error = ErrorResponse::internal("Failed to duplicate link", &e)
+                .with_code(ErrorCode::LinkCreateError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Claim an anonymous link
+///
+/// Makes the authenticated user the owner of a link created without an account, using the
+/// claim token returned at creation. The token works once, and the link no longer expires.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn claim_link(
+    State(pool): State<PgPool>,
+    State(cache): State<LinkCache>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+    ApiJson(payload): ApiJson<ClaimLinkRequest>,
+) -> impl IntoResponse {
+    match cache.get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.user_id.is_some() => {
+            let error = ErrorResponse::new("This link already has an owner")
+                .with_code(ErrorCode::AlreadyClaimed);
+            return (StatusCode::CONFLICT, Json(error)).into_response();
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    if let Err((status, error)) = check_link_quota(&pool, user.id).await {
+        return (status, Json(error)).into_response();
+    }
+
+    let token_hash = hash_claim_token(payload.claim_token.trim());
+    match database::queries::claim_link(&pool, link_id, &token_hash, user.id).await {
+        Ok(Some(link)) => {
+            cache.invalidate(link.id).await;
+            let response = ApiResponse::success_with_message(link, "Link claimed successfully");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Ok(None) => {
+            let error =
+                ErrorResponse::new("Invalid claim token").with_code(ErrorCode::InvalidClaimToken);
+            (StatusCode::FORBIDDEN, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to claim link: {e}");
+            let error = ErrorResponse::internal("Failed to claim link", &e)
+                .with_code(ErrorCode::LinkClaimError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Favorite a link
+///
+/// Stars a link for the current user. Any link the user can see can be favorited,
+/// and favoriting it again has no effect.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn add_favorite(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match database::queries::get_link_by_id(&pool, link_id).await {
+        Ok(Some(link)) if link.is_visible_to(Some(user.id)) => {}
+        Ok(_) => {
+            let error = ErrorResponse::new("Link not found").with_code(ErrorCode::NotFound);
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to fetch link: {e}");
+            let error = ErrorResponse::internal("Failed to fetch link", &e)
+                .with_code(ErrorCode::LinkFetchError);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    }
+
+    match database::queries::add_favorite(&pool, user.id, link_id).await {
+        Ok(()) => {
+            let response = ApiResponse::success_with_message((), "Link added to favorites");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to favorite link: {e}");
+            let error = ErrorResponse::internal("Failed to favorite link", &e)
+                .with_code(ErrorCode::FavoriteError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Unfavorite a link
+///
+/// Removes a link from the current user's favorites. Succeeds even if the link wasn't favorited.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn remove_favorite(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Path(link_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match database::queries::remove_favorite(&pool, user.id, link_id).await {
+        Ok(()) => {
+            let response = ApiResponse::success_with_message((), "Link removed from favorites");
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(link_id = %link_id, "Failed to unfavorite link: {e}");
+            let error = ErrorResponse::internal("Failed to unfavorite link", &e)
+                .with_code(ErrorCode::FavoriteError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Get favorite links
+///
+/// Returns the links the current user has favorited, most recently favorited first.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn get_favorites(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    match database::queries::get_favorited_links(&pool, user.id).await {
+        Ok(links) => {
+            let response = ApiResponse::success(redact_links(links, Some(user.id)));
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to fetch favorites: {e}");
+            let error = ErrorResponse::internal("Failed to fetch favorites", &e)
+                .with_code(ErrorCode::FavoritesFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Columns of a CSV export, in order
+const CSV_EXPORT_COLUMNS: [&str; 6] = [
+    "url",
+    "title",
+    "description",
+    "tags",
+    "click_count",
+    "created_at",
+];
+
+/// Formats a link export can be downloaded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Picks the format from the `Accept` header, preferring JSON when both are acceptable
+    ///
+    /// A missing header, or one accepting anything, yields JSON. Returns None when the
+    /// client accepts neither format.
+    fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.trim().is_empty())
+        else {
+            return Some(ExportFormat::Json);
+        };
+
+        let mut best: Option<(f32, Self)> = None;
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let media_type = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let format = match media_type.as_str() {
+                "application/json" | "application/*" | "*/*" => ExportFormat::Json,
+                "text/csv" | "text/*" => ExportFormat::Csv,
+                _ => continue,
+            };
+            if quality > 0.0 && best.is_none_or(|(best_quality, _)| quality > best_quality) {
+                best = Some((quality, format));
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn content_disposition(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "attachment; filename=\"links-export.json\"",
+            ExportFormat::Csv => "attachment; filename=\"links-export.csv\"",
+        }
+    }
+}
+
+/// Encodes one link as a CSV row; tags are joined with commas inside a single field
+fn link_csv_row(link: &Link) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        link.url.as_str(),
+        link.title.as_str(),
+        link.description.as_str(),
+        link.tags.join(",").as_str(),
+        link.click_count.to_string().as_str(),
+        link.created_at.to_rfc3339().as_str(),
+    ])?;
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
+
+const DEFAULT_SYNC_PAGE_SIZE: i64 = 100;
+const MAX_SYNC_PAGE_SIZE: i64 = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// Only return links changed after this RFC3339 timestamp; every link when absent
+    pub since: Option<String>,
+    /// `next_cursor` from the previous page; takes the place of `since`
+    pub cursor: Option<String>,
+    /// Page size, 1 to 500 (default 100)
+    pub limit: Option<i64>,
+}
+
+/// Encodes where a sync page ended as `<updated_at in µs>_<link id>`
+fn sync_cursor(link: &Link) -> String {
+    format!("{}_{}", link.updated_at.timestamp_micros(), link.id)
+}
+
+fn parse_sync_cursor(cursor: &str) -> Option<(DateTime<Utc>, Uuid)> {
+    let (micros, id) = cursor.split_once('_')?;
+    let changed_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?;
+    Some((changed_at, id.parse().ok()?))
+}
+
+/// Sync the current user's links
+///
+/// Returns the caller's links that changed after `since`, oldest change first, so offline
+/// clients can catch up incrementally. Deleted links are included as tombstones with
+/// `deleted: true`. When more changes are waiting, `meta.next_cursor` fetches the next page;
+/// once it is absent, the newest `updated_at` seen is the `since` for the next sync.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn sync_links(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<SyncQuery>,
+) -> impl IntoResponse {
+    let since = match parse_timestamp_param("since", params.since.as_deref()) {
+        Ok(since) => since,
+        Err(error) => return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response(),
+    };
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SYNC_PAGE_SIZE)
+        .clamp(1, MAX_SYNC_PAGE_SIZE);
+
+    // Links changed exactly at `since` were already synced, so start past all of them
+    let (since, after_id) = match params.cursor.as_deref().map(str::trim) {
+        None | Some("") => (since.unwrap_or(DateTime::UNIX_EPOCH), Uuid::max()),
+        Some(cursor) => match parse_sync_cursor(cursor) {
+            Some(position) => position,
+            None => {
+                let error = ErrorResponse::new(
+                    "Invalid `cursor`, pass `next_cursor` from the previous page",
+                )
+                .with_code(ErrorCode::InvalidCursor);
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+            }
+        },
+    };
+
+    // One extra link tells whether there is another page
+    match get_links_changed_since(&pool, user.id, since, after_id, limit + 1).await {
+        Ok(mut links) => {
+            let has_more = links.len() as i64 > limit;
+            links.truncate(limit as usize);
+            let next_cursor = links
+                .last()
+                .filter(|_| has_more)
+                .map(|synced| sync_cursor(&synced.link));
+            let response = ApiResponse::success(links).with_next_cursor(next_cursor);
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to fetch changed links: {e}");
+            let error = ErrorResponse::internal("Failed to fetch changed links", &e)
+                .with_code(ErrorCode::LinksFetchError);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Export the current user's links
+///
+/// Streams every non-deleted link owned by the caller as a download. `Accept: text/csv`
+/// yields a CSV with the columns url, title, description, tags, click_count and
+/// created_at; otherwise the links are sent as a JSON array, including tags and preview.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn export_links(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let Some(format) = ExportFormat::negotiate(&headers) else {
+        let error =
+            ErrorResponse::new("Links can only be exported as application/json or text/csv")
+                .with_code(ErrorCode::NotAcceptable);
+        return (StatusCode::NOT_ACCEPTABLE, Json(error)).into_response();
+    };
+
+    let (tx, rx) = mpsc::channel::<Result<Vec<u8>, sqlx::Error>>(EXPORT_CHANNEL_CAPACITY);
+
+    // Links are serialized one at a time as rows arrive, so the export is never fully buffered
+    tokio::spawn(async move {
+        let mut links = get_links_by_user(&pool, user.id);
+        let mut chunk = match format {
+            ExportFormat::Json => b"[".to_vec(),
+            ExportFormat::Csv => format!("{}\r\n", CSV_EXPORT_COLUMNS.join(",")).into_bytes(),
+        };
+        let mut first = true;
+
+        while let Some(result) = links.next().await {
+            let link = match result {
+                Ok(link) => link,
+                Err(e) => {
+                    tracing::error!(user_id = %user.id, "Failed to export links: {e}");
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+
+            let encoded = match format {
+                ExportFormat::Json => {
+                    let mut row = if first { Vec::new() } else { b",".to_vec() };
+                    serde_json::to_writer(&mut row, &link)
+                        .map(|()| row)
+                        .map_err(anyhow::Error::from)
+                }
+                ExportFormat::Csv => link_csv_row(&link).map_err(anyhow::Error::from),
+            };
+            match encoded {
+                Ok(row) => chunk.extend(row),
+                Err(e) => {
+                    tracing::error!(link_id = %link.id, "Failed to serialize link for export: {e}");
+                    continue;
+                }
+            }
+            first = false;
+            if tx.send(Ok(std::mem::take(&mut chunk))).await.is_err() {
+                // The client went away
+                return;
+            }
+        }
+
+        if format == ExportFormat::Json {
+            chunk.push(b']');
+        }
+        let _ = tx.send(Ok(chunk)).await;
+    });
+
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, format.content_type()),
+            (header::CONTENT_DISPOSITION, format.content_disposition()),
+            (header::VARY, "accept"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Outcome of a bookmarks import
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportSummary {
+    /// Number of links created
+    pub imported: usize,
+    /// Number of entries skipped because of an invalid URL or because the link was already saved
+    pub skipped: usize,
+}
+
+/// Tags from an imported file, normalized and trimmed to what a new link may carry
+fn import_tags(tags: &[String]) -> Vec<String> {
+    normalize_tags(tags)
+        .into_iter()
+        .filter(|tag| tag.chars().count() <= MAX_TAG_LENGTH)
+        .take(MAX_TAGS)
+        .collect()
+}
+
+/// Import links from a bookmarks file
+///
+/// Accepts a multipart upload of a Netscape bookmarks HTML file, as exported by
+/// browsers, and saves every bookmark as a private link. A JSON file downloaded from
+/// `GET /api/links/export` is accepted too, keeping each link's description, tags and
+/// visibility. Invalid URLs and URLs the user already saved are skipped.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn import_links(
+    State(pool): State<PgPool>,
+    State(previews): State<PreviewQueue>,
+    Extension(user): Extension<AuthUser>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let html = match multipart.next_field().await {
+        Ok(Some(field)) => match field.bytes().await {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                let error = ErrorResponse::new(format!(
+                    "Bookmarks file must be at most {} MiB",
+                    IMPORT_BODY_LIMIT / (1024 * 1024)
+                ))
+                .with_code(ErrorCode::PayloadTooLarge);
+                return (StatusCode::PAYLOAD_TOO_LARGE, Json(error)).into_response();
+            }
+            Err(e) => {
+                let error = ErrorResponse::new(format!("Failed to read uploaded file: {e}"))
+                    .with_code(ErrorCode::InvalidUpload);
+                return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+            }
+        },
+        Ok(None) => {
+            let error = ErrorResponse::new("No bookmarks file was uploaded")
+                .with_code(ErrorCode::InvalidUpload);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+        Err(e) => {
+            let error = ErrorResponse::new(format!("Invalid multipart upload: {e}"))
+                .with_code(ErrorCode::InvalidUpload);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    let mut quota = match load_link_quota(&pool, user.id).await {
+        Ok(quota) => quota,
+        Err((status, error)) => return (status, Json(error)).into_response(),
+    };
+
+    let mut summary = ImportSummary {
+        imported: 0,
+        skipped: 0,
+    };
+
+    let bookmarks = match parse_bookmarks_file(&html) {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => {
+            let error = ErrorResponse::new(format!("Invalid links export: {e}"))
+                .with_code(ErrorCode::InvalidUpload);
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    for bookmark in bookmarks {
+        if parse_link_url(&bookmark.url).is_err() {
+            summary.skipped += 1;
+            continue;
+        }
+        let url = match normalize_url(&bookmark.url) {
+            Ok(url) => url,
+            Err(_) => {
+                summary.skipped += 1;
+                continue;
+            }
+        };
+
+        match find_link_by_url(&pool, user.id, &url).await {
+            Ok(Some(_)) => {
+                summary.skipped += 1;
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::error!(user_id = %user.id, "Failed to check for duplicate link: {e}");
+                let error = ErrorResponse::internal("Failed to check for duplicate link", &e)
+                    .with_code(ErrorCode::LinkFetchError)
+                    .with_details(json!(summary));
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+        }
+
+        // Bookmarks imported so far stay; the rest of the file is left out
+        if quota.current >= i64::from(quota.limit) {
+            let error = quota_exceeded(&quota).with_details(json!({
+                "current": quota.current,
+                "limit": quota.limit,
+                "imported": summary.imported,
+                "skipped": summary.skipped,
+            }));
+            return (StatusCode::FORBIDDEN, Json(error)).into_response();
+        }
+
+        let new_link = NewLink {
+            url,
+            original_url: bookmark.url,
+            title: bookmark.title.chars().take(MAX_TITLE_LENGTH).collect(),
+            description: bookmark.description,
+            user_id: Some(user.id),
+            tags: import_tags(&bookmark.tags),
+            visibility: bookmark.visibility.unwrap_or_default(),
+            slug: None,
+            expires_at: None,
+            claim_token_hash: None,
+            collection_id: None,
+            track_clicks: true,
+            notes: None,
+            publish_at: None,
+        };
+
+        match create_link(&pool, new_link, None).await {
+            Ok(link) => {
+                summary.imported += 1;
+                quota.current += 1;
+                dispatch_link_event(pool.clone(), WebhookEvent::LinkCreated, &link);
+                previews.enqueue(&pool, link.id).await;
+            }
+            Err(e) => {
+                tracing::error!(user_id = %user.id, "Failed to create link: {e}");
+                let error = ErrorResponse::internal("Failed to create link", &e)
+                    .with_code(ErrorCode::LinkCreateError)
+                    .with_details(json!(summary));
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+            }
+        }
+    }
+
+    let message = format!(
+        "Imported {} links, skipped {}",
+        summary.imported, summary.skipped
+    );
+    let response = ApiResponse::success_with_message(summary, message);
+    (StatusCode::OK, Json(response)).into_response()
+}
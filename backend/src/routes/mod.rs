@@ -1,24 +1,206 @@
+pub mod admin;
+pub mod collections;
+pub mod dashboard;
 pub mod health;
 pub mod links;
+pub mod users;
+pub mod webhooks;
 
-use crate::database::PgPool;
+use crate::database::{LinkCache, PgPool};
+use crate::middleware::{
+    auth::{auth, optional_auth, require_role},
+    body_limit::{body_limit, DEFAULT_BODY_LIMIT, IMPORT_BODY_LIMIT},
+    rate_limit::{rate_limit, rate_limit_by_ip, ClickLimiter, RateLimiter},
+};
+use crate::models::auth::UserRole;
+use crate::services::{auth::AuthService, click_buffer::ClickBuffer, preview_jobs::PreviewQueue};
 use axum::{
-    routing::{delete, get, post},
+    extract::FromRef,
+    middleware::from_fn_with_state,
+    routing::{delete, get, patch, post, put},
     Router,
 };
 
+/// What counting a click needs besides the database
+#[derive(Clone)]
+pub struct ClickTracking {
+    /// Lets each visitor count once per link per window
+    pub limiter: ClickLimiter,
+    /// Holds counted clicks until they are written in a batch
+    pub buffer: ClickBuffer,
+}
+
+/// State shared by the link routes
+///
+/// Handlers extract each part directly with `State<PgPool>`, `State<LinkCache>`,
+/// `State<PreviewQueue>` or `State<ClickTracking>`.
+#[derive(Clone)]
+pub struct LinkState {
+    pub pool: PgPool,
+    pub cache: LinkCache,
+    pub previews: PreviewQueue,
+    pub clicks: ClickTracking,
+}
+
+impl LinkState {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cache: LinkCache::new(),
+            previews: PreviewQueue::new(),
+            clicks: ClickTracking {
+                limiter: ClickLimiter::from_env(),
+                buffer: ClickBuffer::from_env(),
+            },
+        }
+    }
+}
+
+impl FromRef<LinkState> for PgPool {
+    fn from_ref(state: &LinkState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<LinkState> for LinkCache {
+    fn from_ref(state: &LinkState) -> Self {
+        state.cache.clone()
+    }
+}
+
+impl FromRef<LinkState> for PreviewQueue {
+    fn from_ref(state: &LinkState) -> Self {
+        state.previews.clone()
+    }
+}
+
+impl FromRef<LinkState> for ClickTracking {
+    fn from_ref(state: &LinkState) -> Self {
+        state.clicks.clone()
+    }
+}
+
+/// The health, link, user and admin routes, each group behind the authentication it needs
+///
+/// The server adds the auth routes, which need email delivery configured, along with
+/// Swagger UI, metrics and the outer middleware stack.
+pub fn create_api_router(pool: PgPool, auth_service: AuthService, link_state: LinkState) -> Router {
+    Router::new()
+        .route("/health", get(health::root))
+        .merge(create_ping_router(pool))
+        .merge(
+            create_public_router(link_state.clone())
+                .layer(from_fn_with_state(auth_service.clone(), optional_auth)),
+        )
+        .merge(create_protected_router(link_state).layer(from_fn_with_state(auth_service, auth)))
+}
+
 pub fn create_ping_router(pool: PgPool) -> Router {
     Router::new()
         .route("/api/admin/db/health", get(health::health_check))
+        .route("/ready", get(health::ready))
         .with_state(pool)
 }
 
-// Protected routes that require authentication
-pub fn create_protected_router(pool: PgPool) -> Router {
+// Routes that work with or without authentication
+pub fn create_public_router(state: LinkState) -> Router {
+    let anonymous_link_limiter = RateLimiter::anonymous_from_env();
+
     Router::new()
         .route("/api/links", get(links::get_links))
-        .route("/api/links", post(links::handle_create_link))
+        .route(
+            "/api/links/anonymous",
+            post(links::handle_create_anonymous_link)
+                .layer(from_fn_with_state(anonymous_link_limiter, rate_limit_by_ip)),
+        )
+        .route("/api/links/batch", post(links::get_links_batch))
+        .route("/api/links/restore", post(links::undo_delete_link))
+        .route("/api/links/trending", get(links::get_trending_links))
+        .route("/api/links/{id}", get(links::get_link_by_id_handler))
+        .route("/api/links/{id}/qr", get(links::get_link_qr))
+        .route("/api/links/{id}/pixel.gif", get(links::track_click_pixel))
+        .route("/api/links/{id}/related", get(links::get_related_links))
+        .route(
+            "/api/links/{id}/preview-image",
+            get(links::get_link_preview_image),
+        )
+        .route("/api/tags", get(links::get_tags))
+        .route("/s/{slug}", get(links::redirect_slug))
+        .route(
+            "/api/users/{username}",
+            get(users::get_user_profile_handler),
+        )
+        .route("/api/users/{username}/feed.xml", get(users::user_feed))
+        .layer(body_limit(DEFAULT_BODY_LIMIT))
+        .with_state(state)
+}
+
+// Protected routes that require authentication
+pub fn create_protected_router(state: LinkState) -> Router {
+    let create_link_limiter = RateLimiter::from_env();
+
+    Router::new()
+        .route(
+            "/api/links",
+            post(links::handle_create_link)
+                .layer(from_fn_with_state(create_link_limiter, rate_limit))
+                .delete(links::delete_all_links),
+        )
+        .route("/api/links/search", get(links::search_links))
+        .route("/api/links/leaderboard", get(links::get_link_leaderboard))
+        .route("/api/links/export", get(links::export_links))
+        .route("/api/links/sync", get(links::sync_links))
+        .route("/api/dashboard", get(dashboard::get_dashboard))
+        .route("/api/links/{id}", put(links::update_link_handler))
+        .route("/api/links/{id}", patch(links::patch_link_handler))
         .route("/api/links/{id}", delete(links::delete_link))
         .route("/api/links/{id}/click", post(links::track_click))
-        .with_state(pool)
+        .route("/api/links/{id}/stats", get(links::get_link_stats))
+        .route("/api/links/{id}/clicks", get(links::get_link_clicks))
+        .route(
+            "/api/links/{id}/refresh-preview",
+            post(links::refresh_link_preview),
+        )
+        .route("/api/links/{id}/status", get(links::get_link_status))
+        .route("/api/links/{id}/restore", post(links::restore_link))
+        .route("/api/links/{id}/transfer", post(links::transfer_link))
+        .route("/api/links/{id}/duplicate", post(links::duplicate_link))
+        .route("/api/links/{id}/claim", post(links::claim_link))
+        .route(
+            "/api/links/{id}/favorite",
+            post(links::add_favorite).delete(links::remove_favorite),
+        )
+        .route("/api/favorites", get(links::get_favorites))
+        .route(
+            "/api/collections",
+            post(collections::create_collection_handler).get(collections::list_collections),
+        )
+        .route(
+            "/api/collections/{id}",
+            delete(collections::delete_collection_handler),
+        )
+        .route(
+            "/api/collections/{id}/links",
+            get(collections::get_collection_links),
+        )
+        .route("/api/webhooks", post(webhooks::create_webhook_handler))
+        .merge(create_admin_router())
+        .layer(body_limit(DEFAULT_BODY_LIMIT))
+        // Added after the default limit so bookmark files only get the larger one
+        .route(
+            "/api/links/import",
+            post(links::import_links).layer(body_limit(IMPORT_BODY_LIMIT)),
+        )
+        .with_state(state)
+}
+
+// Admin-only routes, nested in the protected router so `auth` has already run
+fn create_admin_router() -> Router<LinkState> {
+    Router::new()
+        .route("/api/admin/users", get(admin::list_users))
+        .route("/api/admin/users/{id}", delete(admin::delete_user_handler))
+        .route("/api/admin/users/{id}/quota", put(admin::update_link_quota))
+        .route("/api/admin/pool-stats", get(admin::pool_stats))
+        .route("/api/admin/audit", get(admin::list_audit_log))
+        .route_layer(from_fn_with_state(UserRole::Admin, require_role))
 }
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::env;
+
+use crate::{
+    api::{ApiResponse, ErrorResponse},
+    database::{
+        queries::{get_public_links_by_user, get_user_id_by_username, get_user_profile},
+        PgPool,
+    },
+    services::feed::render_rss,
+};
+
+/// Number of links included in a user's feed
+const FEED_ITEM_LIMIT: i64 = 50;
+
+/// Get a user's public profile
+///
+/// Returns the user's username, join date, and how many active public links they have
+/// along with the clicks those links received. Email and account status are never included.
+pub async fn get_user_profile_handler(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+) -> impl IntoResponse {
+    match get_user_profile(&pool, &username).await {
+        Ok(Some(profile)) => (StatusCode::OK, Json(ApiResponse::success(profile))).into_response(),
+        Ok(None) => {
+            let error = ErrorResponse::new("User not found").with_code("USER_NOT_FOUND");
+            (StatusCode::NOT_FOUND, Json(error)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(username = %username, "Failed to fetch user: {e}");
+            let error =
+                ErrorResponse::internal("Failed to fetch user", &e).with_code("USER_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
+
+/// Get a user's RSS feed
+///
+/// Renders the user's most recent public links as an RSS 2.0 feed.
+/// Private links and scheduled links that aren't published yet never appear in the feed.
+pub async fn user_feed(
+    State(pool): State<PgPool>,
+    Path(username): Path<String>,
+) -> impl IntoResponse {
+    let user_id = match get_user_id_by_username(&pool, &username).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => {
+            let error = ErrorResponse::new("User not found").with_code("USER_NOT_FOUND");
+            return (StatusCode::NOT_FOUND, Json(error)).into_response();
+        }
+        Err(e) => {
+            tracing::error!(username = %username, "Failed to fetch user: {e}");
+            let error =
+                ErrorResponse::internal("Failed to fetch user", &e).with_code("USER_FETCH_ERROR");
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response();
+        }
+    };
+
+    match get_public_links_by_user(&pool, user_id, FEED_ITEM_LIMIT).await {
+        Ok(links) => {
+            let site_url = env::var("FRONTEND_REQUEST_URL").unwrap_or_default();
+            let feed = render_rss(&username, &site_url, &links);
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+                feed,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user_id, "Failed to fetch feed links: {e}");
+            let error =
+                ErrorResponse::internal("Failed to fetch links", &e).with_code("LINKS_FETCH_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use url::Url;
+use validator::Validate;
+
+use crate::{
+    api::{
+        extract::ApiJson,
+        models::{CreateWebhookRequest, ValidationErrorResponse},
+        ApiResponse, ErrorResponse,
+    },
+    database::{queries::create_webhook, PgPool},
+    middleware::auth::AuthUser,
+    services::{
+        link_preview::check_host,
+        webhooks::{generate_secret, WebhookEvent},
+    },
+};
+
+/// Register a webhook
+///
+/// Subscribes an endpoint to events for the authenticated user's links. Each delivery
+/// is a JSON POST signed with HMAC-SHA256 of the body in the `X-Signature` header,
+/// keyed by the `secret` returned in this response.
+/// Requires Authentication: Bearer token from /api/auth/login
+pub async fn create_webhook_handler(
+    State(pool): State<PgPool>,
+    Extension(user): Extension<AuthUser>,
+    ApiJson(payload): ApiJson<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    if let Err(validation_errors) = payload.validate() {
+        let error = ValidationErrorResponse::from(&validation_errors);
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let url = match Url::parse(&payload.url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => {
+            let error = ErrorResponse::new("Webhook URL must use http or https protocol")
+                .with_code("INVALID_URL");
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+    };
+
+    if check_host(&url).is_err() {
+        let error = ErrorResponse::new("Webhook URL must not point to a private or loopback host")
+            .with_code("BLOCKED_HOST");
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+    }
+
+    let mut events: Vec<String> = Vec::with_capacity(payload.events.len());
+    for event in payload.events {
+        if !WebhookEvent::is_known(&event) {
+            let error = ErrorResponse::new(format!("Unknown webhook event: {event}"))
+                .with_code("INVALID_EVENT");
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response();
+        }
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+
+    match create_webhook(&pool, user.id, url.as_str(), &generate_secret(), &events).await {
+        Ok(webhook) => {
+            let response =
+                ApiResponse::success_with_message(webhook, "Webhook registered successfully");
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(user_id = %user.id, "Failed to register webhook: {e}");
+            let error = ErrorResponse::internal("Failed to register webhook", &e)
+                .with_code("WEBHOOK_CREATE_ERROR");
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error)).into_response()
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use axum::http::HeaderMap;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    sync::OnceLock,
+};
+
+/// User-Agent substrings treated as bots when `BOT_USER_AGENTS` is not set
+const DEFAULT_BOT_USER_AGENTS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "facebookexternalhit",
+    "slackbot",
+    "twitterbot",
+    "linkedinbot",
+    "discordbot",
+    "telegrambot",
+    "whatsapp",
+    "skypeuripreview",
+    "embedly",
+    "preview",
+];
+
+static BOT_USER_AGENTS: OnceLock<Vec<String>> = OnceLock::new();
+
+static TRUSTED_PROXIES: OnceLock<Vec<IpNetwork>> = OnceLock::new();
+
+/// An address or CIDR range such as `10.0.0.0/8` or `fd00::/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IpNetwork {
+    addr: IpAddr,
+    prefix: u32,
+}
+
+impl IpNetwork {
+    fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (value.parse::<IpAddr>().ok()?, None),
+        };
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max_prefix);
+        (prefix <= max_prefix).then_some(Self { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Proxies whose forwarding headers are believed, as a comma-separated `TRUSTED_PROXIES`
+/// list of addresses and CIDR ranges. Empty unless set, so headers are ignored by default.
+fn trusted_proxies() -> &'static [IpNetwork] {
+    TRUSTED_PROXIES.get_or_init(|| {
+        env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .filter_map(|value| {
+                let network = IpNetwork::parse(value);
+                if network.is_none() {
+                    tracing::warn!("Ignoring invalid TRUSTED_PROXIES entry {value:?}");
+                }
+                network
+            })
+            .collect()
+    })
+}
+
+/// Resolves the client IP from the socket address, or from proxy headers when the
+/// connection comes from a trusted proxy
+///
+/// Headers from anyone else are ignored, since clients can put whatever they like in
+/// them. Behind trusted proxies, `X-Forwarded-For` is read from the right and the first
+/// hop that isn't a trusted proxy is the client; `X-Real-IP` is used when there is no
+/// `X-Forwarded-For`.
+pub fn client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
+    resolve_client_ip(headers, addr.ip(), trusted_proxies()).to_string()
+}
+
+fn resolve_client_ip(headers: &HeaderMap, peer: IpAddr, trusted: &[IpNetwork]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|network| network.contains(ip));
+    if !is_trusted(peer) {
+        return peer;
+    }
+
+    let header_value = |name: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let forwarded_for = header_value("X-Forwarded-For");
+    let hops = if forwarded_for.trim().is_empty() {
+        header_value("X-Real-IP")
+    } else {
+        forwarded_for
+    };
+
+    let mut client = peer;
+    for hop in hops.rsplit(',') {
+        let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !is_trusted(ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// Hashes a client IP with a server-side salt so raw addresses are never stored
+///
+/// The salt comes from `IP_HASH_SALT`, falling back to `JWT_SECRET`. Unless
+/// `IP_HASH_ROTATE_DAILY` is `false`, the current UTC date is mixed into the salt, so a
+/// visitor's clicks can't be linked across days and returning visitors count as unique
+/// once per day.
+pub fn hash_ip(ip: &str) -> String {
+    let salt = env::var("IP_HASH_SALT")
+        .or_else(|_| env::var("JWT_SECRET"))
+        .unwrap_or_default();
+    let rotate_daily = env::var("IP_HASH_ROTATE_DAILY")
+        .map(|value| !value.trim().eq_ignore_ascii_case("false"))
+        .unwrap_or(true);
+
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    if rotate_daily {
+        hasher.update(Utc::now().date_naive().to_string().as_bytes());
+    }
+    hasher.update(ip.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Bot User-Agent substrings, configurable as a comma-separated `BOT_USER_AGENTS`
+fn bot_user_agents() -> &'static [String] {
+    BOT_USER_AGENTS.get_or_init(|| match env::var("BOT_USER_AGENTS") {
+        Ok(value) => value
+            .split(',')
+            .map(|agent| agent.trim().to_lowercase())
+            .filter(|agent| !agent.is_empty())
+            .collect(),
+        Err(_) => DEFAULT_BOT_USER_AGENTS
+            .iter()
+            .map(|agent| agent.to_string())
+            .collect(),
+    })
+}
+
+/// Whether a User-Agent belongs to a crawler, link-preview fetcher or prefetcher
+///
+/// Matching is a case-insensitive substring check, so `bot` also covers `Googlebot`.
+/// Requests without a User-Agent are counted as people.
+pub fn is_bot_user_agent(user_agent: Option<&str>) -> bool {
+    let Some(user_agent) = user_agent else {
+        return false;
+    };
+    let user_agent = user_agent.to_lowercase();
+    bot_user_agents()
+        .iter()
+        .any(|agent| user_agent.contains(agent.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const PEER: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(10, 0, 0, 2));
+
+    fn forwarded_for(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    fn networks(values: &[&str]) -> Vec<IpNetwork> {
+        values
+            .iter()
+            .map(|value| IpNetwork::parse(value).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peer() {
+        let headers = forwarded_for("1.2.3.4");
+        assert_eq!(resolve_client_ip(&headers, PEER, &[]), PEER);
+    }
+
+    #[test]
+    fn takes_rightmost_untrusted_hop_behind_trusted_proxy() {
+        let trusted = networks(&["10.0.0.0/8"]);
+        // The client spoofed 6.6.6.6; the proxy appended the address it saw
+        let headers = forwarded_for("6.6.6.6, 198.51.100.7, 10.1.2.3");
+        assert_eq!(
+            resolve_client_ip(&headers, PEER, &trusted),
+            "198.51.100.7".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn falls_back_to_peer_without_headers() {
+        let trusted = networks(&["10.0.0.2"]);
+        assert_eq!(resolve_client_ip(&HeaderMap::new(), PEER, &trusted), PEER);
+    }
+
+    #[test]
+    fn uses_real_ip_from_trusted_proxy() {
+        let trusted = networks(&["10.0.0.0/8"]);
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Real-IP", HeaderValue::from_static("203.0.113.9"));
+        assert_eq!(
+            resolve_client_ip(&headers, PEER, &trusted),
+            "203.0.113.9".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn parses_networks() {
+        let network = IpNetwork::parse("192.168.0.0/16").unwrap();
+        assert!(network.contains("192.168.4.5".parse().unwrap()));
+        assert!(!network.contains("192.169.0.1".parse().unwrap()));
+        assert!(IpNetwork::parse("::1")
+            .unwrap()
+            .contains("::1".parse().unwrap()));
+        assert!(IpNetwork::parse("10.0.0.0/33").is_none());
+        assert!(IpNetwork::parse("not-an-ip").is_none());
+    }
+
+    #[test]
+    fn recognizes_crawler_and_preview_user_agents() {
+        for user_agent in [
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)",
+            "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)",
+            "Mozilla/5.0 (compatible; Discordbot/2.0; +https://discordapp.com)",
+            "WhatsApp/2.23.20.0",
+            "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+        ] {
+            assert!(is_bot_user_agent(Some(user_agent)), "{user_agent}");
+        }
+    }
+
+    #[test]
+    fn counts_browsers_and_missing_user_agents_as_people() {
+        for user_agent in [
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36",
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+            "Mozilla/5.0 (X11; Linux x86_64; rv:127.0) Gecko/20100101 Firefox/127.0",
+        ] {
+            assert!(!is_bot_user_agent(Some(user_agent)), "{user_agent}");
+        }
+        assert!(!is_bot_user_agent(None));
+    }
+}
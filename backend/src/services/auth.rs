@@ -1,28 +1,118 @@
 use bcrypt::{hash, verify, DEFAULT_COST};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
+use jsonwebtoken::{encode, EncodingKey, Header, Validation};
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
+use std::env;
+use thiserror::Error;
+use uuid::Uuid;
 
 use crate::{
-    database::queries,
-    models::auth::{AuthResponse, Claims, RegisterRequest, User, UserStatus},
+    database::{
+        queries,
+        refresh_tokens::{self, Rotation},
+    },
+    models::auth::{normalize_email, AuthResponse, Claims, RegisterRequest, User, UserStatus},
 };
 
+/// How long a refresh token works, unless `REFRESH_TOKEN_TTL_DAYS` says otherwise
+const DEFAULT_REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+
+/// Issuer of access tokens, unless `JWT_ISSUER` says otherwise
+const DEFAULT_JWT_ISSUER: &str = "linksphere";
+
+/// Why a login was refused
+#[derive(Debug, Error)]
+pub enum LoginError {
+    /// No account has the email, or the password is wrong; which one isn't revealed
+    #[error("Invalid email or password")]
+    InvalidCredentials,
+    #[error("Email not verified")]
+    NotVerified,
+    #[error("Account is not active")]
+    Inactive,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Why a refresh token couldn't be exchanged
+#[derive(Debug, Error)]
+pub enum RefreshError {
+    #[error("Refresh token is invalid or has been revoked")]
+    Invalid,
+    #[error("Refresh token has expired, log in again")]
+    Expired,
+    #[error("Refresh token was already used; every session from that login has been signed out")]
+    Reused,
+    #[error("Account is not active")]
+    Inactive,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+fn refresh_token_ttl() -> Duration {
+    let days = env::var("REFRESH_TOKEN_TTL_DAYS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|&days| days > 0)
+        .unwrap_or(DEFAULT_REFRESH_TOKEN_TTL_DAYS);
+    Duration::days(days)
+}
+
+/// Issuer written into access tokens and required of every token presented
+pub fn jwt_issuer() -> String {
+    env::var("JWT_ISSUER")
+        .ok()
+        .map(|issuer| issuer.trim().to_string())
+        .filter(|issuer| !issuer.is_empty())
+        .unwrap_or_else(|| DEFAULT_JWT_ISSUER.to_string())
+}
+
+/// Checks an access token must pass besides its signature
+///
+/// `exp`, `nbf` and `iss` are all required, so tokens missing one are rejected rather than
+/// let through unchecked. The library's default leeway of a minute absorbs clock skew.
+pub fn access_token_validation(issuer: &str) -> Validation {
+    let mut validation = Validation::default();
+    validation.set_required_spec_claims(&["exp", "nbf", "iss"]);
+    validation.validate_nbf = true;
+    validation.set_issuer(&[issuer]);
+    validation
+}
+
+fn generate_refresh_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Refresh tokens are stored hashed so a database leak doesn't hand out sessions
+fn hash_refresh_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
 #[derive(Clone)]
 pub struct AuthService {
     pool: PgPool,
     jwt_secret: String,
+    jwt_issuer: String,
 }
 
 impl AuthService {
     pub fn new(pool: PgPool, jwt_secret: String) -> Self {
-        Self { pool, jwt_secret }
+        Self {
+            pool,
+            jwt_secret,
+            jwt_issuer: jwt_issuer(),
+        }
     }
 
     pub fn get_jwt_secret(&self) -> &str {
         &self.jwt_secret
     }
 
+    pub fn get_jwt_issuer(&self) -> &str {
+        &self.jwt_issuer
+    }
+
     pub fn get_pool(&self) -> &PgPool {
         &self.pool
     }
@@ -51,13 +141,14 @@ impl AuthService {
                 id, email, username, password_hash, 
                 gender as "gender: _", 
                 status as "status: _",
+                role as "role: _",
                 is_verified,
                 verification_attempts,
                 verified_at,
                 created_at, 
                 updated_at
             "#,
-            req.email,
+            normalize_email(&req.email),
             req.username,
             password_hash,
             req.gender as _,
@@ -67,7 +158,7 @@ impl AuthService {
         .await
     }
 
-    pub async fn login(&self, email: &str, password: &str) -> Result<AuthResponse, sqlx::Error> {
+    pub async fn login(&self, email: &str, password: &str) -> Result<AuthResponse, LoginError> {
         let user = sqlx::query_as!(
             User,
             r#"
@@ -75,37 +166,116 @@ impl AuthService {
                 id, email, username, password_hash, 
                 gender as "gender: _",
                 status as "status: _",
+                role as "role: _",
                 is_verified,
                 verification_attempts,
                 verified_at,
                 created_at, 
                 updated_at
             FROM users
-            WHERE email = $1
+            WHERE LOWER(email) = LOWER($1)
             "#,
             email
         )
-        .fetch_one(&self.pool)
-        .await?;
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(LoginError::InvalidCredentials)?;
 
         if !verify(password.as_bytes(), &user.password_hash)
             .map_err(|e| sqlx::Error::Protocol(format!("Failed to verify password: {e}")))?
         {
-            return Err(sqlx::Error::Protocol("Invalid password".to_string()));
+            return Err(LoginError::InvalidCredentials);
         }
 
         // Check if user is verified
         if !user.is_verified {
-            return Err(sqlx::Error::Protocol("Email not verified".to_string()));
+            return Err(LoginError::NotVerified);
         }
 
         // Check if user is active
         if user.status != UserStatus::Active {
-            return Err(sqlx::Error::Protocol("Account is not active".to_string()));
+            return Err(LoginError::Inactive);
         }
 
         let token = self.create_token(&user)?;
-        Ok(AuthResponse { token, user })
+        let refresh_token = self.issue_refresh_token(user.id).await?;
+        Ok(AuthResponse {
+            token,
+            refresh_token,
+            user,
+        })
+    }
+
+    /// Exchanges a refresh token for a new access token and a replacement refresh token
+    ///
+    /// Each refresh token works once. Presenting one that was already exchanged means
+    /// someone kept a copy, so every refresh token descending from the same login is
+    /// revoked and the user has to log in again.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<AuthResponse, RefreshError> {
+        let new_refresh_token = generate_refresh_token();
+        let rotation = refresh_tokens::rotate_refresh_token(
+            &self.pool,
+            &hash_refresh_token(refresh_token),
+            &hash_refresh_token(&new_refresh_token),
+            Utc::now() + refresh_token_ttl(),
+        )
+        .await?;
+
+        let user_id = match rotation {
+            Rotation::Rotated { user_id } => user_id,
+            Rotation::Reused { user_id } => {
+                tracing::warn!(user_id = %user_id, "Refresh token reused, revoked its family");
+                return Err(RefreshError::Reused);
+            }
+            Rotation::Expired => return Err(RefreshError::Expired),
+            Rotation::Invalid => return Err(RefreshError::Invalid),
+        };
+
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT 
+                id, email, username, password_hash, 
+                gender as "gender: _",
+                status as "status: _",
+                role as "role: _",
+                is_verified,
+                verification_attempts,
+                verified_at,
+                created_at, 
+                updated_at
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !user.is_verified || user.status != UserStatus::Active {
+            return Err(RefreshError::Inactive);
+        }
+
+        let token = self.create_token(&user)?;
+        Ok(AuthResponse {
+            token,
+            refresh_token: new_refresh_token,
+            user,
+        })
+    }
+
+    /// Issues the first refresh token of a new login
+    async fn issue_refresh_token(&self, user_id: Uuid) -> Result<String, sqlx::Error> {
+        let refresh_token = generate_refresh_token();
+        refresh_tokens::create_refresh_token(
+            &self.pool,
+            user_id,
+            Uuid::new_v4(),
+            &hash_refresh_token(&refresh_token),
+            Utc::now() + refresh_token_ttl(),
+        )
+        .await?;
+        Ok(refresh_token)
     }
 
     pub async fn complete_verification(&self, email: &str) -> Result<(), sqlx::Error> {
@@ -113,7 +283,8 @@ impl AuthService {
     }
 
     fn create_token(&self, user: &User) -> Result<String, sqlx::Error> {
-        let expiration = Utc::now()
+        let now = Utc::now();
+        let expiration = now
             .checked_add_signed(Duration::hours(24))
             .expect("Valid timestamp")
             .timestamp();
@@ -121,8 +292,11 @@ impl AuthService {
         let claims = Claims {
             sub: user.id,
             exp: expiration,
+            nbf: now.timestamp(),
+            iss: self.jwt_issuer.clone(),
             email: user.email.clone(),
             username: user.username.clone(),
+            role: user.role,
         };
 
         encode(
@@ -133,3 +307,67 @@ impl AuthService {
         .map_err(|e| sqlx::Error::Protocol(format!("Failed to create token: {e}")))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::auth::UserRole;
+    use jsonwebtoken::{decode, errors::ErrorKind, DecodingKey};
+
+    const SECRET: &[u8] = b"unit-test-secret";
+    const ISSUER: &str = "linksphere-test";
+
+    fn token(issuer: &str, not_before: Duration, expires_in: Duration) -> String {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: Uuid::new_v4(),
+            exp: (now + expires_in).timestamp(),
+            nbf: (now + not_before).timestamp(),
+            iss: issuer.to_string(),
+            email: "mock@example.com".to_string(),
+            username: "mock".to_string(),
+            role: UserRole::User,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(SECRET),
+        )
+        .unwrap()
+    }
+
+    fn validate(token: &str) -> Result<Claims, ErrorKind> {
+        decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(SECRET),
+            &access_token_validation(ISSUER),
+        )
+        .map(|data| data.claims)
+        .map_err(|e| e.into_kind())
+    }
+
+    #[test]
+    fn accepts_a_current_token_from_our_issuer() {
+        let claims = validate(&token(ISSUER, Duration::zero(), Duration::hours(1))).unwrap();
+        assert_eq!(claims.iss, ISSUER);
+    }
+
+    #[test]
+    fn rejects_an_expired_token() {
+        // Expired by more than the minute of leeway
+        let expired = token(ISSUER, -Duration::hours(2), -Duration::minutes(5));
+        assert_eq!(validate(&expired).unwrap_err(), ErrorKind::ExpiredSignature);
+    }
+
+    #[test]
+    fn rejects_a_token_from_another_issuer() {
+        let foreign = token("someone-else", Duration::zero(), Duration::hours(1));
+        assert_eq!(validate(&foreign).unwrap_err(), ErrorKind::InvalidIssuer);
+    }
+
+    #[test]
+    fn rejects_a_token_that_is_not_valid_yet() {
+        let early = token(ISSUER, Duration::minutes(5), Duration::hours(1));
+        assert_eq!(validate(&early).unwrap_err(), ErrorKind::ImmatureSignature);
+    }
+}
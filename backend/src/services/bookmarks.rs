@@ -0,0 +1,83 @@
+use crate::database::models::LinkVisibility;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+
+/// A single entry from a browser bookmarks export or a LinkSphere JSON export
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub url: String,
+    pub title: String,
+    /// Only LinkSphere exports carry a description, tags and visibility
+    pub description: String,
+    pub tags: Vec<String>,
+    pub visibility: Option<LinkVisibility>,
+}
+
+/// The fields of an exported link that are carried over on import
+#[derive(Debug, Deserialize)]
+struct ExportedLink {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    visibility: Option<LinkVisibility>,
+}
+
+/// Reads an uploaded bookmarks file: a JSON array is taken to be a LinkSphere export from
+/// `GET /api/links/export`, anything else a Netscape bookmark file
+pub fn parse_bookmarks_file(contents: &str) -> Result<Vec<Bookmark>, serde_json::Error> {
+    if contents.trim_start().starts_with('[') {
+        parse_link_export(contents)
+    } else {
+        Ok(parse_netscape_bookmarks(contents))
+    }
+}
+
+/// Extracts links from a LinkSphere JSON export, keeping their title, description, tags
+/// and visibility; fields such as click counts and previews are left behind
+pub fn parse_link_export(json: &str) -> Result<Vec<Bookmark>, serde_json::Error> {
+    let links: Vec<ExportedLink> = serde_json::from_str(json)?;
+    Ok(links
+        .into_iter()
+        .map(|link| Bookmark {
+            title: if link.title.trim().is_empty() {
+                link.url.clone()
+            } else {
+                link.title
+            },
+            url: link.url,
+            description: link.description,
+            tags: link.tags,
+            visibility: link.visibility,
+        })
+        .collect())
+}
+
+/// Extracts bookmarks from a Netscape bookmark file (`<DL><DT><A HREF="...">`),
+/// the format browsers use for bookmark exports
+///
+/// Folders are flattened; every anchor with an `href` becomes a bookmark titled
+/// by its text, or by the URL when the text is empty.
+pub fn parse_netscape_bookmarks(html: &str) -> Vec<Bookmark> {
+    let document = Html::parse_document(html);
+    let anchor_selector = Selector::parse("a[href]").unwrap();
+
+    document
+        .select(&anchor_selector)
+        .filter_map(|anchor| {
+            let url = anchor.value().attr("href")?.trim().to_string();
+            let title = anchor.text().collect::<String>().trim().to_string();
+            let title = if title.is_empty() { url.clone() } else { title };
+            Some(Bookmark {
+                url,
+                title,
+                description: String::new(),
+                tags: Vec::new(),
+                visibility: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,230 @@
+use crate::database::{
+    queries::{flush_clicks, BufferedClick},
+    LinkCache, PgPool,
+};
+use std::{
+    collections::HashMap,
+    env, mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::Notify, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// How often buffered clicks are written, unless `CLICK_FLUSH_INTERVAL_MS` says otherwise
+const DEFAULT_FLUSH_INTERVAL_MS: u64 = 1000;
+/// Buffered clicks that trigger a write before the interval is up, unless
+/// `CLICK_FLUSH_MAX_CLICKS` says otherwise
+const DEFAULT_FLUSH_MAX_CLICKS: usize = 500;
+/// Flushes' worth of clicks kept while the database is unreachable; older ones are dropped
+const MAX_PENDING_FLUSHES: usize = 20;
+
+#[derive(Default)]
+struct Pending {
+    clicks: Vec<BufferedClick>,
+    per_link: HashMap<Uuid, i64>,
+}
+
+/// Collects counted clicks in memory so they can be written to the database in batches
+///
+/// Clicks still in the buffer are lost if the process dies without a graceful shutdown,
+/// and a link's click count lags behind by at most one flush interval. While writes keep
+/// failing the buffer holds at most [`MAX_PENDING_FLUSHES`] flushes' worth of clicks.
+#[derive(Clone)]
+pub struct ClickBuffer {
+    pending: Arc<Mutex<Pending>>,
+    full: Arc<Notify>,
+    interval: Duration,
+    max_clicks: usize,
+}
+
+impl ClickBuffer {
+    pub fn new(interval: Duration, max_clicks: usize) -> Self {
+        Self {
+            pending: Arc::default(),
+            full: Arc::default(),
+            interval,
+            max_clicks,
+        }
+    }
+
+    /// Builds the click buffer from `CLICK_FLUSH_INTERVAL_MS` (default 1000) and
+    /// `CLICK_FLUSH_MAX_CLICKS` (default 500)
+    pub fn from_env() -> Self {
+        let interval_ms = env::var("CLICK_FLUSH_INTERVAL_MS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_FLUSH_INTERVAL_MS);
+        let max_clicks = env::var("CLICK_FLUSH_MAX_CLICKS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_FLUSH_MAX_CLICKS);
+        Self::new(Duration::from_millis(interval_ms.max(1)), max_clicks.max(1))
+    }
+
+    /// Buffers a counted click and returns how many clicks on its link are now waiting
+    pub fn push(&self, click: BufferedClick) -> i64 {
+        let mut pending = self.pending.lock().expect("click buffer lock poisoned");
+        let waiting = pending.per_link.entry(click.link_id).or_default();
+        *waiting += 1;
+        let waiting = *waiting;
+        pending.clicks.push(click);
+        if pending.clicks.len() == self.max_clicks {
+            self.full.notify_one();
+        }
+        waiting
+    }
+
+    /// How many counted clicks on a link are waiting to be written
+    pub fn waiting(&self, link_id: Uuid) -> i64 {
+        let pending = self.pending.lock().expect("click buffer lock poisoned");
+        pending.per_link.get(&link_id).copied().unwrap_or_default()
+    }
+
+    /// Empties the buffer, returning the clicks that were waiting
+    fn take(&self) -> Vec<BufferedClick> {
+        let mut pending = self.pending.lock().expect("click buffer lock poisoned");
+        pending.per_link.clear();
+        mem::take(&mut pending.clicks)
+    }
+
+    /// Puts back clicks that couldn't be written, ahead of the ones buffered since
+    ///
+    /// When that leaves more than the buffer may hold, the oldest clicks are dropped so a
+    /// long database outage can't exhaust memory.
+    fn restore(&self, mut clicks: Vec<BufferedClick>) {
+        let mut pending = self.pending.lock().expect("click buffer lock poisoned");
+        clicks.append(&mut pending.clicks);
+
+        let capacity = self.max_clicks.saturating_mul(MAX_PENDING_FLUSHES);
+        let dropped = clicks.len().saturating_sub(capacity);
+        if dropped > 0 {
+            clicks.drain(..dropped);
+            tracing::warn!(
+                "Dropped the {dropped} oldest buffered clicks to stay within {capacity}"
+            );
+        }
+
+        pending.per_link.clear();
+        for click in &clicks {
+            *pending.per_link.entry(click.link_id).or_default() += 1;
+        }
+        pending.clicks = clicks;
+    }
+
+    /// Writes every buffered click, returning how many were written
+    ///
+    /// On failure the clicks go back into the buffer for the next flush.
+    async fn flush(&self, pool: &PgPool, cache: &LinkCache) -> Result<usize, sqlx::Error> {
+        let clicks = self.take();
+        if clicks.is_empty() {
+            return Ok(0);
+        }
+
+        match flush_clicks(pool, &clicks).await {
+            Ok(link_ids) => {
+                for link_id in link_ids {
+                    cache.invalidate(link_id).await;
+                }
+                Ok(clicks.len())
+            }
+            Err(e) => {
+                self.restore(clicks);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Starts the background task that writes buffered clicks
+///
+/// The buffer is flushed every interval, or sooner once it holds the configured number of
+/// clicks. Once `shutdown` is cancelled the task flushes whatever is left and exits, so
+/// awaiting the returned handle after the server stops keeps clicks from being lost.
+pub fn spawn_click_flusher(
+    pool: PgPool,
+    cache: LinkCache,
+    buffer: ClickBuffer,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let stopping = tokio::select! {
+                _ = tokio::time::sleep(buffer.interval) => false,
+                _ = buffer.full.notified() => false,
+                _ = shutdown.cancelled() => true,
+            };
+
+            match buffer.flush(&pool, &cache).await {
+                Ok(written) if stopping => {
+                    tracing::info!("Flushed {written} buffered clicks before shutdown");
+                }
+                Ok(_) => {}
+                Err(e) if stopping => {
+                    let lost = buffer.take().len();
+                    tracing::error!("Failed to flush {lost} buffered clicks before shutdown: {e}");
+                }
+                Err(e) => tracing::warn!("Failed to flush buffered clicks, will retry: {e}"),
+            }
+
+            if stopping {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn click(link_id: Uuid, ip_hash: &str) -> BufferedClick {
+        BufferedClick {
+            link_id,
+            referrer: None,
+            user_agent: None,
+            ip_hash: ip_hash.to_string(),
+            clicked_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn restore_puts_failed_clicks_back_ahead_of_newer_ones() {
+        let buffer = ClickBuffer::new(Duration::from_secs(1), 10);
+        let link_id = Uuid::new_v4();
+        buffer.push(click(link_id, "old"));
+        let failed = buffer.take();
+        buffer.push(click(link_id, "new"));
+
+        buffer.restore(failed);
+
+        assert_eq!(buffer.waiting(link_id), 2);
+        let order: Vec<String> = buffer.take().into_iter().map(|c| c.ip_hash).collect();
+        assert_eq!(order, ["old", "new"]);
+    }
+
+    #[test]
+    fn restore_drops_the_oldest_clicks_beyond_capacity() {
+        let max_clicks = 2;
+        let capacity = max_clicks * MAX_PENDING_FLUSHES;
+        let buffer = ClickBuffer::new(Duration::from_secs(1), max_clicks);
+        let (old_link, new_link) = (Uuid::new_v4(), Uuid::new_v4());
+        let failed: Vec<BufferedClick> = (0..capacity)
+            .map(|i| click(old_link, &format!("old-{i}")))
+            .collect();
+        for i in 0..3 {
+            buffer.push(click(new_link, &format!("new-{i}")));
+        }
+
+        buffer.restore(failed);
+
+        assert_eq!(buffer.waiting(old_link), capacity as i64 - 3);
+        assert_eq!(buffer.waiting(new_link), 3);
+        let kept = buffer.take();
+        assert_eq!(kept.len(), capacity);
+        assert_eq!(kept[0].ip_hash, "old-3");
+        assert_eq!(kept[capacity - 1].ip_hash, "new-2");
+    }
+}
@@ -0,0 +1,53 @@
+use crate::database::models::Link;
+
+/// Escapes text for use inside XML element content or attribute values
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a user's links as an RSS 2.0 document
+///
+/// `site_url` becomes the channel link; each item links straight to the saved URL.
+pub fn render_rss(username: &str, site_url: &str, links: &[Link]) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    xml.push_str("\n<rss version=\"2.0\">\n<channel>\n");
+    xml.push_str(&format!(
+        "<title>{} on LinkSphere</title>\n",
+        escape_xml(username)
+    ));
+    xml.push_str(&format!("<link>{}</link>\n", escape_xml(site_url)));
+    xml.push_str(&format!(
+        "<description>Recent public links shared by {}</description>\n",
+        escape_xml(username)
+    ));
+
+    for link in links {
+        xml.push_str("<item>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&link.title)));
+        xml.push_str(&format!("<link>{}</link>\n", escape_xml(&link.url)));
+        xml.push_str(&format!(
+            "<description>{}</description>\n",
+            escape_xml(&link.description)
+        ));
+        xml.push_str(&format!(
+            "<pubDate>{}</pubDate>\n",
+            link.created_at.to_rfc2822()
+        ));
+        xml.push_str(&format!("<guid isPermaLink=\"false\">{}</guid>\n", link.id));
+        xml.push_str("</item>\n");
+    }
+
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
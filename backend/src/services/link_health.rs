@@ -0,0 +1,247 @@
+use crate::{
+    database::{
+        models::LinkHealth,
+        queries::{get_links_due_for_health_check, record_link_health},
+        LinkCache, PgPool,
+    },
+    services::link_preview::{check_host, redirect_policy, LinkPreviewError, PublicOnlyResolver},
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures_util::{stream, StreamExt};
+use reqwest::{Client, StatusCode};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use url::Url;
+use uuid::Uuid;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Kept short since the submitter waits for it
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often the background checker picks up a batch of links
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Links are checked again once their last check is this old
+const RECHECK_AFTER: TimeDelta = TimeDelta::hours(24);
+const CHECK_BATCH_SIZE: i64 = 50;
+const MAX_CONCURRENT_CHECKS: usize = 8;
+/// Failed checks in a row before a link is flagged dead
+const DEAD_AFTER_FAILURES: i32 = 3;
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// How a single check went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckOutcome {
+    /// The target answered with a success or redirect status
+    Reachable,
+    /// The target answered with a 4xx/5xx status, or its host couldn't be reached
+    Failed,
+    /// The check timed out or was rate limited, which says nothing about the link
+    Inconclusive,
+}
+
+impl CheckOutcome {
+    fn reachable(self) -> Option<bool> {
+        match self {
+            CheckOutcome::Reachable => Some(true),
+            CheckOutcome::Failed => Some(false),
+            CheckOutcome::Inconclusive => None,
+        }
+    }
+}
+
+/// Result of requesting a link's URL
+#[derive(Debug)]
+pub struct HealthCheck {
+    /// Status the target answered with, if it answered
+    pub status: Option<u16>,
+    pub outcome: CheckOutcome,
+    /// Why the request failed, if it did
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    fn failed(error: impl ToString) -> Self {
+        Self {
+            status: None,
+            outcome: CheckOutcome::Failed,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Client that follows the same redirect and address rules as preview fetches
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .user_agent("LinkSphere-LinkChecker/1.0")
+            .timeout(CHECK_TIMEOUT)
+            .redirect(redirect_policy())
+            .dns_resolver(Arc::new(PublicOnlyResolver))
+            .build()
+            .expect("link checker HTTP client configuration is valid")
+    })
+}
+
+/// Sends a HEAD request to a URL and classifies the answer
+///
+/// Servers that reject HEAD with 405 or 501 are asked again with GET; the body is never read.
+pub async fn check_url(url: &str) -> HealthCheck {
+    let url = match Url::parse(url) {
+        Ok(url) => url,
+        Err(e) => return HealthCheck::failed(format!("Invalid URL: {e}")),
+    };
+    if let Err(e) = check_host(&url) {
+        return HealthCheck::failed(e);
+    }
+
+    match head_or_get(url, CHECK_TIMEOUT).await {
+        Ok(response) => {
+            let status = response.status();
+            let outcome = if status == StatusCode::TOO_MANY_REQUESTS {
+                CheckOutcome::Inconclusive
+            } else if status.is_client_error() || status.is_server_error() {
+                CheckOutcome::Failed
+            } else {
+                CheckOutcome::Reachable
+            };
+            HealthCheck {
+                status: Some(status.as_u16()),
+                outcome,
+                error: None,
+            }
+        }
+        Err(e) => {
+            // DNS failures, refused connections and bad redirects mean the target is gone
+            let outcome = if e.is_timeout() {
+                CheckOutcome::Inconclusive
+            } else if e.is_connect() || e.is_redirect() {
+                CheckOutcome::Failed
+            } else {
+                CheckOutcome::Inconclusive
+            };
+            HealthCheck {
+                status: None,
+                outcome,
+                error: Some(format!("{:#}", anyhow::Error::from(e))),
+            }
+        }
+    }
+}
+
+/// Finds out whether a URL someone is submitting clearly doesn't exist
+///
+/// Returns why when it doesn't: the server answered 404 or 410, or the host doesn't
+/// resolve or refuses connections. Anything less clear lets the link through, including
+/// timeouts, 403s from bot protection and hosts on internal networks, which are never
+/// contacted.
+pub async fn find_unreachable(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    check_host(&url).ok()?;
+
+    match head_or_get(url, VERIFY_TIMEOUT).await {
+        Ok(response) => matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE)
+            .then(|| format!("The server answered {}", response.status())),
+        Err(e) if e.is_connect() && !is_blocked_host(&e) => {
+            Some(format!("{:#}", anyhow::Error::from(e)))
+        }
+        Err(_) => None,
+    }
+}
+
+/// Whether a request failed because the host only resolved to internal addresses
+fn is_blocked_host(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        if matches!(
+            cause.downcast_ref::<LinkPreviewError>(),
+            Some(LinkPreviewError::BlockedHost(_))
+        ) {
+            return true;
+        }
+        source = cause.source();
+    }
+    false
+}
+
+/// Sends a HEAD request, asking again with GET if the server rejects HEAD with 405 or 501
+async fn head_or_get(url: Url, timeout: Duration) -> reqwest::Result<reqwest::Response> {
+    let response = client().head(url.clone()).timeout(timeout).send().await;
+    if response.as_ref().is_ok_and(|response| {
+        matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        )
+    }) {
+        return client().get(url).timeout(timeout).send().await;
+    }
+    response
+}
+
+/// Stores a check's outcome on the link and drops it from the cache
+///
+/// Returns the link's resulting health and check time, or None if the link is gone.
+pub async fn record_check(
+    pool: &PgPool,
+    cache: &LinkCache,
+    link_id: Uuid,
+    check: &HealthCheck,
+) -> Result<Option<(LinkHealth, DateTime<Utc>)>, sqlx::Error> {
+    let result = record_link_health(
+        pool,
+        link_id,
+        check.outcome.reachable(),
+        DEAD_AFTER_FAILURES,
+    )
+    .await?;
+    cache.invalidate(link_id).await;
+    Ok(result)
+}
+
+/// Checks one batch of the links that are due, returning how many were checked
+async fn check_due_links(pool: &PgPool, cache: &LinkCache) -> Result<usize, sqlx::Error> {
+    let links =
+        get_links_due_for_health_check(pool, Utc::now() - RECHECK_AFTER, CHECK_BATCH_SIZE).await?;
+    let checked = links.len();
+
+    stream::iter(links)
+        .for_each_concurrent(MAX_CONCURRENT_CHECKS, |link| async move {
+            let check = check_url(&link.url).await;
+            match record_check(pool, cache, link.id, &check).await {
+                Ok(Some((LinkHealth::Dead, _))) if check.outcome == CheckOutcome::Failed => {
+                    tracing::info!(
+                        link_id = %link.id,
+                        status = check.status,
+                        error = check.error.as_deref(),
+                        "Link is dead"
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!(link_id = %link.id, "Failed to record link health: {e}");
+                }
+            }
+        })
+        .await;
+
+    Ok(checked)
+}
+
+/// Spawns a background task that checks links whose last check is over a day old
+///
+/// Each minute it checks one batch, never-checked links first. Links are flagged dead
+/// after several failed checks in a row, never deleted.
+pub fn spawn_link_health_checker(pool: PgPool, cache: LinkCache) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match check_due_links(&pool, &cache).await {
+                Ok(0) => {}
+                Ok(checked) => tracing::debug!("Checked the health of {checked} links"),
+                Err(e) => tracing::warn!("Failed to load links due for a health check: {e}"),
+            }
+        }
+    });
+}
@@ -1,47 +1,474 @@
-use crate::database::models::LinkPreview;
+use crate::{
+    database::models::{LinkPreview, LinkPreviewKind, PreviewValidators},
+    metrics::{PREVIEW_FETCH_FAILURE, PREVIEW_FETCH_SUCCESS, PREVIEW_TIMEOUT},
+    services::robots::robots_allow,
+};
 use anyhow::{anyhow, Context, Result};
-use reqwest::{header, Client};
+use encoding_rs::{Encoding, UTF_8};
+use percent_encoding::percent_decode_str;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header, redirect, Client, StatusCode,
+};
 use scraper::{Html, Selector};
-use std::time::Duration;
-use url::Url;
+use serde::Deserialize;
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use url::{Host, Url};
 
-pub async fn fetch_link_preview(url: &str) -> Result<LinkPreview> {
+pub(crate) const MAX_RETRY_ATTEMPTS: u32 = 3;
+pub(crate) const INITIAL_RETRY_DELAY_MS: u64 = 1000;
+const DEFAULT_FETCH_TIMEOUT_SECS: u64 = 10;
+/// Names the crawler and where to find out about it, so site owners can tell it apart
+const DEFAULT_USER_AGENT: &str =
+    "LinkSphereBot/1.0 (+https://github.com/Nkwenti-Severian-Ndongtsop/LinkSphere)";
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024; // 2 MiB
+const MAX_OEMBED_BYTES: usize = 64 * 1024; // 64 KiB
+/// Only the start of a page is searched for a `<meta>` charset declaration, as browsers do
+const CHARSET_SNIFF_BYTES: usize = 1024;
+const MAX_REDIRECTS: usize = 5;
+const DEFAULT_MAX_CONCURRENT_FETCHES: usize = 20;
+/// Permit waits at least this long are logged at info level so the limit can be tuned
+const SLOW_PERMIT_WAIT: Duration = Duration::from_secs(1);
+
+static FETCH_PERMITS: OnceLock<Semaphore> = OnceLock::new();
+static DEFAULT_IMAGE: OnceLock<Option<String>> = OnceLock::new();
+static USER_AGENT: OnceLock<String> = OnceLock::new();
+
+lazy_static::lazy_static! {
+    /// Matches `<meta charset="...">` as well as the charset in a `http-equiv` content type
+    static ref META_CHARSET_REGEX: regex::bytes::Regex =
+        regex::bytes::Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap();
+}
+
+#[derive(Debug, Error)]
+pub enum LinkPreviewError {
+    #[error("Link preview fetch timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("Link preview fetch followed more than {0} redirects")]
+    TooManyRedirects(usize),
+    #[error("Refusing to fetch link preview from private or loopback host {0}")]
+    BlockedHost(String),
+    #[error("robots.txt of {0} disallows fetching its link preview")]
+    Disallowed(String),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// What a preview fetch found
+#[derive(Debug)]
+pub enum PreviewFetch {
+    /// The page was downloaded, along with the validators to send on the next refresh
+    Fetched {
+        preview: Box<LinkPreview>,
+        validators: PreviewValidators,
+    },
+    /// The server answered 304, so the stored preview is still current
+    NotModified,
+}
+
+impl LinkPreviewError {
+    /// Surfaces a redirect, host or robots.txt rejection buried in an error chain
+    fn from_anyhow(error: anyhow::Error) -> Self {
+        for cause in error.chain() {
+            match cause.downcast_ref::<LinkPreviewError>() {
+                Some(LinkPreviewError::TooManyRedirects(max)) => {
+                    return LinkPreviewError::TooManyRedirects(*max)
+                }
+                Some(LinkPreviewError::BlockedHost(host)) => {
+                    return LinkPreviewError::BlockedHost(host.clone())
+                }
+                Some(LinkPreviewError::Disallowed(url)) => {
+                    return LinkPreviewError::Disallowed(url.clone())
+                }
+                _ => {}
+            }
+        }
+        LinkPreviewError::Other(error)
+    }
+}
+
+/// Whether an address belongs to a loopback, private, link-local or otherwise internal range
+fn is_blocked_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // "This network", 0.0.0.0/8
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+                // Benchmarking, 198.18.0.0/15
+                || (a == 198 && (b & 0xfe) == 18)
+                // Reserved, 240.0.0.0/4, which includes the broadcast address
+                || a >= 240
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            let first = segments[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80
+                || embedded_ipv4(segments).is_some_and(|ip| is_blocked_ip(IpAddr::V4(ip)))
+        }
+    }
+}
+
+/// The IPv4 address carried by an IPv4-mapped (`::ffff:a.b.c.d`), IPv4-compatible
+/// (`::a.b.c.d`) or NAT64 (`64:ff9b::a.b.c.d`) IPv6 address, any of which can reach it
+fn embedded_ipv4(segments: [u16; 8]) -> Option<Ipv4Addr> {
+    let carries_ipv4 = matches!(
+        segments[..6],
+        [0, 0, 0, 0, 0, 0xffff] | [0, 0, 0, 0, 0, 0] | [0x64, 0xff9b, 0, 0, 0, 0]
+    );
+    carries_ipv4.then(|| {
+        let [high, low] = [segments[6], segments[7]];
+        Ipv4Addr::from((u32::from(high) << 16) | u32::from(low))
+    })
+}
+
+/// Rejects URLs whose host is an internal IP literal or `localhost`
+///
+/// Hostnames are checked again after DNS resolution by [`PublicOnlyResolver`].
+pub(crate) fn check_host(url: &Url) -> Result<(), LinkPreviewError> {
+    let blocked = match url.host() {
+        Some(Host::Ipv4(ip)) => is_blocked_ip(IpAddr::V4(ip)),
+        Some(Host::Ipv6(ip)) => is_blocked_ip(IpAddr::V6(ip)),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost")
+        }
+        None => true,
+    };
+
+    if blocked {
+        Err(LinkPreviewError::BlockedHost(
+            url.host_str().unwrap_or_default().to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// DNS resolver that drops internal addresses, so hostnames pointing at
+/// private ranges can't be used to reach internal services
+pub(crate) struct PublicOnlyResolver;
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| !is_blocked_ip(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(LinkPreviewError::BlockedHost(host).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Follows at most `MAX_REDIRECTS` hops, never to an internal host
+pub(crate) fn redirect_policy() -> redirect::Policy {
+    redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error(LinkPreviewError::TooManyRedirects(MAX_REDIRECTS));
+        }
+        match check_host(attempt.url()) {
+            Ok(()) => attempt.follow(),
+            Err(e) => attempt.error(e),
+        }
+    })
+}
+
+/// Hard deadline for a single preview fetch, configurable via `LINK_PREVIEW_TIMEOUT_SECS`
+fn fetch_timeout() -> Duration {
+    let secs = env::var("LINK_PREVIEW_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_FETCH_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// User-Agent sent with preview fetches, configurable via `LINK_PREVIEW_USER_AGENT`
+///
+/// Some sites only serve Open Graph tags to crawlers they recognise.
+fn user_agent() -> &'static str {
+    USER_AGENT.get_or_init(|| {
+        env::var("LINK_PREVIEW_USER_AGENT")
+            .ok()
+            .map(|agent| agent.trim().to_string())
+            .filter(|agent| !agent.is_empty())
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string())
+    })
+}
+
+/// Whether pages their robots.txt disallows for our User-Agent are skipped, configurable
+/// via `LINK_PREVIEW_RESPECT_ROBOTS`; off by default
+fn respect_robots() -> bool {
+    env::var("LINK_PREVIEW_RESPECT_ROBOTS")
+        .is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Image shown for pages without one, configurable via `DEFAULT_PREVIEW_IMAGE_URL`
+fn default_image() -> Option<&'static str> {
+    DEFAULT_IMAGE
+        .get_or_init(|| {
+            env::var("DEFAULT_PREVIEW_IMAGE_URL")
+                .ok()
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+        })
+        .as_deref()
+}
+
+/// Fills in the default image when the fetched page didn't have one
+fn with_fallback_image(mut fetch: PreviewFetch) -> PreviewFetch {
+    if let PreviewFetch::Fetched { preview, .. } = &mut fetch {
+        if let (None, Some(image)) = (&preview.image, default_image()) {
+            preview.image = Some(image.to_string());
+            preview.image_is_fallback = true;
+        }
+    }
+    fetch
+}
+
+/// Global cap on concurrent preview fetches, configurable via `LINK_PREVIEW_MAX_CONCURRENCY`
+fn fetch_permits() -> &'static Semaphore {
+    FETCH_PERMITS.get_or_init(|| {
+        let permits = env::var("LINK_PREVIEW_MAX_CONCURRENCY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&permits| permits > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_FETCHES);
+        Semaphore::new(permits)
+    })
+}
+
+/// Waits for a free preview fetch slot, logging how long the wait took
+async fn acquire_fetch_permit(url: &str) -> SemaphorePermit<'static> {
+    let started = Instant::now();
+    let permit = fetch_permits()
+        .acquire()
+        .await
+        .expect("link preview semaphore is never closed");
+    let waited = started.elapsed();
+
+    if waited >= SLOW_PERMIT_WAIT {
+        tracing::info!(
+            url = url,
+            wait_ms = waited.as_millis(),
+            "Link preview fetch waited for a concurrency permit"
+        );
+    } else {
+        tracing::debug!(
+            url = url,
+            wait_ms = waited.as_millis(),
+            "Acquired link preview concurrency permit"
+        );
+    }
+    permit
+}
+
+/// Fetches a link preview, retrying transient failures with exponential backoff
+///
+/// Timeouts, connection errors and 5xx responses are retried up to
+/// `MAX_RETRY_ATTEMPTS` times, waiting 1s, 2s, 4s between attempts. Any other
+/// error (404, malformed HTML, invalid URL) is returned immediately.
+pub async fn fetch_link_preview_with_retry(
+    url: &str,
+    validators: &PreviewValidators,
+) -> Result<PreviewFetch, LinkPreviewError> {
+    let mut attempt = 0;
+    loop {
+        match fetch_link_preview(url, validators).await {
+            Ok(preview) => return Ok(preview),
+            Err(e) if attempt < MAX_RETRY_ATTEMPTS && is_transient_error(&e) => {
+                let delay = Duration::from_millis(INITIAL_RETRY_DELAY_MS * 2u64.pow(attempt));
+                attempt += 1;
+                tracing::debug!(
+                    url = url,
+                    attempt = attempt,
+                    delay_ms = delay.as_millis(),
+                    "Retrying link preview fetch after transient error: {e:#}"
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub(crate) fn is_transient_error(error: &LinkPreviewError) -> bool {
+    let error = match error {
+        LinkPreviewError::Timeout(_) => return true,
+        LinkPreviewError::TooManyRedirects(_)
+        | LinkPreviewError::BlockedHost(_)
+        | LinkPreviewError::Disallowed(_) => return false,
+        LinkPreviewError::Other(e) => e,
+    };
+
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|status| status.is_server_error());
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+            );
+        }
+        false
+    })
+}
+
+/// Fetches preview metadata for a URL, giving up after the configured timeout
+///
+/// Non-empty `validators` from an earlier fetch make the request conditional, so an
+/// unchanged page is answered with [`PreviewFetch::NotModified`] instead of being
+/// downloaded again. At most `LINK_PREVIEW_MAX_CONCURRENCY` fetches (default 20) run
+/// at once; the timeout only starts once a slot is free. Pages without an image get
+/// `DEFAULT_PREVIEW_IMAGE_URL` when it is set, flagged with `image_is_fallback`. With
+/// `LINK_PREVIEW_OEMBED_FALLBACK=true`, a page missing a title or description has them
+/// completed from the oEmbed endpoint it advertises. Requests are sent as
+/// `LINK_PREVIEW_USER_AGENT`, and with `LINK_PREVIEW_RESPECT_ROBOTS=true` a page its host's
+/// robots.txt disallows fails with [`LinkPreviewError::Disallowed`] without being fetched.
+pub async fn fetch_link_preview(
+    url: &str,
+    validators: &PreviewValidators,
+) -> Result<PreviewFetch, LinkPreviewError> {
+    let _permit = acquire_fetch_permit(url).await;
+    let timeout = fetch_timeout();
+    let result = match tokio::time::timeout(timeout, fetch_preview(url, validators, timeout)).await
+    {
+        Ok(result) => result
+            .map(with_fallback_image)
+            .map_err(LinkPreviewError::from_anyhow),
+        Err(_) => Err(LinkPreviewError::Timeout(timeout)),
+    };
+
+    let outcome = match &result {
+        Ok(_) => PREVIEW_FETCH_SUCCESS,
+        Err(LinkPreviewError::Timeout(_)) => PREVIEW_TIMEOUT,
+        Err(_) => PREVIEW_FETCH_FAILURE,
+    };
+    metrics::counter!(outcome).increment(1);
+
+    result
+}
+
+async fn fetch_preview(
+    url: &str,
+    validators: &PreviewValidators,
+    timeout: Duration,
+) -> Result<PreviewFetch> {
     let client = Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-        .timeout(Duration::from_secs(10))
+        .user_agent(user_agent())
+        .timeout(timeout)
+        .redirect(redirect_policy())
+        .dns_resolver(Arc::new(PublicOnlyResolver))
         .build()?;
 
     let base_url = Url::parse(url)?;
+    check_host(&base_url)?;
 
     // Special handling for YouTube URLs
     if is_youtube_url(&base_url) {
-        return fetch_youtube_preview(&client, &base_url).await;
+        return fetch_youtube_preview(&client, &base_url)
+            .await
+            .map(PreviewFetch::from);
     }
 
-    let response = client
-        .get(url)
+    if respect_robots() && !robots_allow(&client, &base_url, user_agent()).await {
+        return Err(LinkPreviewError::Disallowed(url.to_string()).into());
+    }
+
+    // Ask for the headers first so documents, images and videos are never downloaded
+    if let Some(kind) = head_content_kind(&client, url).await {
+        if kind != LinkPreviewKind::Html {
+            return Ok(non_html_preview(&base_url, kind).into());
+        }
+    }
+
+    let mut request = client.get(url);
+    if let Some(etag) = &validators.etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+    }
+    let response = request
         .send()
         .await
+        .and_then(|response| response.error_for_status())
         .context("Failed to fetch URL")?;
 
-    // Check content type
-    let content_type = response
-        .headers()
-        .get(header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(PreviewFetch::NotModified);
+    }
 
-    if !content_type.contains("text/html") {
-        return Ok(LinkPreview {
-            title: Some(url.to_string()),
-            description: None,
-            image: None,
-            favicon: None,
-        });
+    // The HEAD request may have been refused, so check the real response too
+    let kind = content_kind(&response);
+    if kind != LinkPreviewKind::Html {
+        return Ok(non_html_preview(&base_url, kind).into());
     }
 
-    let html = response.text().await?;
-    let document = Html::parse_document(&html);
+    let validators = PreviewValidators {
+        etag: header_value(&response, header::ETAG),
+        last_modified: header_value(&response, header::LAST_MODIFIED),
+    };
+
+    // Relative URLs in the page are relative to where redirects ended up
+    let page_url = response.url().clone();
+    let content_type = header_value(&response, header::CONTENT_TYPE);
+    let body = read_body_limited(response, MAX_BODY_BYTES).await?;
+    let html = decode_html(&body, content_type.as_deref());
+    let (mut preview, oembed_url) = parse_html_preview(&html, &page_url);
+
+    // Single-page apps often render their metadata in the browser, leaving the
+    // served HTML without it; an advertised oEmbed endpoint can fill the gaps
+    if (is_blank(&preview.title) || is_blank(&preview.description)) && oembed_fallback_enabled() {
+        if let Some(oembed_url) = oembed_url {
+            match fetch_oembed(&client, oembed_url).await {
+                Ok(oembed) => oembed.fill(&mut preview),
+                Err(e) => tracing::debug!(url = url, "Failed to fetch oEmbed data: {e:#}"),
+            }
+        }
+    }
+
+    Ok(PreviewFetch::Fetched {
+        preview: Box::new(preview),
+        validators,
+    })
+}
+
+/// Reads the preview metadata of an HTML page, along with its oEmbed endpoint if it has one
+///
+/// Relative URLs are resolved against `page_url`.
+fn parse_html_preview(html: &str, page_url: &Url) -> (LinkPreview, Option<Url>) {
+    let document = Html::parse_document(html);
 
     // Selectors for metadata
     let title_selector =
@@ -49,7 +476,14 @@ pub async fn fetch_link_preview(url: &str) -> Result<LinkPreview> {
     let desc_selector = Selector::parse("meta[property='og:description'], meta[name='twitter:description'], meta[name='description']").unwrap();
     let image_selector =
         Selector::parse("meta[property='og:image'], meta[name='twitter:image']").unwrap();
-    let favicon_selector = Selector::parse("link[rel='icon'], link[rel='shortcut icon']").unwrap();
+    // `~=` matches `icon` as one of several rel values, such as `shortcut icon`
+    let favicon_selector = Selector::parse("link[rel~='icon' i][href]").unwrap();
+    let site_name_selector =
+        Selector::parse("meta[property='og:site_name'], meta[name='application-name']").unwrap();
+    let locale_selector = Selector::parse("meta[property='og:locale']").unwrap();
+    let oembed_selector =
+        Selector::parse("link[rel~='alternate' i][type='application/json+oembed' i][href]")
+            .unwrap();
 
     // Extract metadata
     let title = document.select(&title_selector).next().map(|el| {
@@ -69,22 +503,221 @@ pub async fn fetch_link_preview(url: &str) -> Result<LinkPreview> {
         .select(&image_selector)
         .next()
         .and_then(|el| el.value().attr("content"))
-        .map(|href| resolve_url(&base_url, href));
+        .map(|href| resolve_url(page_url, href));
 
+    // Browsers request /favicon.ico when a page doesn't declare an icon
     let favicon = document
         .select(&favicon_selector)
         .next()
         .and_then(|el| el.value().attr("href"))
-        .map(|href| resolve_url(&base_url, href));
+        .map(|href| resolve_url(page_url, href))
+        .or_else(|| page_url.join("/favicon.ico").ok().map(String::from));
+
+    let site_name = document
+        .select(&site_name_selector)
+        .next()
+        .and_then(|el| el.value().attr("content"))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from);
+
+    // og:locale uses underscores (`en_US`) where lang attributes use hyphens (`en-US`)
+    let language = document
+        .select(&locale_selector)
+        .next()
+        .and_then(|el| el.value().attr("content"))
+        .or_else(|| document.root_element().value().attr("lang"))
+        .map(str::trim)
+        .filter(|language| !language.is_empty())
+        .map(|language| language.replace('_', "-"));
+
+    let oembed_url = document
+        .select(&oembed_selector)
+        .next()
+        .and_then(|el| el.value().attr("href"))
+        .and_then(|href| page_url.join(href).ok());
 
-    Ok(LinkPreview {
+    let preview = LinkPreview {
         title,
         description,
         image,
         favicon,
+        site_name,
+        language,
+        kind: LinkPreviewKind::Html,
+        image_is_fallback: false,
+        thumbnail_small: None,
+        thumbnail_large: None,
+    };
+    (preview, oembed_url)
+}
+
+/// The fields of an oEmbed response a preview can use
+#[derive(Debug, Deserialize)]
+struct OEmbed {
+    title: Option<String>,
+    author_name: Option<String>,
+    provider_name: Option<String>,
+    thumbnail_url: Option<String>,
+}
+
+impl OEmbed {
+    /// Fills in what the page itself didn't provide, leaving its own metadata alone
+    fn fill(self, preview: &mut LinkPreview) {
+        fill_blank(&mut preview.title, self.title);
+        fill_blank(
+            &mut preview.description,
+            self.author_name.map(|author| format!("By {author}")),
+        );
+        fill_blank(&mut preview.image, self.thumbnail_url);
+        fill_blank(&mut preview.site_name, self.provider_name);
+    }
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|value| value.trim().is_empty())
+}
+
+fn fill_blank(field: &mut Option<String>, value: Option<String>) {
+    if is_blank(field) && !is_blank(&value) {
+        *field = value;
+    }
+}
+
+/// Whether pages missing metadata may be completed from their oEmbed endpoint, configurable
+/// via `LINK_PREVIEW_OEMBED_FALLBACK`; off by default since it costs another request
+fn oembed_fallback_enabled() -> bool {
+    env::var("LINK_PREVIEW_OEMBED_FALLBACK")
+        .is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Fetches the oEmbed JSON a page advertises, with the same host checks as the page itself
+async fn fetch_oembed(client: &Client, url: Url) -> Result<OEmbed> {
+    check_host(&url)?;
+    let response = client
+        .get(url)
+        .header(header::ACCEPT, "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .context("Failed to fetch oEmbed endpoint")?;
+    let body = read_body_limited(response, MAX_OEMBED_BYTES).await?;
+    serde_json::from_slice(&body).context("oEmbed endpoint returned invalid JSON")
+}
+
+impl From<LinkPreview> for PreviewFetch {
+    /// A preview that can't be fetched conditionally
+    fn from(preview: LinkPreview) -> Self {
+        PreviewFetch::Fetched {
+            preview: Box::new(preview),
+            validators: PreviewValidators::default(),
+        }
+    }
+}
+
+fn header_value(response: &reqwest::Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// Content kind reported by a HEAD request, or None if the server didn't answer it usefully
+async fn head_content_kind(client: &Client, url: &str) -> Option<LinkPreviewKind> {
+    let response = client.head(url).send().await.ok()?;
+    if !response.status().is_success() || !response.headers().contains_key(header::CONTENT_TYPE) {
+        return None;
+    }
+    Some(content_kind(&response))
+}
+
+fn content_kind(response: &reqwest::Response) -> LinkPreviewKind {
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    LinkPreviewKind::from_content_type(content_type)
+}
+
+/// Preview for a resource that has no HTML metadata to parse
+///
+/// The title is the file name from the URL; images also use the URL as their image.
+fn non_html_preview(url: &Url, kind: LinkPreviewKind) -> LinkPreview {
+    let title = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .map(|name| percent_decode_str(name).decode_utf8_lossy().into_owned())
+        .unwrap_or_else(|| url.to_string());
+
+    LinkPreview {
+        title: Some(title),
+        description: None,
+        image: (kind == LinkPreviewKind::Image).then(|| url.to_string()),
+        favicon: None,
+        site_name: None,
+        language: None,
+        kind,
+        image_is_fallback: false,
+        thumbnail_small: None,
+        thumbnail_large: None,
+    }
+}
+
+/// Reads at most `limit` bytes of the response body
+///
+/// Preview metadata lives in the document head, so anything past the limit is
+/// dropped rather than buffered.
+pub(crate) async fn read_body_limited(
+    mut response: reqwest::Response,
+    limit: usize,
+) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let remaining = limit - body.len();
+        if chunk.len() >= remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Decodes an HTML page to UTF-8
+///
+/// A byte order mark wins, then the charset from the `Content-Type` header, then a
+/// `<meta>` declaration near the top of the page. Pages declaring none are read as UTF-8.
+fn decode_html(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(charset_from_content_type)
+        .or_else(|| meta_charset(body))
+        .unwrap_or(UTF_8);
+    let (html, _, _) = encoding.decode(body);
+    html.into_owned()
+}
+
+/// Encoding named by the `charset` parameter of a content type, if it is a known one
+fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
     })
 }
 
+/// Encoding declared by a `<meta>` tag at the start of the page
+fn meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+    let head = &body[..body.len().min(CHARSET_SNIFF_BYTES)];
+    let label = META_CHARSET_REGEX.captures(head)?.get(1)?;
+    // A page can't declare itself UTF-16 from inside an ASCII-compatible meta tag
+    Encoding::for_label(label.as_bytes()).map(Encoding::output_encoding)
+}
+
 fn resolve_url(base: &Url, path: &str) -> String {
     if path.starts_with("http://") || path.starts_with("https://") {
         path.to_string()
@@ -139,6 +772,12 @@ async fn fetch_youtube_preview(client: &Client, url: &Url) -> Result<LinkPreview
                             )),
                             image,
                             favicon: Some("https://www.youtube.com/favicon.ico".to_string()),
+                            site_name: Some("YouTube".to_string()),
+                            language: None,
+                            kind: LinkPreviewKind::Video,
+                            image_is_fallback: false,
+                            thumbnail_small: None,
+                            thumbnail_large: None,
                         });
                     }
                 }
@@ -184,6 +823,12 @@ async fn fetch_youtube_preview(client: &Client, url: &Url) -> Result<LinkPreview
             )),
             image: Some(image),
             favicon: Some("https://www.youtube.com/favicon.ico".to_string()),
+            site_name: Some("YouTube".to_string()),
+            language: None,
+            kind: LinkPreviewKind::Video,
+            image_is_fallback: false,
+            thumbnail_small: None,
+            thumbnail_large: None,
         })
     } else {
         // Last resort fallback
@@ -192,6 +837,12 @@ async fn fetch_youtube_preview(client: &Client, url: &Url) -> Result<LinkPreview
             description: None,
             image: Some(format!("https://i.ytimg.com/vi/{video_id}/hqdefault.jpg")),
             favicon: Some("https://www.youtube.com/favicon.ico".to_string()),
+            site_name: Some("YouTube".to_string()),
+            language: None,
+            kind: LinkPreviewKind::Video,
+            image_is_fallback: false,
+            thumbnail_small: None,
+            thumbnail_large: None,
         })
     }
 }
@@ -214,3 +865,236 @@ fn extract_youtube_video_id(url: &Url) -> Result<String> {
 
     Ok(video_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{response::Redirect, routing::get, Router};
+
+    fn blocked(ip: &str) -> bool {
+        is_blocked_ip(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn blocks_internal_ipv4_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.0.1",
+            "169.254.169.254",
+            "0.1.2.3",
+            "100.64.0.1",
+            "198.18.0.1",
+            "198.19.255.255",
+            "224.0.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+        ] {
+            assert!(blocked(ip), "{ip} should be blocked");
+        }
+        for ip in ["93.184.216.34", "198.20.0.1", "8.8.8.8"] {
+            assert!(!blocked(ip), "{ip} should be allowed");
+        }
+    }
+
+    #[test]
+    fn blocks_internal_ipv6_ranges() {
+        for ip in [
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::7f00:1",
+            "64:ff9b::169.254.169.254",
+        ] {
+            assert!(blocked(ip), "{ip} should be blocked");
+        }
+        for ip in [
+            "2606:4700::1111",
+            "64:ff9b::8.8.8.8",
+            "::ffff:93.184.216.34",
+        ] {
+            assert!(!blocked(ip), "{ip} should be allowed");
+        }
+    }
+
+    #[test]
+    fn rejects_localhost_urls() {
+        for url in [
+            "http://localhost/",
+            "http://api.localhost:8080/",
+            "http://[::1]/",
+        ] {
+            assert!(matches!(
+                check_host(&Url::parse(url).unwrap()),
+                Err(LinkPreviewError::BlockedHost(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn resolver_refuses_names_pointing_at_loopback() {
+        let result = PublicOnlyResolver
+            .resolve("localhost".parse().unwrap())
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// Serves `/loop`, which redirects to itself, and `/internal`, which redirects to a
+    /// loopback address. The test client reaches it as `public.test`, so only the
+    /// redirect policy stands between it and the loopback target.
+    async fn redirecting_server() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let port = addr.port();
+        let app = Router::new()
+            .route(
+                "/loop",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://public.test:{port}/loop"))
+                }),
+            )
+            .route(
+                "/internal",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://127.0.0.1:{port}/secret"))
+                }),
+            )
+            .route("/secret", get(|| async { "internal" }));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    async fn fetch_through_policy(addr: SocketAddr, path: &str) -> LinkPreviewError {
+        let client = Client::builder()
+            .redirect(redirect_policy())
+            .resolve("public.test", addr)
+            .build()
+            .unwrap();
+        let url = format!("http://public.test:{}{path}", addr.port());
+        let error = client.get(url).send().await.unwrap_err();
+        LinkPreviewError::from_anyhow(error.into())
+    }
+
+    #[tokio::test]
+    async fn stops_following_a_redirect_loop() {
+        let addr = redirecting_server().await;
+        assert!(matches!(
+            fetch_through_policy(addr, "/loop").await,
+            LinkPreviewError::TooManyRedirects(MAX_REDIRECTS)
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_to_follow_a_redirect_to_loopback() {
+        let addr = redirecting_server().await;
+        assert!(matches!(
+            fetch_through_policy(addr, "/internal").await,
+            LinkPreviewError::BlockedHost(host) if host == "127.0.0.1"
+        ));
+    }
+
+    /// A Latin-1 page: `é` and `è` are the single bytes 0xE9 and 0xE8
+    const LATIN1_PAGE: &[u8] =
+        b"<html><head><meta charset=\"iso-8859-1\"><title>Caf\xe9 cr\xe8me</title></head></html>";
+
+    #[test]
+    fn reads_charset_from_content_type() {
+        for content_type in [
+            "text/html; charset=ISO-8859-1",
+            "text/html;charset=\"latin1\"",
+            "text/html; foo=bar; Charset='iso-8859-1'",
+        ] {
+            assert_eq!(
+                charset_from_content_type(content_type),
+                Some(encoding_rs::WINDOWS_1252),
+                "{content_type}"
+            );
+        }
+        assert_eq!(charset_from_content_type("text/html"), None);
+        assert_eq!(
+            charset_from_content_type("text/html; charset=nonsense"),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_charset_from_meta_tags() {
+        assert_eq!(meta_charset(LATIN1_PAGE), Some(encoding_rs::WINDOWS_1252));
+        let http_equiv =
+            b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=iso-8859-1\">";
+        assert_eq!(meta_charset(http_equiv), Some(encoding_rs::WINDOWS_1252));
+        assert_eq!(meta_charset(b"<title>No declaration</title>"), None);
+    }
+
+    #[test]
+    fn decodes_latin1_pages() {
+        let from_meta = decode_html(LATIN1_PAGE, Some("text/html"));
+        assert!(
+            from_meta.contains("<title>Caf\u{e9} cr\u{e8}me</title>"),
+            "{from_meta}"
+        );
+
+        let undeclared = b"<title>Caf\xe9</title>";
+        let from_header = decode_html(undeclared, Some("text/html; charset=iso-8859-1"));
+        assert!(from_header.contains("Caf\u{e9}"), "{from_header}");
+
+        // Without a declaration the page is read as UTF-8, where 0xE9 alone is invalid
+        let as_utf8 = decode_html(undeclared, None);
+        assert!(as_utf8.contains('\u{fffd}'), "{as_utf8}");
+
+        let (preview, _) =
+            parse_html_preview(&from_meta, &Url::parse("https://example.fr/").unwrap());
+        assert_eq!(preview.title.as_deref(), Some("Caf\u{e9} cr\u{e8}me"));
+    }
+
+    #[test]
+    fn discovers_oembed_endpoint_of_an_spa_shell() {
+        let html = r#"<!doctype html>
+            <html lang="en">
+            <head>
+                <title></title>
+                <link rel="icon" href="/static/icon.png">
+                <link rel="alternate" type="application/json+oembed"
+                      href="/oembed?url=https%3A%2F%2Fapp.example%2Fposts%2F42&format=json"
+                      title="Post 42">
+            </head>
+            <body><div id="root"></div><script src="/bundle.js"></script></body>
+            </html>"#;
+        let page_url = Url::parse("https://app.example/posts/42").unwrap();
+
+        let (preview, oembed_url) = parse_html_preview(html, &page_url);
+
+        assert_eq!(
+            oembed_url.map(String::from).as_deref(),
+            Some(
+                "https://app.example/oembed?url=https%3A%2F%2Fapp.example%2Fposts%2F42&format=json"
+            )
+        );
+        assert!(is_blank(&preview.title));
+        assert!(is_blank(&preview.description));
+        assert_eq!(
+            preview.favicon.as_deref(),
+            Some("https://app.example/static/icon.png")
+        );
+        assert_eq!(preview.language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn pages_without_an_oembed_link_have_no_endpoint() {
+        let html = r#"<html><head>
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            <meta property="og:title" content="Plain page">
+        </head></html>"#;
+        let page_url = Url::parse("https://blog.example/").unwrap();
+
+        let (preview, oembed_url) = parse_html_preview(html, &page_url);
+
+        assert_eq!(oembed_url, None);
+        assert_eq!(preview.title.as_deref(), Some("Plain page"));
+    }
+}
@@ -0,0 +1,59 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders a markdown link description to HTML that is safe to embed in a page
+///
+/// Descriptions are user input, so the HTML goes through ammonia's allowlist: scripts,
+/// styles, event handler attributes and `javascript:` URLs are removed, and links get
+/// `rel="noopener noreferrer"`.
+pub fn render_markdown(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS;
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+    ammonia::clean(&unsafe_html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_ordinary_markdown() {
+        let html = render_markdown("**Rust** [book](https://doc.rust-lang.org/book/)");
+        assert!(html.contains("<strong>Rust</strong>"), "{html}");
+        assert!(
+            html.contains(
+                r#"<a href="https://doc.rust-lang.org/book/" rel="noopener noreferrer">book</a>"#
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn strips_script_tags() {
+        let html = render_markdown("Hello <script>alert('xss')</script> world");
+        assert!(!html.contains("<script"), "{html}");
+        assert!(!html.contains("alert"), "{html}");
+        assert!(html.contains("Hello"), "{html}");
+    }
+
+    #[test]
+    fn strips_event_handler_attributes() {
+        let html = render_markdown(r#"<img src="https://example.com/a.png" onerror="alert(1)">"#);
+        assert!(!html.contains("onerror"), "{html}");
+        assert!(!html.contains("alert"), "{html}");
+    }
+
+    #[test]
+    fn drops_javascript_urls() {
+        for markdown in [
+            "[click me](javascript:alert(1))",
+            r#"<a href="javascript:alert(1)">click me</a>"#,
+            "[click me](JaVaScRiPt:alert(1))",
+        ] {
+            let html = render_markdown(markdown);
+            assert!(!html.to_lowercase().contains("javascript:"), "{html}");
+            assert!(html.contains("click me"), "{html}");
+        }
+    }
+}
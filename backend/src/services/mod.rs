@@ -1,3 +1,16 @@
+pub mod analytics;
 pub mod auth;
+pub mod bookmarks;
+pub mod click_buffer;
 pub mod email;
+pub mod feed;
+pub mod link_health;
 pub mod link_preview;
+pub mod markdown;
+pub mod preview_image;
+pub mod preview_jobs;
+pub mod qr;
+pub mod robots;
+pub mod undo;
+pub mod url;
+pub mod webhooks;
@@ -0,0 +1,217 @@
+use crate::database::models::{LinkThumbnail, ThumbnailVariant};
+use crate::services::link_preview::{check_host, redirect_policy, PublicOnlyResolver};
+use axum::body::Bytes;
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader, Limits};
+use moka::future::Cache;
+use reqwest::{header, Client};
+use std::{
+    io::Cursor,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use thiserror::Error;
+use url::Url;
+
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024; // 5 MiB
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Total size of cached image bodies
+const CACHE_CAPACITY_BYTES: u64 = 64 * 1024 * 1024;
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// Larger images are not decoded for thumbnails, guarding against decompression bombs
+const MAX_THUMBNAIL_SOURCE_DIMENSION: u32 = 8192;
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static IMAGE_CACHE: OnceLock<Cache<String, ProxiedImage>> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum PreviewImageError {
+    #[error("Preview image URL is invalid: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("Refusing to fetch preview image from private or loopback host {0}")]
+    BlockedHost(String),
+    #[error("Preview image host responded with content type `{0}`, not an image")]
+    NotAnImage(String),
+    #[error("Preview image is larger than {MAX_IMAGE_BYTES} bytes")]
+    TooLarge,
+    #[error("Failed to fetch preview image: {0}")]
+    Fetch(#[from] reqwest::Error),
+    #[error("Failed to scale preview image: {0}")]
+    Scale(#[from] image::ImageError),
+}
+
+/// An image body along with its content type
+#[derive(Debug, Clone)]
+pub struct ProxiedImage {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// Client that never sends a Referer and never connects to internal addresses
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .user_agent("LinkSphere-ImageProxy/1.0")
+            .timeout(FETCH_TIMEOUT)
+            .referer(false)
+            .redirect(redirect_policy())
+            .dns_resolver(Arc::new(PublicOnlyResolver))
+            .build()
+            .expect("image proxy HTTP client configuration is valid")
+    })
+}
+
+fn image_cache() -> &'static Cache<String, ProxiedImage> {
+    IMAGE_CACHE.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(CACHE_CAPACITY_BYTES)
+            .weigher(|_url: &String, image: &ProxiedImage| {
+                u32::try_from(image.bytes.len()).unwrap_or(u32::MAX)
+            })
+            .time_to_live(CACHE_TTL)
+            .build()
+    })
+}
+
+/// Returns a preview image, downloading it only when it isn't cached yet
+pub async fn get_preview_image(url: &str) -> Result<ProxiedImage, PreviewImageError> {
+    let cache = image_cache();
+    if let Some(image) = cache.get(url).await {
+        return Ok(image);
+    }
+
+    let image = fetch_image(url).await?;
+    cache.insert(url.to_string(), image.clone()).await;
+    Ok(image)
+}
+
+async fn fetch_image(url: &str) -> Result<ProxiedImage, PreviewImageError> {
+    let parsed = Url::parse(url)?;
+    if check_host(&parsed).is_err() {
+        return Err(PreviewImageError::BlockedHost(
+            parsed.host_str().unwrap_or_default().to_string(),
+        ));
+    }
+
+    let mut response = client().get(parsed).send().await?.error_for_status()?;
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    if !content_type
+        .trim()
+        .to_ascii_lowercase()
+        .starts_with("image/")
+    {
+        return Err(PreviewImageError::NotAnImage(content_type));
+    }
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_IMAGE_BYTES as u64)
+    {
+        return Err(PreviewImageError::TooLarge);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_IMAGE_BYTES {
+            return Err(PreviewImageError::TooLarge);
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(ProxiedImage {
+        content_type,
+        bytes: Bytes::from(body),
+    })
+}
+
+/// Downloads a preview image and scales it down to each [`ThumbnailVariant`] width
+///
+/// The download goes through the proxy cache, so serving the original right after
+/// doesn't fetch it again. Variants at least as wide as the image are skipped, since
+/// the original already fits; the proxy serves it in their place.
+pub async fn build_thumbnails(url: &str) -> Result<Vec<LinkThumbnail>, PreviewImageError> {
+    let image = get_preview_image(url).await?;
+    tokio::task::spawn_blocking(move || scale_image(&image.bytes))
+        .await
+        .expect("thumbnail scaling doesn't panic")
+}
+
+fn scale_image(bytes: &[u8]) -> Result<Vec<LinkThumbnail>, PreviewImageError> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_THUMBNAIL_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_THUMBNAIL_SOURCE_DIMENSION);
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)?;
+    reader.limits(limits);
+    let source = reader.decode()?;
+
+    ThumbnailVariant::ALL
+        .into_iter()
+        .filter(|variant| variant.width() < source.width())
+        .map(|variant| {
+            Ok(LinkThumbnail {
+                variant,
+                content_type: "image/png".to_string(),
+                data: encode_png(&scale_to_width(&source, variant.width()))?,
+            })
+        })
+        .collect()
+}
+
+/// Scales `image` down to `width`, keeping its aspect ratio
+fn scale_to_width(image: &DynamicImage, width: u32) -> DynamicImage {
+    let height = u64::from(image.height()) * u64::from(width) / u64::from(image.width());
+    let height = u32::try_from(height).unwrap_or(u32::MAX).max(1);
+    image.resize_exact(width, height, FilterType::CatmullRom)
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, image::ImageError> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    /// A 640x320 image encoded as `format`, wide enough for the small variant only
+    fn encoded(format: ImageFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(640, 320, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        }));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn scales_common_web_formats() {
+        for format in [
+            ImageFormat::Png,
+            ImageFormat::Jpeg,
+            ImageFormat::WebP,
+            ImageFormat::Gif,
+        ] {
+            let thumbnails = scale_image(&encoded(format))
+                .unwrap_or_else(|e| panic!("{format:?} wasn't scaled: {e}"));
+
+            assert_eq!(thumbnails.len(), 1, "{format:?}");
+            let small = &thumbnails[0];
+            assert_eq!(small.variant, ThumbnailVariant::Small);
+            assert_eq!(small.content_type, "image/png");
+            let scaled =
+                image::load_from_memory_with_format(&small.data, ImageFormat::Png).unwrap();
+            assert_eq!((scaled.width(), scaled.height()), (320, 160), "{format:?}");
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    database::{
+        models::{LinkPreview, LinkThumbnail, PreviewValidators, ThumbnailVariant},
+        queries::{
+            claim_preview_jobs, complete_preview_job, enqueue_preview_job, fail_preview_job,
+            requeue_running_preview_jobs, update_link_preview, PreviewJob,
+        },
+        LinkCache, PgPool,
+    },
+    services::{
+        link_preview::{
+            fetch_link_preview, is_transient_error, PreviewFetch, INITIAL_RETRY_DELAY_MS,
+            MAX_RETRY_ATTEMPTS,
+        },
+        preview_image::build_thumbnails,
+    },
+};
+use chrono::Utc;
+use futures_util::future::join_all;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Notify;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Jobs claimed per round; the fetches themselves are capped by the preview semaphore
+const CLAIM_BATCH_SIZE: i64 = 20;
+/// How often the worker checks for due retries when nothing wakes it up
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a claimed job may run before another worker assumes it was abandoned; far
+/// longer than a preview fetch plus its thumbnails can take
+const JOB_LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// Handle for queueing preview fetches and waking up the worker
+#[derive(Clone, Default)]
+pub struct PreviewQueue {
+    wake: Arc<Notify>,
+}
+
+impl PreviewQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a preview fetch for a link
+    ///
+    /// Failing to queue only costs the link its preview, so errors are logged, not returned.
+    pub async fn enqueue(&self, pool: &PgPool, link_id: Uuid) {
+        match enqueue_preview_job(pool, link_id).await {
+            Ok(()) => self.wake.notify_one(),
+            Err(e) => tracing::warn!(link_id = %link_id, "Failed to queue preview fetch: {e}"),
+        }
+    }
+}
+
+/// Starts the background worker that processes queued preview fetches
+///
+/// The worker runs on `tasks` and stops claiming jobs once `shutdown` is cancelled, so
+/// graceful shutdown waits for the current batch. Unfinished jobs stay in the table and
+/// are picked up again, by this or another worker, once their lease runs out.
+pub fn spawn_preview_worker(
+    pool: PgPool,
+    cache: LinkCache,
+    queue: PreviewQueue,
+    tasks: &TaskTracker,
+    shutdown: CancellationToken,
+) {
+    tasks.spawn(async move {
+        requeue_abandoned_jobs(&pool).await;
+
+        while !shutdown.is_cancelled() {
+            let jobs = match claim_preview_jobs(&pool, CLAIM_BATCH_SIZE).await {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    tracing::warn!("Failed to claim preview jobs: {e}");
+                    Vec::new()
+                }
+            };
+
+            if jobs.is_empty() {
+                tokio::select! {
+                    _ = queue.wake.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => requeue_abandoned_jobs(&pool).await,
+                    _ = shutdown.cancelled() => {}
+                }
+                continue;
+            }
+
+            join_all(jobs.into_iter().map(|job| {
+                let span = tracing::info_span!("preview_job", link_id = %job.link_id);
+                run_job(&pool, &cache, job).instrument(span)
+            }))
+            .await;
+        }
+    });
+}
+
+/// Requeues jobs whose worker stopped, or was killed, without finishing them
+///
+/// Jobs still inside their lease are left to the worker running them.
+async fn requeue_abandoned_jobs(pool: &PgPool) {
+    let started_before = Utc::now() - JOB_LEASE;
+    match requeue_running_preview_jobs(pool, started_before).await {
+        Ok(0) => {}
+        Ok(requeued) => tracing::info!("Requeued {requeued} abandoned preview jobs"),
+        Err(e) => tracing::warn!("Failed to requeue abandoned preview jobs: {e}"),
+    }
+}
+
+async fn run_job(pool: &PgPool, cache: &LinkCache, job: PreviewJob) {
+    // Jobs run for new or changed URLs, so there is no earlier copy to validate
+    let result = match fetch_link_preview(&job.url, &PreviewValidators::default()).await {
+        Ok(PreviewFetch::Fetched {
+            mut preview,
+            validators,
+        }) => {
+            let thumbnails = add_thumbnails(job.link_id, &mut preview).await;
+            match update_link_preview(pool, job.link_id, Some(&*preview), &validators, &thumbnails)
+                .await
+            {
+                Ok(_) => {
+                    cache.invalidate(job.link_id).await;
+                    complete_preview_job(pool, job.link_id).await
+                }
+                Err(e) => fail_preview_job(pool, job.link_id, &e.to_string(), None).await,
+            }
+        }
+        Ok(PreviewFetch::NotModified) => complete_preview_job(pool, job.link_id).await,
+        Err(e) => {
+            // Same backoff as inline retries: 1s, 2s, 4s
+            let retry_at = (is_transient_error(&e) && job.attempts <= MAX_RETRY_ATTEMPTS as i32)
+                .then(|| {
+                    let exponent = u32::try_from(job.attempts - 1).unwrap_or_default();
+                    let delay = Duration::from_millis(INITIAL_RETRY_DELAY_MS * 2u64.pow(exponent));
+                    Utc::now() + delay
+                });
+            tracing::warn!(
+                url = %job.url,
+                attempt = job.attempts,
+                will_retry = retry_at.is_some(),
+                "Failed to fetch link preview: {e:#}"
+            );
+            let result = fail_preview_job(pool, job.link_id, &format!("{e:#}"), retry_at).await;
+            if retry_at.is_none() {
+                // The link's preview status changed to failed
+                cache.invalidate(job.link_id).await;
+            }
+            result
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to update preview job: {e}");
+    }
+}
+
+/// Scales a freshly fetched preview's image down to thumbnails and points the preview's
+/// `thumbnail_small` and `thumbnail_large` at the link's preview image endpoint
+///
+/// The endpoint serves the original image when a variant wasn't made, so the fields are
+/// set even when scaling is skipped or fails; failures are only logged. The deployment's
+/// default image is shared by many links and isn't scaled for each of them.
+pub async fn add_thumbnails(link_id: Uuid, preview: &mut LinkPreview) -> Vec<LinkThumbnail> {
+    let Some(image) = preview.image.as_deref() else {
+        return Vec::new();
+    };
+
+    let thumbnails = if preview.image_is_fallback {
+        Vec::new()
+    } else {
+        build_thumbnails(image).await.unwrap_or_else(|e| {
+            tracing::warn!(link_id = %link_id, "Failed to build preview thumbnails: {e}");
+            Vec::new()
+        })
+    };
+
+    let thumbnail_url = |variant: ThumbnailVariant| {
+        format!(
+            "/api/links/{link_id}/preview-image?variant={}",
+            variant.as_str()
+        )
+    };
+    preview.thumbnail_small = Some(thumbnail_url(ThumbnailVariant::Small));
+    preview.thumbnail_large = Some(thumbnail_url(ThumbnailVariant::Large));
+    thumbnails
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use image::{ImageFormat, Luma};
+use qrcode::QrCode;
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Mutex, OnceLock},
+};
+use uuid::Uuid;
+
+pub const DEFAULT_QR_SIZE: u32 = 256;
+pub const MIN_QR_SIZE: u32 = 64;
+pub const MAX_QR_SIZE: u32 = 1024;
+const MAX_CACHED_CODES: usize = 1024;
+
+/// Rendered PNGs keyed by (link_id, size), stored with the URL they encode
+type QrCache = HashMap<(Uuid, u32), (String, Vec<u8>)>;
+
+static QR_CACHE: OnceLock<Mutex<QrCache>> = OnceLock::new();
+
+/// Renders `data` as a square PNG QR code of roughly `size` pixels
+pub fn render_qr_png(data: &str, size: u32) -> Result<Vec<u8>> {
+    let code = QrCode::new(data.as_bytes())?;
+    let image = code
+        .render::<Luma<u8>>()
+        .min_dimensions(size, size)
+        .max_dimensions(size, size)
+        .build();
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Returns the QR code PNG for a link, rendering it only if the cached copy is
+/// missing or was generated for a different URL
+pub fn link_qr_png(link_id: Uuid, url: &str, size: u32) -> Result<Vec<u8>> {
+    let cache = QR_CACHE.get_or_init(Default::default);
+
+    if let Some((cached_url, png)) = cache.lock().unwrap().get(&(link_id, size)) {
+        if cached_url == url {
+            return Ok(png.clone());
+        }
+    }
+
+    let png = render_qr_png(url, size)?;
+
+    let mut cache = cache.lock().unwrap();
+    if cache.len() >= MAX_CACHED_CODES {
+        cache.clear();
+    }
+    cache.insert((link_id, size), (url.to_string(), png.clone()));
+
+    Ok(png)
+}
@@ -0,0 +1,303 @@
+use crate::services::link_preview::read_body_limited;
+use moka::future::Cache;
+use reqwest::Client;
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use url::Url;
+
+/// How long a host's robots.txt is trusted before it is fetched again
+const ROBOTS_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Upper bound on the number of hosts whose robots.txt is cached
+const ROBOTS_CACHE_CAPACITY: u64 = 10_000;
+/// Crawlers must read at least 500 KiB of a robots.txt; the rest is ignored
+const MAX_ROBOTS_BYTES: usize = 500 * 1024;
+
+static ROBOTS_CACHE: OnceLock<Cache<String, Arc<RobotsRules>>> = OnceLock::new();
+
+/// The `Allow` and `Disallow` rules of a robots.txt that apply to one crawler
+#[derive(Debug, Default)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+impl RobotsRules {
+    /// Rules forbidding every path, for a robots.txt that is unreachable
+    pub fn disallow_all() -> Self {
+        Self {
+            rules: vec![Rule {
+                allow: false,
+                pattern: "/".to_string(),
+            }],
+        }
+    }
+
+    /// Reads the rules for `user_agent` from a robots.txt body
+    ///
+    /// Groups naming the crawler's product token (the part of the User-Agent before the
+    /// first `/`) win over the `*` group; several matching groups are merged, as RFC 9309
+    /// asks. Lines that can't be parsed are skipped.
+    pub fn parse(body: &str, user_agent: &str) -> Self {
+        let token = product_token(user_agent);
+        let mut own = Vec::new();
+        let mut any = Vec::new();
+        let mut named_in_group = false;
+        let mut any_in_group = false;
+        let mut own_group_seen = false;
+        // A user-agent line after rules starts a new group rather than extending the last
+        let mut in_rules = false;
+
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        named_in_group = false;
+                        any_in_group = false;
+                        in_rules = false;
+                    }
+                    if value == "*" {
+                        any_in_group = true;
+                    } else if value.eq_ignore_ascii_case(token) {
+                        named_in_group = true;
+                        own_group_seen = true;
+                    }
+                }
+                key @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty `Disallow:` allows everything, which is already the default
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = || Rule {
+                        allow: key == "allow",
+                        pattern: value.to_string(),
+                    };
+                    if named_in_group {
+                        own.push(rule());
+                    }
+                    if any_in_group {
+                        any.push(rule());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            rules: if own_group_seen { own } else { any },
+        }
+    }
+
+    /// Whether a path, including its query string, may be fetched
+    ///
+    /// The rule with the longest matching pattern decides, and `Allow` wins a tie. Paths
+    /// no rule matches are allowed.
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| pattern_matches(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// The crawler name robots.txt groups are matched against, such as `LinkSphereBot`
+fn product_token(user_agent: &str) -> &str {
+    user_agent
+        .split(|c: char| c == '/' || c.is_whitespace())
+        .next()
+        .unwrap_or_default()
+}
+
+/// Matches a robots.txt path pattern, where `*` stands for any run of characters and a
+/// trailing `$` anchors the pattern to the end of the path
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    if anchored {
+        rest.ends_with(last)
+    } else {
+        rest.contains(last)
+    }
+}
+
+fn robots_cache() -> &'static Cache<String, Arc<RobotsRules>> {
+    ROBOTS_CACHE.get_or_init(|| {
+        Cache::builder()
+            .max_capacity(ROBOTS_CACHE_CAPACITY)
+            .time_to_live(ROBOTS_CACHE_TTL)
+            .build()
+    })
+}
+
+/// Whether the host's robots.txt lets `user_agent` fetch `url`
+///
+/// Each host's robots.txt is fetched once and cached for a few minutes. Following RFC 9309
+/// §2.3.1, a missing robots.txt (a 4xx status) allows everything, while one that is
+/// unreachable (a 5xx status or a failed request) disallows everything until it is
+/// fetched again.
+pub async fn robots_allow(client: &Client, url: &Url, user_agent: &str) -> bool {
+    let origin = url.origin().ascii_serialization();
+    let rules = robots_cache()
+        .get_with(origin.clone(), fetch_robots(client, origin, user_agent))
+        .await;
+
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    rules.allows(&path)
+}
+
+async fn fetch_robots(client: &Client, origin: String, user_agent: &str) -> Arc<RobotsRules> {
+    let robots_url = format!("{origin}/robots.txt");
+    let response = match client.get(&robots_url).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) if response.status().is_client_error() => {
+            tracing::debug!(url = robots_url, status = %response.status(), "No robots.txt to honor");
+            return Arc::default();
+        }
+        Ok(response) => {
+            tracing::debug!(url = robots_url, status = %response.status(), "robots.txt is unavailable");
+            return Arc::new(RobotsRules::disallow_all());
+        }
+        Err(e) => {
+            tracing::debug!(url = robots_url, "Failed to fetch robots.txt: {e}");
+            return Arc::new(RobotsRules::disallow_all());
+        }
+    };
+
+    match read_body_limited(response, MAX_ROBOTS_BYTES).await {
+        Ok(body) => Arc::new(RobotsRules::parse(
+            &String::from_utf8_lossy(&body),
+            user_agent,
+        )),
+        Err(e) => {
+            tracing::debug!(url = robots_url, "Failed to read robots.txt: {e:#}");
+            Arc::new(RobotsRules::disallow_all())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Router};
+
+    const USER_AGENT: &str = "LinkSphereBot/1.0 (+https://linksphere.app/bot)";
+
+    const ROBOTS_TXT: &str = "\
+User-agent: *
+Disallow: /private/
+Allow: /private/press-kit
+
+User-agent: OtherBot
+Disallow: /
+";
+
+    #[test]
+    fn allows_paths_no_rule_forbids() {
+        let rules = RobotsRules::parse(ROBOTS_TXT, USER_AGENT);
+        assert!(rules.allows("/"));
+        assert!(rules.allows("/blog/post?id=1"));
+        assert!(rules.allows("/private/press-kit/logo.png"));
+    }
+
+    #[test]
+    fn disallows_matching_paths() {
+        let rules = RobotsRules::parse(ROBOTS_TXT, USER_AGENT);
+        assert!(!rules.allows("/private/"));
+        assert!(!rules.allows("/private/notes.html"));
+        assert!(!RobotsRules::parse(ROBOTS_TXT, "OtherBot/2.0").allows("/blog"));
+    }
+
+    #[test]
+    fn disallow_all_forbids_every_path() {
+        let rules = RobotsRules::disallow_all();
+        assert!(!rules.allows("/"));
+        assert!(!rules.allows("/anything?at=all"));
+    }
+
+    /// Serves `/robots.txt` with `status`, returning the server's origin
+    async fn robots_server(status: StatusCode) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/robots.txt",
+            get(move || async move { (status, ROBOTS_TXT) }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    async fn fetched_rules(origin: String) -> Arc<RobotsRules> {
+        fetch_robots(&Client::new(), origin, USER_AGENT).await
+    }
+
+    #[tokio::test]
+    async fn honors_a_fetched_robots_txt() {
+        let rules = fetched_rules(robots_server(StatusCode::OK).await).await;
+        assert!(rules.allows("/blog"));
+        assert!(!rules.allows("/private/notes.html"));
+    }
+
+    #[tokio::test]
+    async fn missing_robots_txt_allows_everything() {
+        for status in [StatusCode::NOT_FOUND, StatusCode::FORBIDDEN] {
+            let rules = fetched_rules(robots_server(status).await).await;
+            assert!(rules.allows("/private/notes.html"), "{status}");
+        }
+    }
+
+    #[tokio::test]
+    async fn server_error_disallows_everything() {
+        for status in [
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            let rules = fetched_rules(robots_server(status).await).await;
+            assert!(!rules.allows("/"), "{status}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_host_disallows_everything() {
+        // Bind a port, then free it so nothing is listening there
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let rules = fetched_rules(origin).await;
+        assert!(!rules.allows("/"));
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::{decode, encode, errors::Error, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::env;
+use uuid::Uuid;
+
+/// How long a deleted link can be restored with the token returned by the delete
+pub const UNDO_TOKEN_TTL: TimeDelta = TimeDelta::seconds(30);
+
+/// Keeps undo tokens and login tokens from being accepted in place of each other
+const UNDO_TOKEN_AUDIENCE: &str = "linksphere:undo-delete";
+
+/// Claims of a token that restores one deleted link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UndoClaims {
+    /// The deleted link
+    pub sub: Uuid,
+    /// Who owned the link when it was deleted
+    pub owner: Uuid,
+    pub aud: String,
+    pub exp: i64,
+}
+
+/// Undo tokens are signed with the same `JWT_SECRET` as login tokens
+fn secret() -> String {
+    env::var("JWT_SECRET").unwrap_or_default()
+}
+
+/// Issues a token restoring a link its owner just deleted, and when it stops working
+pub fn create_undo_token(link_id: Uuid, owner_id: Uuid) -> (String, DateTime<Utc>) {
+    let expires_at = Utc::now() + UNDO_TOKEN_TTL;
+    let claims = UndoClaims {
+        sub: link_id,
+        owner: owner_id,
+        aud: UNDO_TOKEN_AUDIENCE.to_string(),
+        exp: expires_at.timestamp(),
+    };
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret().as_bytes()),
+    )
+    .expect("undo token claims always serialize and HMAC accepts keys of any size");
+    (token, expires_at)
+}
+
+/// Checks an undo token's signature, audience and expiry, without any grace period
+pub fn verify_undo_token(token: &str) -> Result<UndoClaims, Error> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[UNDO_TOKEN_AUDIENCE]);
+    validation.leeway = 0;
+    decode::<UndoClaims>(
+        token,
+        &DecodingKey::from_secret(secret().as_bytes()),
+        &validation,
+    )
+    .map(|data| data.claims)
+}
@@ -0,0 +1,167 @@
+use rand::{distr::Alphanumeric, Rng};
+use std::{env, sync::OnceLock};
+use thiserror::Error;
+use url::Url;
+
+/// Query parameters stripped from URLs when `URL_STRIP_PARAMS` is not set.
+/// A trailing `*` matches any parameter with that prefix.
+const DEFAULT_STRIP_PARAMS: &[&str] = &["utm_*", "fbclid", "gclid", "mc_eid"];
+
+/// Length of generated short-link slugs
+pub const SLUG_LENGTH: usize = 7;
+pub const MIN_CUSTOM_SLUG_LENGTH: usize = 3;
+pub const MAX_CUSTOM_SLUG_LENGTH: usize = 32;
+
+static STRIP_PARAMS: OnceLock<Vec<String>> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum UrlError {
+    #[error("Invalid URL: {0}")]
+    Parse(#[from] url::ParseError),
+    #[error("URL must use http or https protocol")]
+    UnsupportedScheme,
+    #[error("URL must have a host")]
+    MissingHost,
+}
+
+/// Tracking parameters to strip, configurable as a comma-separated `URL_STRIP_PARAMS`
+fn strip_params() -> &'static [String] {
+    STRIP_PARAMS.get_or_init(|| match env::var("URL_STRIP_PARAMS") {
+        Ok(value) => value
+            .split(',')
+            .map(|param| param.trim().to_lowercase())
+            .filter(|param| !param.is_empty())
+            .collect(),
+        Err(_) => DEFAULT_STRIP_PARAMS.iter().map(|p| p.to_string()).collect(),
+    })
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    strip_params()
+        .iter()
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *pattern,
+        })
+}
+
+/// The decoded name of one raw `name=value` query piece
+fn raw_param_name(piece: &str) -> String {
+    let name = piece.split('=').next().unwrap_or_default();
+    url::form_urlencoded::parse(name.as_bytes())
+        .next()
+        .map(|(name, _)| name.into_owned())
+        .unwrap_or_default()
+}
+
+/// Normalizes a user-supplied URL before it is stored
+///
+/// Lowercases the scheme and host, drops default ports, resolves `.`/`..` path
+/// segments (all handled by `Url::parse` for http/https) and removes tracking
+/// query parameters such as `utm_*` and `fbclid`.
+pub fn normalize_url(raw: &str) -> Result<String, UrlError> {
+    let mut url = Url::parse(raw.trim())?;
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(UrlError::UnsupportedScheme);
+    }
+    if url.host_str().is_none() {
+        return Err(UrlError::MissingHost);
+    }
+
+    // Work on the raw `&`-separated pieces so the parameters we keep stay byte-for-byte
+    // as the user wrote them (`?flag` stays valueless, `%20` isn't turned into `+`)
+    if let Some(query) = url.query() {
+        let pieces: Vec<&str> = query.split('&').collect();
+        let kept: Vec<&str> = pieces
+            .iter()
+            .copied()
+            .filter(|piece| !is_tracking_param(&raw_param_name(piece)))
+            .collect();
+
+        if kept.len() != pieces.len() {
+            let kept = kept.join("&");
+            url.set_query((!kept.is_empty()).then_some(kept.as_str()));
+        }
+    }
+
+    Ok(url.to_string())
+}
+
+/// Builds a comparison key used to detect duplicate URLs
+///
+/// Scheme and host are lowercased and default ports dropped (both handled by
+/// `Url::parse`), trailing slashes are stripped from the path and the fragment
+/// is ignored, so `http://Example.com:80/` and `http://example.com` collide.
+pub fn dedupe_key(raw: &str) -> Option<String> {
+    let url = Url::parse(raw.trim()).ok()?;
+    let host = url.host_str()?;
+    let port = url
+        .port()
+        .map(|port| format!(":{port}"))
+        .unwrap_or_default();
+    let path = url.path().trim_end_matches('/');
+    let query = url
+        .query()
+        .map(|query| format!("?{query}"))
+        .unwrap_or_default();
+
+    Some(format!("{}://{host}{port}{path}{query}", url.scheme()))
+}
+
+/// Generates a random base62 slug for a short link
+pub fn generate_slug() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(SLUG_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// Whether a user-chosen slug is 3–32 ASCII letters and digits
+pub fn is_valid_slug(slug: &str) -> bool {
+    (MIN_CUSTOM_SLUG_LENGTH..=MAX_CUSTOM_SLUG_LENGTH).contains(&slug.len())
+        && slug.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_tracking_params() {
+        assert_eq!(
+            normalize_url("https://Example.com/page?utm_source=x&id=7&fbclid=abc").unwrap(),
+            "https://example.com/page?id=7"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/?utm_medium=email").unwrap(),
+            "https://example.com/"
+        );
+    }
+
+    #[test]
+    fn leaves_untouched_query_as_written() {
+        assert_eq!(
+            normalize_url("https://example.com/search?flag&q=a%20b").unwrap(),
+            "https://example.com/search?flag&q=a%20b"
+        );
+    }
+
+    #[test]
+    fn keeps_remaining_params_encoded_as_written() {
+        assert_eq!(
+            normalize_url("https://example.com/?flag&utm_campaign=spring&q=a%20b").unwrap(),
+            "https://example.com/?flag&q=a%20b"
+        );
+    }
+
+    #[test]
+    fn rejects_non_http_schemes() {
+        assert!(matches!(
+            normalize_url("ftp://example.com/file"),
+            Err(UrlError::UnsupportedScheme)
+        ));
+    }
+}
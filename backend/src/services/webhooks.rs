@@ -0,0 +1,167 @@
+use crate::{
+    database::{models::Link, models::Webhook, queries::get_webhooks_for_event, PgPool},
+    services::link_preview::{PublicOnlyResolver, INITIAL_RETRY_DELAY_MS, MAX_RETRY_ATTEMPTS},
+};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{redirect, Client, StatusCode};
+use serde_json::json;
+use sha2::Sha256;
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tracing::Instrument;
+
+const DELIVERY_TIMEOUT_SECS: u64 = 10;
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Link lifecycle events that can be delivered to webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    LinkCreated,
+    LinkUpdated,
+    LinkDeleted,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 3] = [
+        WebhookEvent::LinkCreated,
+        WebhookEvent::LinkUpdated,
+        WebhookEvent::LinkDeleted,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::LinkCreated => "link.created",
+            WebhookEvent::LinkUpdated => "link.updated",
+            WebhookEvent::LinkDeleted => "link.deleted",
+        }
+    }
+
+    pub fn is_known(name: &str) -> bool {
+        Self::ALL.iter().any(|event| event.as_str() == name)
+    }
+}
+
+/// Generates a random signing secret for a new webhook
+pub fn generate_secret() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Hex-encoded HMAC-SHA256 of `body`, sent as `X-Signature: sha256=<hex>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Shared client that never follows redirects or connects to internal addresses
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .user_agent("LinkSphere-Webhooks/1.0")
+            .timeout(Duration::from_secs(DELIVERY_TIMEOUT_SECS))
+            .redirect(redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicOnlyResolver))
+            .build()
+            .expect("webhook HTTP client configuration is valid")
+    })
+}
+
+/// Notifies the link owner's webhooks about an event without blocking the caller
+///
+/// Each subscription is delivered independently; failures are only logged.
+pub fn dispatch_link_event(pool: PgPool, event: WebhookEvent, link: &Link) {
+    // Unclaimed anonymous links have nobody to notify
+    let Some(user_id) = link.user_id else {
+        return;
+    };
+    let payload = json!({
+        "event": event.as_str(),
+        "timestamp": Utc::now(),
+        "data": link,
+    });
+
+    // Deliveries keep the request's span so their logs carry its request ID
+    let dispatch = async move {
+        let webhooks = match get_webhooks_for_event(&pool, user_id, event.as_str()).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                tracing::warn!(
+                    user_id = %user_id,
+                    event = event.as_str(),
+                    "Failed to load webhooks: {e}"
+                );
+                return;
+            }
+        };
+        if webhooks.is_empty() {
+            return;
+        }
+
+        let body = Arc::new(payload.to_string());
+        for webhook in webhooks {
+            let body = Arc::clone(&body);
+            let delivery = async move {
+                if let Err(e) = deliver_with_retry(&webhook, event, &body).await {
+                    tracing::warn!(
+                        webhook_id = %webhook.id,
+                        url = %webhook.url,
+                        event = event.as_str(),
+                        "Webhook delivery failed: {e:#}"
+                    );
+                }
+            };
+            tokio::spawn(delivery.in_current_span());
+        }
+    };
+    tokio::spawn(dispatch.in_current_span());
+}
+
+/// Posts a payload to a webhook, retrying with the same backoff as preview fetches
+///
+/// Network errors, 429 and 5xx responses are retried; other 4xx responses are not.
+async fn deliver_with_retry(webhook: &Webhook, event: WebhookEvent, body: &str) -> Result<()> {
+    let signature = format!("sha256={}", sign(&webhook.secret, body.as_bytes()));
+    let mut attempt = 0;
+    loop {
+        let result = client()
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Signature", &signature)
+            .header("X-Webhook-Event", event.as_str())
+            .body(body.to_owned())
+            .send()
+            .await;
+
+        let retryable = match result {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let error = anyhow!("Endpoint responded with {status}");
+                if !(status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS) {
+                    return Err(error);
+                }
+                error
+            }
+            Err(e) => anyhow::Error::from(e),
+        };
+
+        if attempt >= MAX_RETRY_ATTEMPTS {
+            return Err(retryable.context(format!("Gave up after {} attempts", attempt + 1)));
+        }
+        let delay = Duration::from_millis(INITIAL_RETRY_DELAY_MS * 2u64.pow(attempt));
+        attempt += 1;
+        tracing::debug!(
+            webhook_id = %webhook.id,
+            attempt = attempt,
+            delay_ms = delay.as_millis(),
+            "Retrying webhook delivery: {retryable:#}"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn deleting_a_user_removes_their_links_instead_of_orphaning_them(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let admin = create_user(&pool, "admin", UserRole::Admin).await;
+    let owner = create_user(&pool, "leaving", UserRole::User).await;
+    let bystander = create_user(&pool, "staying", UserRole::User).await;
+    let doomed = create_link(&pool, owner.id, "Doomed").await;
+    create_link(&pool, owner.id, "Also doomed").await;
+    let kept = create_link(&pool, bystander.id, "Kept").await;
+
+    // Warm the cache so the deletion has to clear it
+    let uri = format!("/api/links/{doomed}");
+    let (status, _, body) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["user"]["username"], "leaving");
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::DELETE,
+            &format!("/api/admin/users/{}", owner.id),
+            Some(&admin.token()),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["deleted_links"], 2);
+
+    let (status, _, body) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+
+    // No link is left behind rendering with `"user": null`
+    let (status, _, body) = send(&app, request(Method::GET, "/api/links", None, None)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let links = body["data"].as_array().expect("Expected a list of links");
+    assert_eq!(links.len(), 1, "{body}");
+    assert_eq!(links[0]["id"], kept.to_string());
+    assert_eq!(links[0]["user"]["username"], "staying");
+    assert!(links.iter().all(|link| !link["user"].is_null()), "{body}");
+}
+
+#[sqlx::test]
+async fn deleting_a_missing_user_is_404(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let admin = create_user(&pool, "admin", UserRole::Admin).await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::DELETE,
+            &format!("/api/admin/users/{}", uuid::Uuid::new_v4()),
+            Some(&admin.token()),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{body}");
+    assert_eq!(body["code"], "NOT_FOUND");
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use axum::{
+    extract::ConnectInfo,
+    http::{Method, StatusCode},
+};
+use backend::{
+    database::{queries::BufferedClick, LinkCache},
+    models::auth::UserRole,
+    services::click_buffer::{spawn_click_flusher, ClickBuffer},
+};
+use chrono::Utc;
+use common::{create_link, create_user, request, send, test_app};
+use futures_util::future::join_all;
+use sqlx::PgPool;
+use std::{net::SocketAddr, time::Duration};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+async fn stored_click_count(pool: &PgPool, link_id: Uuid) -> i32 {
+    sqlx::query_scalar("SELECT click_count FROM links WHERE id = $1")
+        .bind(link_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn click_on_unknown_link_is_404(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let user = create_user(&pool, "clicker", UserRole::User).await;
+
+    let uri = format!("/api/links/{}/click", Uuid::new_v4());
+    let (status, _, body) =
+        send(&app, request(Method::POST, &uri, Some(&user.token()), None)).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "LINK_NOT_FOUND");
+}
+
+#[sqlx::test]
+async fn click_on_link_increments_its_count(pool: PgPool) {
+    let (app, link_state) = test_app(&pool);
+    let user = create_user(&pool, "clicker", UserRole::User).await;
+    let link_id = create_link(&pool, user.id, "Clicked").await;
+
+    let uri = format!("/api/links/{link_id}/click");
+    let (status, _, body) =
+        send(&app, request(Method::POST, &uri, Some(&user.token()), None)).await;
+
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["counted"], true);
+    assert_eq!(body["data"]["click_count"], 1);
+
+    // The click is buffered; stopping the flusher writes it
+    let shutdown = CancellationToken::new();
+    let flusher = spawn_click_flusher(
+        pool.clone(),
+        link_state.cache.clone(),
+        link_state.clicks.buffer.clone(),
+        shutdown.clone(),
+    );
+    shutdown.cancel();
+    flusher.await.unwrap();
+
+    assert_eq!(stored_click_count(&pool, link_id).await, 1);
+}
+
+#[sqlx::test]
+async fn rapid_repeat_click_from_one_visitor_is_not_counted(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let user = create_user(&pool, "clicker", UserRole::User).await;
+    let link_id = create_link(&pool, user.id, "Clicked twice").await;
+    let uri = format!("/api/links/{link_id}/click");
+
+    let (status, _, first) =
+        send(&app, request(Method::POST, &uri, Some(&user.token()), None)).await;
+    assert_eq!(status, StatusCode::OK, "{first}");
+    assert_eq!(first["data"]["counted"], true);
+    assert_eq!(first["data"]["click_count"], 1);
+
+    let (status, _, second) =
+        send(&app, request(Method::POST, &uri, Some(&user.token()), None)).await;
+    assert_eq!(status, StatusCode::OK, "{second}");
+    assert_eq!(second["data"]["counted"], false);
+    assert_eq!(second["data"]["click_count"], 1);
+
+    // Another visitor's click still counts
+    let mut other_visitor = request(Method::POST, &uri, Some(&user.token()), None);
+    other_visitor
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from(([198, 51, 100, 20], 40000))));
+    let (status, _, third) = send(&app, other_visitor).await;
+    assert_eq!(status, StatusCode::OK, "{third}");
+    assert_eq!(third["data"]["counted"], true);
+    assert_eq!(third["data"]["click_count"], 2);
+}
+
+#[sqlx::test]
+async fn buffered_clicks_are_written_on_graceful_shutdown(pool: PgPool) {
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let first = create_link(&pool, owner.id, "First").await;
+    let second = create_link(&pool, owner.id, "Second").await;
+    // Long enough that only the shutdown flush can write the clicks
+    let buffer = ClickBuffer::new(Duration::from_secs(3600), 1000);
+    for (link_id, visitor) in [(first, "a"), (first, "b"), (second, "a")] {
+        buffer.push(BufferedClick {
+            link_id,
+            referrer: Some("https://news.example/".to_string()),
+            user_agent: None,
+            ip_hash: visitor.to_string(),
+            clicked_at: Utc::now(),
+        });
+    }
+
+    let shutdown = CancellationToken::new();
+    let flusher = spawn_click_flusher(
+        pool.clone(),
+        LinkCache::new(),
+        buffer.clone(),
+        shutdown.clone(),
+    );
+    tokio::task::yield_now().await;
+    assert_eq!(stored_click_count(&pool, first).await, 0);
+
+    shutdown.cancel();
+    flusher.await.unwrap();
+
+    assert_eq!(stored_click_count(&pool, first).await, 2);
+    assert_eq!(stored_click_count(&pool, second).await, 1);
+    let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM link_clicks")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(events, 3);
+    assert_eq!(buffer.waiting(first), 0);
+}
+
+#[sqlx::test]
+async fn concurrent_clicks_keep_the_count_equal_to_the_events(pool: PgPool) {
+    const VISITORS: u8 = 50;
+    let (app, link_state) = test_app(&pool);
+    let user = create_user(&pool, "clicker", UserRole::User).await;
+    let link_id = create_link(&pool, user.id, "Popular").await;
+    let uri = format!("/api/links/{link_id}/click");
+    let token = user.token();
+
+    // Each click comes from its own address, so none is a repeat
+    let clicks = (1..=VISITORS).map(|visitor| {
+        let mut click = request(Method::POST, &uri, Some(&token), None);
+        click.extensions_mut().insert(ConnectInfo(SocketAddr::from((
+            [198, 51, 100, visitor],
+            40000,
+        ))));
+        send(&app, click)
+    });
+    for (status, _, body) in join_all(clicks).await {
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["data"]["counted"], true, "{body}");
+    }
+
+    let shutdown = CancellationToken::new();
+    let flusher = spawn_click_flusher(
+        pool.clone(),
+        link_state.cache.clone(),
+        link_state.clicks.buffer.clone(),
+        shutdown.clone(),
+    );
+    shutdown.cancel();
+    flusher.await.unwrap();
+
+    let events: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM link_clicks WHERE link_id = $1 AND NOT is_bot")
+            .bind(link_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let click_count = stored_click_count(&pool, link_id).await;
+    assert_eq!(i64::from(click_count), events);
+    assert_eq!(click_count, i32::from(VISITORS));
+}
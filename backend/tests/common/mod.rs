@@ -0,0 +1,149 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test gets its own database from `#[sqlx::test]`, so the helpers insert rows
+//! directly and build the same router the server runs.
+#![allow(dead_code)]
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::connect_info::MockConnectInfo,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use backend::{
+    models::auth::{Claims, UserRole},
+    routes::{self, LinkState},
+    services::auth::{jwt_issuer, AuthService},
+};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+pub const JWT_SECRET: &str = "integration-test-secret";
+
+/// Address the mocked connection appears to come from
+pub const CLIENT_ADDR: ([u8; 4], u16) = ([203, 0, 113, 10], 50000);
+
+pub struct TestUser {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub role: UserRole,
+}
+
+impl TestUser {
+    /// A signed access token for this user, valid for an hour
+    pub fn token(&self) -> String {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: self.id,
+            exp: (now + Duration::hours(1)).timestamp(),
+            nbf: now.timestamp(),
+            iss: jwt_issuer(),
+            email: self.email.clone(),
+            username: self.username.clone(),
+            role: self.role,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+        )
+        .expect("Failed to sign test token")
+    }
+}
+
+/// The API router with its link state, accepting requests as if from [`CLIENT_ADDR`]
+pub fn test_app(pool: &PgPool) -> (Router, LinkState) {
+    let link_state = LinkState::new(pool.clone());
+    let auth_service = AuthService::new(pool.clone(), JWT_SECRET.to_string());
+    let app = routes::create_api_router(pool.clone(), auth_service, link_state.clone())
+        .layer(MockConnectInfo(SocketAddr::from(CLIENT_ADDR)));
+    (app, link_state)
+}
+
+/// Inserts an active, verified user
+pub async fn create_user(pool: &PgPool, username: &str, role: UserRole) -> TestUser {
+    let email = format!("{username}@example.com");
+    let id = sqlx::query_scalar(
+        "INSERT INTO users (email, username, password_hash, gender, status, is_verified, role)
+         VALUES ($1, $2, 'not-a-real-hash', 'other', 'active', true, $3)
+         RETURNING id",
+    )
+    .bind(&email)
+    .bind(username)
+    .bind(role)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to insert test user");
+
+    TestUser {
+        id,
+        username: username.to_string(),
+        email,
+        role,
+    }
+}
+
+/// Inserts a public link owned by `owner`
+pub async fn create_link(pool: &PgPool, owner: Uuid, title: &str) -> Uuid {
+    let slug = Uuid::new_v4().simple().to_string()[..10].to_string();
+    let url = format!("https://example.com/{slug}");
+    sqlx::query_scalar(
+        "INSERT INTO links (user_id, url, original_url, title, description, slug, visibility)
+         VALUES ($1, $2, $2, $3, '', $4, 'public')
+         RETURNING id",
+    )
+    .bind(owner)
+    .bind(&url)
+    .bind(title)
+    .bind(&slug)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to insert test link")
+}
+
+/// Builds a request, with a bearer token and a JSON body when given
+pub fn request(
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .expect("Failed to build test request")
+}
+
+/// Sends a request and returns the status, headers and JSON body (`Null` when empty)
+pub async fn send(app: &Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+    let response = app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Router is infallible");
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("Failed to read response body");
+    let json = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()))
+    };
+    (status, headers, json)
+}
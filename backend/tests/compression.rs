@@ -0,0 +1,89 @@
+mod common;
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use backend::{middleware::compression::compression_layer, models::auth::UserRole};
+use common::{create_link, create_user, test_app};
+use sqlx::PgPool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn compressed_app(pool: &PgPool) -> Router {
+    let (app, _) = test_app(pool);
+    app.layer(compression_layer())
+}
+
+/// Sends a GET accepting gzip or brotli, returning the raw, possibly encoded body
+async fn get(app: &Router, uri: &str) -> (StatusCode, HeaderMap, Bytes) {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header(header::ACCEPT_ENCODING, "gzip, br")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, headers, body)
+}
+
+#[sqlx::test]
+async fn large_list_responses_are_compressed(pool: PgPool) {
+    let app = compressed_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    for n in 0..10 {
+        create_link(
+            &pool,
+            owner.id,
+            &format!("A fairly descriptive link title {n}"),
+        )
+        .await;
+    }
+
+    let (status, headers, _) = get(&app, "/api/links").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        headers.contains_key(header::CONTENT_ENCODING),
+        "{headers:?}"
+    );
+}
+
+#[sqlx::test]
+async fn small_responses_are_not_compressed(pool: PgPool) {
+    let app = compressed_app(&pool);
+
+    let (status, headers, body) = get(&app, &format!("/api/links/{}", Uuid::new_v4())).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.len() < 1024, "{}", body.len());
+    assert!(
+        !headers.contains_key(header::CONTENT_ENCODING),
+        "{headers:?}"
+    );
+}
+
+#[sqlx::test]
+async fn qr_codes_are_sent_as_plain_png(pool: PgPool) {
+    let app = compressed_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Scannable").await;
+
+    let (status, headers, body) = get(&app, &format!("/api/links/{link_id}/qr?size=512")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "image/png");
+    assert!(
+        !headers.contains_key(header::CONTENT_ENCODING),
+        "{headers:?}"
+    );
+    // Large enough that only the image rule keeps it uncompressed
+    assert!(body.len() > 1024, "{}", body.len());
+    assert!(body.starts_with(PNG_SIGNATURE));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{request, send, test_app};
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::time::Duration;
+
+/// A pool for a database that isn't there; nothing connects until a query runs
+fn unreachable_pool() -> PgPool {
+    PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(500))
+        .connect_lazy("postgres://linksphere@127.0.0.1:1/linksphere")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn health_answers_without_a_database() {
+    let (app, _) = test_app(&unreachable_pool());
+
+    let (status, _, body) = send(&app, request(Method::GET, "/health", None, None)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "running");
+}
+
+#[tokio::test]
+async fn ready_reports_a_missing_database() {
+    let (app, _) = test_app(&unreachable_pool());
+
+    let (status, _, body) = send(&app, request(Method::GET, "/ready", None, None)).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "DB_UNAVAILABLE");
+}
+
+#[sqlx::test]
+async fn ready_once_the_database_answers(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+
+    let (status, _, _) = send(&app, request(Method::GET, "/ready", None, None)).await;
+
+    assert_eq!(status, StatusCode::OK);
+}
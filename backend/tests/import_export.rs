@@ -0,0 +1,165 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app, TestUser};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+const BOUNDARY: &str = "linksphere-test-boundary";
+
+fn upload(token: &str, filename: &str, contents: &str) -> Request<Body> {
+    let body = format!(
+        "--{BOUNDARY}\r\n\
+         Content-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n\
+         {contents}\r\n\
+         --{BOUNDARY}--\r\n"
+    );
+    Request::post("/api/links/import")
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn export(app: &Router, user: &TestUser) -> Vec<Value> {
+    let (status, headers, body) = send(
+        app,
+        request(Method::GET, "/api/links/export", Some(&user.token()), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+    body.as_array().unwrap().clone()
+}
+
+/// What an import is expected to carry over, in a stable order
+fn portable_fields(links: &[Value]) -> Vec<Value> {
+    let mut fields: Vec<Value> = links
+        .iter()
+        .map(|link| {
+            json!({
+                "url": link["url"],
+                "title": link["title"],
+                "description": link["description"],
+                "tags": link["tags"],
+                "visibility": link["visibility"],
+            })
+        })
+        .collect();
+    fields.sort_by_key(|link| link["url"].to_string());
+    fields
+}
+
+#[sqlx::test]
+async fn exported_links_import_into_another_account(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let alice = create_user(&pool, "alice", UserRole::User).await;
+    let bob = create_user(&pool, "bob", UserRole::User).await;
+
+    let tagged = create_link(&pool, alice.id, "Rust book").await;
+    sqlx::query(
+        "UPDATE links SET description = 'Learn Rust', tags = '{rust,books}', visibility = 'private'
+         WHERE id = $1",
+    )
+    .bind(tagged)
+    .execute(&pool)
+    .await
+    .unwrap();
+    create_link(&pool, alice.id, "Plain, public link").await;
+
+    let exported = export(&app, &alice).await;
+    assert_eq!(exported.len(), 2);
+
+    let file = Value::Array(exported.clone()).to_string();
+    let (status, _, body) = send(&app, upload(&bob.token(), "links.json", &file)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["imported"], 2);
+    assert_eq!(body["data"]["skipped"], 0);
+
+    let imported = export(&app, &bob).await;
+    assert_eq!(portable_fields(&imported), portable_fields(&exported));
+}
+
+#[sqlx::test]
+async fn reimporting_an_export_skips_saved_links(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let alice = create_user(&pool, "alice", UserRole::User).await;
+    create_link(&pool, alice.id, "Already saved").await;
+
+    let file = Value::Array(export(&app, &alice).await).to_string();
+    let (status, _, body) = send(&app, upload(&alice.token(), "links.json", &file)).await;
+
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["imported"], 0);
+    assert_eq!(body["data"]["skipped"], 1);
+}
+
+#[sqlx::test]
+async fn netscape_bookmarks_still_import(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let alice = create_user(&pool, "alice", UserRole::User).await;
+    let file = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><A HREF="https://www.rust-lang.org/">Rust</A>
+    <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+</DL><p>"#;
+
+    let (status, _, body) = send(&app, upload(&alice.token(), "bookmarks.html", file)).await;
+
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"]["imported"], 1);
+    assert_eq!(body["data"]["skipped"], 1);
+}
+
+#[sqlx::test]
+async fn malformed_export_is_rejected(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let alice = create_user(&pool, "alice", UserRole::User).await;
+
+    let (status, _, body) = send(
+        &app,
+        upload(&alice.token(), "links.json", "[{\"title\": 1}"),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_UPLOAD");
+}
+
+#[sqlx::test]
+async fn csv_export_quotes_titles_containing_commas(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let user = create_user(&pool, "exporter", UserRole::User).await;
+    create_link(&pool, user.id, "Rust, the book").await;
+
+    let mut request = request(Method::GET, "/api/links/export", Some(&user.token()), None);
+    request
+        .headers_mut()
+        .insert(header::ACCEPT, "text/csv".parse().unwrap());
+    let (status, headers, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(headers[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/csv"));
+
+    let csv = body.as_str().expect("CSV body");
+    assert!(csv.contains(",\"Rust, the book\","), "{csv}");
+
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    let headers = reader.headers().unwrap().clone();
+    assert_eq!(&headers[1], "title");
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].len(), headers.len());
+    assert_eq!(&rows[0][1], "Rust, the book");
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use backend::models::auth::UserRole;
+use common::{create_user, send, test_app};
+use sqlx::PgPool;
+
+fn post_raw(uri: &str, token: &str, body: &'static str) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn malformed_json_gets_the_error_envelope(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let user = create_user(&pool, "writer", UserRole::User).await;
+
+    let (status, headers, body) =
+        send(&app, post_raw("/api/links", &user.token(), "{bad json")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+    assert_eq!(body["success"], false, "{body}");
+    assert_eq!(body["code"], "INVALID_JSON");
+    assert!(
+        body["message"].as_str().is_some_and(|m| !m.is_empty()),
+        "{body}"
+    );
+}
+
+#[sqlx::test]
+async fn wrongly_typed_field_gets_the_error_envelope(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let user = create_user(&pool, "writer", UserRole::User).await;
+
+    let (status, _, body) = send(
+        &app,
+        post_raw(
+            "/api/links",
+            &user.token(),
+            r#"{"url": 42, "title": "Answer"}"#,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["code"], "INVALID_JSON");
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::{database::LinkCache, models::auth::UserRole};
+use common::{create_link, create_user, request, send, test_app};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn rename_in_database(pool: &PgPool, link_id: Uuid, title: &str) {
+    sqlx::query("UPDATE links SET title = $2 WHERE id = $1")
+        .bind(link_id)
+        .bind(title)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test]
+async fn second_read_is_served_from_the_cache(pool: PgPool) {
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Original").await;
+    let cache = LinkCache::new();
+
+    let first = cache.get_link_by_id(&pool, link_id).await.unwrap().unwrap();
+    assert_eq!(first.title, "Original");
+
+    // A change the cache wasn't told about stays invisible until invalidation
+    rename_in_database(&pool, link_id, "Changed behind the cache").await;
+    let cached = cache.get_link_by_id(&pool, link_id).await.unwrap().unwrap();
+    assert_eq!(cached.title, "Original");
+
+    cache.invalidate(link_id).await;
+    let fresh = cache.get_link_by_id(&pool, link_id).await.unwrap().unwrap();
+    assert_eq!(fresh.title, "Changed behind the cache");
+}
+
+#[sqlx::test]
+async fn deleted_links_are_not_cached(pool: PgPool) {
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Deleted for now").await;
+    let cache = LinkCache::new();
+    let set_deleted = |deleted: bool| {
+        sqlx::query("UPDATE links SET deleted_at = CASE WHEN $2 THEN NOW() END WHERE id = $1")
+            .bind(link_id)
+            .bind(deleted)
+            .execute(&pool)
+    };
+
+    set_deleted(true).await.unwrap();
+    assert!(cache
+        .get_link_by_id(&pool, link_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    // The miss wasn't remembered, so the restored link is found straight away
+    set_deleted(false).await.unwrap();
+    assert!(cache
+        .get_link_by_id(&pool, link_id)
+        .await
+        .unwrap()
+        .is_some());
+}
+
+#[sqlx::test]
+async fn updating_a_link_invalidates_its_cached_copy(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Before").await;
+    let uri = format!("/api/links/{link_id}");
+
+    let (_, _, body) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(body["data"]["title"], "Before");
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::PATCH,
+            &uri,
+            Some(&owner.token()),
+            Some(json!({ "title": "After" })),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (_, _, body) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(body["data"]["title"], "After");
+}
+
+#[sqlx::test]
+async fn deleting_a_link_invalidates_its_cached_copy(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Doomed").await;
+    let uri = format!("/api/links/{link_id}");
+
+    let (status, _, _) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, _) = send(
+        &app,
+        request(Method::DELETE, &uri, Some(&owner.token()), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, _) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
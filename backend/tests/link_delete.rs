@@ -0,0 +1,81 @@
+mod common;
+
+use axum::http::{header, Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn delete_without_a_token_is_401(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Mine").await;
+
+    let (status, headers, body) = send(
+        &app,
+        request(Method::DELETE, &format!("/api/links/{link_id}"), None, None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+    assert_eq!(headers[header::WWW_AUTHENTICATE], "Bearer");
+    assert_eq!(body["code"], "UNAUTHORIZED");
+}
+
+#[sqlx::test]
+async fn delete_with_a_bad_token_hides_why_it_failed(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Mine").await;
+    let tampered = format!("{}x", owner.token());
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::DELETE,
+            &format!("/api/links/{link_id}"),
+            Some(&tampered),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{body}");
+    assert_eq!(body["code"], "UNAUTHORIZED");
+    assert_eq!(body["message"], "Invalid token");
+}
+
+#[sqlx::test]
+async fn delete_by_someone_else_is_403(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let stranger = create_user(&pool, "stranger", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Mine").await;
+    let uri = format!("/api/links/{link_id}");
+
+    let (status, _, body) = send(
+        &app,
+        request(Method::DELETE, &uri, Some(&stranger.token()), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+
+    let (status, _, _) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn delete_by_the_owner_is_200(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Mine").await;
+    let uri = format!("/api/links/{link_id}");
+
+    let (status, _, body) = send(
+        &app,
+        request(Method::DELETE, &uri, Some(&owner.token()), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, _, _) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+fn get_if_none_match(uri: &str, etag: &HeaderValue) -> axum::http::Request<axum::body::Body> {
+    let mut request = request(Method::GET, uri, None, None);
+    request
+        .headers_mut()
+        .insert(header::IF_NONE_MATCH, etag.clone());
+    request
+}
+
+#[sqlx::test]
+async fn matching_etag_gets_304(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let uri = format!(
+        "/api/links/{}",
+        create_link(&pool, owner.id, "Cached").await
+    );
+
+    let (status, headers, _) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    let etag = headers[header::ETAG].clone();
+
+    let (status, headers, body) = send(&app, get_if_none_match(&uri, &etag)).await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+    assert_eq!(headers[header::ETAG], etag);
+    assert_eq!(body, Value::Null);
+}
+
+#[sqlx::test]
+async fn stale_etag_gets_200_with_new_etag(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let uri = format!(
+        "/api/links/{}",
+        create_link(&pool, owner.id, "Before").await
+    );
+
+    let (_, headers, _) = send(&app, request(Method::GET, &uri, None, None)).await;
+    let stale = headers[header::ETAG].clone();
+
+    let (status, _, _) = send(
+        &app,
+        request(
+            Method::PATCH,
+            &uri,
+            Some(&owner.token()),
+            Some(json!({ "title": "After" })),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, headers, body) = send(&app, get_if_none_match(&uri, &stale)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_ne!(headers[header::ETAG], stale);
+    assert_eq!(body["data"]["title"], "After");
+}
+
+#[sqlx::test]
+async fn wildcard_matches_any_etag(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let uri = format!("/api/links/{}", create_link(&pool, owner.id, "Any").await);
+
+    let (status, _, _) = send(
+        &app,
+        get_if_none_match(&uri, &HeaderValue::from_static("*")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_MODIFIED);
+}
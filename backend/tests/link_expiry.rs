@@ -0,0 +1,171 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use chrono::{DateTime, Duration, Utc};
+use common::{create_link, create_user, request, send, test_app};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn expire(pool: &PgPool, link_id: Uuid) {
+    sqlx::query("UPDATE links SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(link_id)
+        .execute(pool)
+        .await
+        .expect("Failed to expire link");
+}
+
+fn ids(links: &Value) -> Vec<&str> {
+    links
+        .as_array()
+        .expect("Expected a list of links")
+        .iter()
+        .map(|link| link["id"].as_str().unwrap())
+        .collect()
+}
+
+#[sqlx::test]
+async fn expired_links_are_left_out_of_batch_search_favorites_and_feed(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let live = create_link(&pool, owner.id, "Lighthouse keeping").await;
+    let expired = create_link(&pool, owner.id, "Lighthouse history").await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::POST,
+            &format!("/api/links/{expired}/favorite"),
+            Some(&owner.token()),
+            None,
+        ),
+    )
+    .await;
+    assert!(status.is_success(), "{body}");
+    expire(&pool, expired).await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::POST,
+            "/api/links/batch",
+            None,
+            Some(json!({ "ids": [live, expired] })),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(ids(&body["data"]), [live.to_string()]);
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::GET,
+            "/api/links/search?q=lighthouse",
+            Some(&owner.token()),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(ids(&body["data"]), [live.to_string()]);
+
+    let (status, _, body) = send(
+        &app,
+        request(Method::GET, "/api/favorites", Some(&owner.token()), None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert!(ids(&body["data"]).is_empty(), "{body}");
+
+    let (status, _, body) = send(
+        &app,
+        request(Method::GET, "/api/users/owner/feed.xml", None, None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let feed = body.as_str().expect("Feed is XML");
+    assert!(feed.contains("Lighthouse keeping"), "{feed}");
+    assert!(!feed.contains("Lighthouse history"), "{feed}");
+}
+
+fn put_body(expires_at: Option<DateTime<Utc>>) -> Value {
+    let mut body = json!({
+        "url": "https://example.com/updated",
+        "title": "Updated",
+        "description": "Updated description",
+    });
+    if let Some(expires_at) = expires_at {
+        body["expires_at"] = json!(expires_at);
+    }
+    body
+}
+
+async fn stored_expiry(pool: &PgPool, link_id: Uuid) -> Option<DateTime<Utc>> {
+    sqlx::query_scalar("SELECT expires_at FROM links WHERE id = $1")
+        .bind(link_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read expiry")
+}
+
+#[sqlx::test]
+async fn put_stores_a_future_expiry_and_clears_it_when_omitted(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Expiring").await;
+    let uri = format!("/api/links/{link_id}");
+    let expires_at = Utc::now() + Duration::days(7);
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::PUT,
+            &uri,
+            Some(&owner.token()),
+            Some(put_body(Some(expires_at))),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let stored = stored_expiry(&pool, link_id).await.expect("Expiry stored");
+    assert!(
+        (stored - expires_at).num_milliseconds().abs() < 1,
+        "{stored}"
+    );
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::PUT,
+            &uri,
+            Some(&owner.token()),
+            Some(put_body(None)),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(stored_expiry(&pool, link_id).await, None);
+}
+
+#[sqlx::test]
+async fn put_rejects_a_past_expiry(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Expiring").await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::PUT,
+            &format!("/api/links/{link_id}"),
+            Some(&owner.token()),
+            Some(put_body(Some(Utc::now() - Duration::hours(1)))),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{body}");
+    assert_eq!(body["code"], "INVALID_EXPIRY");
+    assert_eq!(stored_expiry(&pool, link_id).await, None);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn patching_only_visibility_keeps_the_other_fields(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Untouched").await;
+    let uri = format!("/api/links/{link_id}");
+
+    let (_, _, before) = send(&app, request(Method::GET, &uri, None, None)).await;
+    let before = &before["data"];
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::PATCH,
+            &uri,
+            Some(&owner.token()),
+            Some(json!({ "visibility": "private" })),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (status, _, after) =
+        send(&app, request(Method::GET, &uri, Some(&owner.token()), None)).await;
+    assert_eq!(status, StatusCode::OK, "{after}");
+    let after = &after["data"];
+    assert_eq!(after["visibility"], "private");
+    for field in [
+        "url",
+        "original_url",
+        "title",
+        "description",
+        "tags",
+        "slug",
+    ] {
+        assert!(!before[field].is_null(), "{field} missing");
+        assert_eq!(after[field], before[field], "{field} changed");
+    }
+
+    // The URL didn't change, so no preview fetch was queued
+    let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM preview_jobs WHERE link_id = $1")
+        .bind(link_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(queued, 0);
+
+    // Private links disappear from public listings
+    let (status, _, body) = send(&app, request(Method::GET, "/api/links", None, None)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["data"], json!([]));
+}
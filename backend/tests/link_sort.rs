@@ -0,0 +1,141 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// Three links whose creation time, clicks, title and last click each order them differently
+async fn seed(pool: &PgPool) {
+    let owner = create_user(pool, "sorter", UserRole::User).await;
+    for (title, created_days_ago, clicks, clicked_minutes_ago) in [
+        ("Banana", 3, 5, Some(10)),
+        ("cherry", 2, 50, None),
+        ("apple", 1, 0, Some(60)),
+    ] {
+        let link_id = create_link(pool, owner.id, title).await;
+        sqlx::query(
+            "UPDATE links SET
+                created_at = NOW() - make_interval(days => $2),
+                click_count = $3,
+                last_clicked_at = NOW() - make_interval(mins => $4)
+             WHERE id = $1",
+        )
+        .bind(link_id)
+        .bind(created_days_ago)
+        .bind(clicks)
+        .bind(clicked_minutes_ago)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+}
+
+async fn titles_sorted_by(pool: &PgPool, sort: &str) -> Vec<String> {
+    let (app, _) = test_app(pool);
+    let uri = format!("/api/links?sort={sort}");
+    let (status, _, body) = send(&app, request(Method::GET, &uri, None, None)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|link| link["title"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn titles(expected: &[&str]) -> Vec<String> {
+    expected.iter().map(|title| title.to_string()).collect()
+}
+
+#[sqlx::test]
+async fn sorts_by_creation_time(pool: PgPool) {
+    seed(&pool).await;
+    assert_eq!(
+        titles_sorted_by(&pool, "created_asc").await,
+        titles(&["Banana", "cherry", "apple"])
+    );
+    assert_eq!(
+        titles_sorted_by(&pool, "created_desc").await,
+        titles(&["apple", "cherry", "Banana"])
+    );
+}
+
+#[sqlx::test]
+async fn newest_first_by_default(pool: PgPool) {
+    seed(&pool).await;
+    let (app, _) = test_app(&pool);
+    let (_, _, body) = send(&app, request(Method::GET, "/api/links", None, None)).await;
+    assert_eq!(body["data"][0]["title"], "apple");
+}
+
+#[sqlx::test]
+async fn sorts_by_clicks(pool: PgPool) {
+    seed(&pool).await;
+    assert_eq!(
+        titles_sorted_by(&pool, "clicks_desc").await,
+        titles(&["cherry", "Banana", "apple"])
+    );
+}
+
+#[sqlx::test]
+async fn sorts_by_title_ignoring_case(pool: PgPool) {
+    seed(&pool).await;
+    assert_eq!(
+        titles_sorted_by(&pool, "title_asc").await,
+        titles(&["apple", "Banana", "cherry"])
+    );
+}
+
+#[sqlx::test]
+async fn sorts_by_last_click_with_unclicked_last(pool: PgPool) {
+    seed(&pool).await;
+    assert_eq!(
+        titles_sorted_by(&pool, "recently_clicked").await,
+        titles(&["Banana", "apple", "cherry"])
+    );
+}
+
+#[sqlx::test]
+async fn unknown_sort_is_rejected(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let (status, _, body) = send(
+        &app,
+        request(Method::GET, "/api/links?sort=popular", None, None),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "INVALID_SORT");
+}
+
+#[sqlx::test]
+async fn sort_order_holds_across_pages(pool: PgPool) {
+    seed(&pool).await;
+    let (app, _) = test_app(&pool);
+
+    let (_, _, first) = send(
+        &app,
+        request(
+            Method::GET,
+            "/api/links?sort=clicks_desc&limit=2",
+            None,
+            None,
+        ),
+    )
+    .await;
+    let cursor = first["next_cursor"].as_str().unwrap().to_string();
+    let uri = format!("/api/links?sort=clicks_desc&limit=2&cursor={cursor}");
+    let (_, _, second) = send(&app, request(Method::GET, &uri, None, None)).await;
+
+    let titles: Vec<&Value> = first["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .chain(second["data"].as_array().unwrap())
+        .map(|link| &link["title"])
+        .collect();
+    assert_eq!(titles, ["cherry", "Banana", "apple"]);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn set_quota(pool: &PgPool, user_id: Uuid, quota: i32) {
+    sqlx::query("UPDATE users SET link_quota = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(quota)
+        .execute(pool)
+        .await
+        .expect("Failed to set link quota");
+}
+
+async fn owner_of(pool: &PgPool, link_id: Uuid) -> Uuid {
+    sqlx::query_scalar("SELECT user_id FROM links WHERE id = $1")
+        .bind(link_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read link owner")
+}
+
+#[sqlx::test]
+async fn transfer_to_a_user_at_their_quota_is_rejected(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let giver = create_user(&pool, "giver", UserRole::User).await;
+    let receiver = create_user(&pool, "receiver", UserRole::User).await;
+    create_link(&pool, receiver.id, "Already owned").await;
+    set_quota(&pool, receiver.id, 1).await;
+    let link_id = create_link(&pool, giver.id, "Gift").await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::POST,
+            &format!("/api/links/{link_id}/transfer"),
+            Some(&giver.token()),
+            Some(json!({ "new_owner_id": receiver.id })),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{body}");
+    assert_eq!(body["code"], "QUOTA_EXCEEDED");
+    assert_eq!(body["details"], json!({ "current": 1, "limit": 1 }));
+    assert_eq!(owner_of(&pool, link_id).await, giver.id);
+}
+
+#[sqlx::test]
+async fn transfer_within_the_new_owners_quota_succeeds(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let giver = create_user(&pool, "giver", UserRole::User).await;
+    let receiver = create_user(&pool, "receiver", UserRole::User).await;
+    set_quota(&pool, receiver.id, 1).await;
+    let link_id = create_link(&pool, giver.id, "Gift").await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::POST,
+            &format!("/api/links/{link_id}/transfer"),
+            Some(&giver.token()),
+            Some(json!({ "new_owner_id": receiver.id })),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(owner_of(&pool, link_id).await, receiver.id);
+}
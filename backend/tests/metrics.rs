@@ -0,0 +1,62 @@
+mod common;
+
+use axum::{
+    http::{header, Method, StatusCode},
+    middleware::from_fn,
+};
+use backend::{
+    metrics::{create_metrics_router, init_metrics},
+    middleware::metrics::track_metrics,
+    models::auth::UserRole,
+};
+use common::{create_link, create_user, request, send, test_app};
+use sqlx::PgPool;
+
+// The recorder is global to the process, so this file holds a single test
+#[sqlx::test]
+async fn metrics_endpoint_renders_request_metrics_for_prometheus(pool: PgPool) {
+    let handle = init_metrics();
+    let (app, _) = test_app(&pool);
+    let app = app
+        .merge(create_metrics_router(handle))
+        .layer(from_fn(track_metrics));
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let link_id = create_link(&pool, owner.id, "Measured").await;
+
+    let (status, _, _) = send(
+        &app,
+        request(Method::GET, &format!("/api/links/{link_id}"), None, None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, headers, body) = send(&app, request(Method::GET, "/metrics", None, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        headers[header::CONTENT_TYPE],
+        "text/plain; version=0.0.4; charset=utf-8"
+    );
+    let metrics = body.as_str().expect("Metrics are plain text");
+
+    assert!(
+        metrics.contains("# TYPE http_requests_total counter"),
+        "{metrics}"
+    );
+    // Requests are labelled by route pattern, not by the link ID in the path
+    assert!(
+        metrics
+            .contains(r#"http_requests_total{method="GET",path="/api/links/{id}",status="200"} 1"#),
+        "{metrics}"
+    );
+    assert!(!metrics.contains(&link_id.to_string()), "{metrics}");
+    assert!(
+        metrics.contains("# TYPE http_request_duration_seconds histogram"),
+        "{metrics}"
+    );
+    assert!(
+        metrics.contains(
+            r#"http_request_duration_seconds_bucket{method="GET",path="/api/links/{id}",status="200",le="0.005"}"#
+        ),
+        "{metrics}"
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use backend::{database::queries::requeue_running_preview_jobs, models::auth::UserRole};
+use chrono::{Duration, Utc};
+use common::{create_link, create_user};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn insert_running_job(pool: &PgPool, link_id: Uuid, started_ago: Duration) {
+    sqlx::query(
+        "INSERT INTO preview_jobs (link_id, status, attempts, started_at)
+         VALUES ($1, 'running', 1, $2)",
+    )
+    .bind(link_id)
+    .bind(Utc::now() - started_ago)
+    .execute(pool)
+    .await
+    .expect("Failed to insert preview job");
+}
+
+async fn job_status(pool: &PgPool, link_id: Uuid) -> String {
+    sqlx::query_scalar("SELECT status FROM preview_jobs WHERE link_id = $1")
+        .bind(link_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to read preview job")
+}
+
+#[sqlx::test]
+async fn only_jobs_past_their_lease_are_requeued(pool: PgPool) {
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let abandoned = create_link(&pool, owner.id, "Abandoned").await;
+    let in_progress = create_link(&pool, owner.id, "In progress").await;
+    insert_running_job(&pool, abandoned, Duration::minutes(30)).await;
+    insert_running_job(&pool, in_progress, Duration::seconds(5)).await;
+
+    let lease = Duration::minutes(5);
+    let requeued = requeue_running_preview_jobs(&pool, Utc::now() - lease)
+        .await
+        .unwrap();
+
+    assert_eq!(requeued, 1);
+    assert_eq!(job_status(&pool, abandoned).await, "pending");
+    assert_eq!(job_status(&pool, in_progress).await, "running");
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn describe(pool: &PgPool, link_id: Uuid, description: &str) {
+    sqlx::query("UPDATE links SET description = $2 WHERE id = $1")
+        .bind(link_id)
+        .bind(description)
+        .execute(pool)
+        .await
+        .expect("Failed to set description");
+}
+
+#[sqlx::test]
+async fn title_matches_outrank_description_only_matches(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let user = create_user(&pool, "searcher", UserRole::User).await;
+    let in_title = create_link(&pool, user.id, "Ferris the crab").await;
+    // Newer and mentioning the term more often, yet only in its description
+    let in_description = create_link(&pool, user.id, "Mascots of languages").await;
+    describe(
+        &pool,
+        in_description,
+        "Ferris is everywhere: Ferris plushies, Ferris stickers and Ferris shirts",
+    )
+    .await;
+    create_link(&pool, user.id, "Unrelated").await;
+
+    let (status, _, body) = send(
+        &app,
+        request(
+            Method::GET,
+            "/api/links/search?q=ferris",
+            Some(&user.token()),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let ranked: Vec<&str> = body["data"]
+        .as_array()
+        .expect("Expected a list of links")
+        .iter()
+        .map(|link| link["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ranked, [in_title.to_string(), in_description.to_string()]);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use backend::models::auth::UserRole;
+use common::{create_link, create_user, request, send, test_app};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn set_age_and_clicks(pool: &PgPool, link_id: Uuid, hours_old: i32, clicks: i64) {
+    sqlx::query(
+        "UPDATE links
+         SET created_at = NOW() - make_interval(hours => $2), click_count = $3
+         WHERE id = $1",
+    )
+    .bind(link_id)
+    .bind(hours_old)
+    .bind(clicks)
+    .execute(pool)
+    .await
+    .expect("Failed to age link");
+}
+
+#[sqlx::test]
+async fn newer_moderately_clicked_link_outranks_old_heavily_clicked_one(pool: PgPool) {
+    let (app, _) = test_app(&pool);
+    let owner = create_user(&pool, "owner", UserRole::User).await;
+    let old = create_link(&pool, owner.id, "Old favourite").await;
+    let new = create_link(&pool, owner.id, "Picking up").await;
+    let unclicked = create_link(&pool, owner.id, "Nobody cares").await;
+    set_age_and_clicks(&pool, old, 30 * 24, 1000).await;
+    set_age_and_clicks(&pool, new, 2, 20).await;
+    set_age_and_clicks(&pool, unclicked, 1, 0).await;
+
+    let (status, _, body) = send(
+        &app,
+        request(Method::GET, "/api/links/trending", None, None),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let ranked: Vec<&str> = body["data"]
+        .as_array()
+        .expect("Expected a list of links")
+        .iter()
+        .map(|link| link["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ranked, [new.to_string(), old.to_string()]);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use backend::{
+    models::auth::{Gender, RegisterRequest},
+    services::auth::AuthService,
+};
+use common::JWT_SECRET;
+use sqlx::{migrate::Migrator, PgPool};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// The migration that made emails and usernames case-insensitive
+const CASE_INSENSITIVE_IDENTITY: i64 = 20250726000009;
+
+async fn run_migrations(pool: &PgPool, applies: impl Fn(i64) -> bool) {
+    for migration in MIGRATOR.iter() {
+        if migration.migration_type.is_down_migration() || !applies(migration.version) {
+            continue;
+        }
+        sqlx::raw_sql(&migration.sql)
+            .execute(pool)
+            .await
+            .unwrap_or_else(|e| panic!("Migration {} failed: {e}", migration.version));
+    }
+}
+
+async fn insert_user(pool: &PgPool, email: &str, username: &str, verified: bool, age_days: i32) {
+    sqlx::query(
+        "INSERT INTO users (email, username, password_hash, gender, status, is_verified, verified_at, created_at)
+         VALUES ($1, $2, 'hash', 'other', 'active', $3,
+                 CASE WHEN $3 THEN NOW() - make_interval(days => $4) END,
+                 NOW() - make_interval(days => $4))",
+    )
+    .bind(email)
+    .bind(username)
+    .bind(verified)
+    .bind(age_days)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(migrations = false)]
+async fn migration_resolves_case_colliding_accounts(pool: PgPool) {
+    run_migrations(&pool, |version| version < CASE_INSENSITIVE_IDENTITY).await;
+
+    insert_user(&pool, "alice@example.com", "alice", true, 10).await;
+    insert_user(&pool, "Alice@Example.com", "ALICE", true, 5).await;
+    insert_user(&pool, "ALICE@example.com", "Alice", false, 1).await;
+    insert_user(&pool, "bob@example.com", "bob", true, 3).await;
+
+    run_migrations(&pool, |version| version >= CASE_INSENSITIVE_IDENTITY).await;
+
+    let users: Vec<(String, String, bool)> =
+        sqlx::query_as("SELECT email, username, is_verified FROM users ORDER BY created_at")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+    // The oldest account keeps its username and verified email
+    assert_eq!(
+        users[0],
+        ("alice@example.com".to_string(), "alice".to_string(), true)
+    );
+    // Newer ones are renamed and, if they had verified the same email, unverified
+    assert!(users[1].1.starts_with("ALICE_"));
+    assert!(!users[1].2);
+    assert_eq!(
+        users[2],
+        ("bob@example.com".to_string(), "bob".to_string(), true)
+    );
+    assert!(users[3].1.starts_with("Alice_"));
+    assert!(!users[3].2);
+    assert_eq!(users[1].0, "alice@example.com");
+    assert_eq!(users[3].0, "alice@example.com");
+}
+
+fn registration(email: &str, username: &str) -> RegisterRequest {
+    RegisterRequest {
+        email: email.to_string(),
+        username: username.to_string(),
+        password: "correct horse battery staple".to_string(),
+        gender: Gender::Other,
+    }
+}
+
+#[sqlx::test]
+async fn registration_ignores_case(pool: PgPool) {
+    let auth = AuthService::new(pool, JWT_SECRET.to_string());
+
+    let user = auth
+        .register(registration("Carol@Example.COM", "Carol"))
+        .await
+        .unwrap();
+    assert_eq!(user.email, "carol@example.com");
+    assert_eq!(user.username, "Carol");
+
+    assert!(auth
+        .check_user_exists("CAROL@example.com", "someone_else")
+        .await
+        .unwrap());
+    assert!(auth
+        .check_user_exists("other@example.com", "cAROL")
+        .await
+        .unwrap());
+
+    let error = auth
+        .register(registration("carol2@example.com", "CAROL"))
+        .await
+        .unwrap_err();
+    let constraint = error.as_database_error().and_then(|e| e.constraint());
+    assert_eq!(constraint, Some("idx_users_username_lower"));
+}
+
+#[sqlx::test]
+async fn login_ignores_email_case(pool: PgPool) {
+    let auth = AuthService::new(pool, JWT_SECRET.to_string());
+    auth.register(registration("Dave@Example.com", "dave"))
+        .await
+        .unwrap();
+    auth.complete_verification("dave@example.com")
+        .await
+        .unwrap();
+
+    let response = auth
+        .login("DAVE@EXAMPLE.COM", "correct horse battery staple")
+        .await
+        .unwrap();
+    assert_eq!(response.user.username, "dave");
+
+    assert!(auth
+        .login("dave@example.com", "wrong password")
+        .await
+        .is_err());
+}